@@ -0,0 +1,62 @@
+//! Reading presets from the zip archives preset packs are distributed in.
+
+use std::fs::File;
+use std::path::Path;
+
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::{BabylonError, Preset};
+
+/// File extension of Babylon presets.
+const PRESET_EXTENSION: &str = ".bab";
+
+fn zip_error(error: ZipError) -> BabylonError {
+    match error {
+        ZipError::Io(error) => BabylonError::Io(error),
+        error => BabylonError::Zip(error),
+    }
+}
+
+impl Preset {
+    /// Read a preset from a member of a zip archive without extracting it.
+    /// The member is the path of the preset within the archive.
+    pub fn read_from_zip<P: AsRef<Path>>(archive: P, member: &str) -> Result<Preset, BabylonError> {
+        let input = File::open(&archive)?;
+        let mut archive_reader = ZipArchive::new(input).map_err(zip_error)?;
+        let member_reader = archive_reader.by_name(member).map_err(zip_error)?;
+        let source = format!("{}:{}", archive.as_ref().to_string_lossy(), member);
+        Preset::read_named(member_reader, &source)
+    }
+
+    /// Paths of the presets in a zip archive, in the order they are stored.
+    /// Members are considered presets if they have the `.bab` extension.
+    pub fn list_zip_presets<P: AsRef<Path>>(archive: P) -> Result<Vec<String>, BabylonError> {
+        let input = File::open(archive)?;
+        let archive_reader = ZipArchive::new(input).map_err(zip_error)?;
+        Ok(archive_reader
+            .file_names()
+            .filter(|name| name.to_lowercase().ends_with(PRESET_EXTENSION))
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::Preset;
+
+    #[test]
+    fn read_from_zip() {
+        let path = Path::new("tests").join("archive").join("presets.zip");
+        let names = Preset::list_zip_presets(&path).unwrap();
+        assert_eq!(names, vec!["init-1.0.2.bab"]);
+
+        let preset = Preset::read_from_zip(&path, &names[0]).unwrap();
+        assert_eq!(preset.name, "init");
+
+        assert!(Preset::read_from_zip(&path, "missing.bab").is_err());
+    }
+}
@@ -0,0 +1,121 @@
+//! Building presets in code.
+
+use crate::{EffectType, ModSource, ModTarget, Oscillator, Preset};
+
+/// Builds a [`Preset`] one setting at a time, starting from
+/// [`Preset::default`], which sounds like the init preset of Babylon.
+///
+/// ```
+/// use synthahol_babylon::{EffectType, ModSource, ModTarget, Preset};
+///
+/// let preset = Preset::builder()
+///     .name("Wobble")
+///     .polyphony(1)
+///     .enable_effect(EffectType::Distortion)
+///     .add_matrix_route(ModSource::Lfo2, ModTarget::FilterCutoff, 0.5)
+///     .build();
+/// assert_eq!(preset.name, "Wobble");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PresetBuilder {
+    preset: Preset,
+}
+
+impl PresetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.preset.name = name.into();
+        self
+    }
+
+    /// Master volume in decibels. See [`Preset::set_master_volume_db`].
+    pub fn master_volume_db(mut self, db: f64) -> Self {
+        self.preset.set_master_volume_db(db);
+        self
+    }
+
+    pub fn polyphony(mut self, polyphony: u32) -> Self {
+        self.preset.polyphony = polyphony;
+        self
+    }
+
+    /// Replace an oscillator, where zero is the first oscillator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no oscillator at the index.
+    pub fn oscillator(mut self, index: usize, oscillator: Oscillator) -> Self {
+        self.preset.oscillators[index] = oscillator;
+        self
+    }
+
+    pub fn enable_effect(mut self, effect_type: EffectType) -> Self {
+        self.preset.set_effect_enabled(effect_type, true);
+        self
+    }
+
+    /// Route a modulation source to a target in the first unused slot of the
+    /// modulation matrix. The route is ignored if every slot is in use. See
+    /// [`Preset::add_matrix_route`].
+    pub fn add_matrix_route(mut self, source: ModSource, target: ModTarget, amount: f64) -> Self {
+        self.preset.add_matrix_route(source, target, amount);
+        self
+    }
+
+    pub fn build(self) -> Preset {
+        self.preset
+    }
+}
+
+impl Preset {
+    /// Start building a preset in code. See [`PresetBuilder`].
+    pub fn builder() -> PresetBuilder {
+        PresetBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        EffectType, ModSource, ModTarget, Oscillator, Preset, Waveform, MODULATION_MATRIX_SIZE,
+    };
+
+    #[test]
+    fn builder() {
+        let oscillator = Oscillator {
+            waveform: Waveform::Saw,
+            ..Preset::default().oscillators[0].clone()
+        };
+        let preset = Preset::builder()
+            .name("Built")
+            .master_volume_db(-6.0)
+            .polyphony(4)
+            .oscillator(1, oscillator.clone())
+            .enable_effect(EffectType::Reverb)
+            .add_matrix_route(ModSource::Lfo2, ModTarget::FilterResonance, 0.25)
+            .build();
+        assert_eq!(preset.name, "Built");
+        assert!((preset.master_volume_db() + 6.0).abs() < 1e-9);
+        assert_eq!(preset.polyphony, 4);
+        assert_eq!(preset.oscillators[1], oscillator);
+        assert!(preset.reverb.enabled);
+
+        // The first slot of the init preset is already in use.
+        assert_eq!(preset.matrix[0], Preset::default().matrix[0]);
+        assert_eq!(preset.matrix[1].source, ModSource::Lfo2);
+        assert_eq!(preset.matrix[1].target, ModTarget::FilterResonance);
+        assert_eq!(preset.matrix[1].amount, 0.25);
+
+        // Routes past the size of the matrix are ignored.
+        let mut builder = Preset::builder();
+        for _ in 0..10 {
+            builder = builder.add_matrix_route(ModSource::Velocity, ModTarget::Volume, 1.0);
+        }
+        let preset = builder.build();
+        assert_eq!(preset.matrix.len(), MODULATION_MATRIX_SIZE);
+        assert_eq!(preset.matrix[7].source, ModSource::Velocity);
+    }
+}
@@ -0,0 +1,402 @@
+//! Conversions between the values stored in Babylon presets and real units.
+//!
+//! Babylon stores nearly every control as a floating point value, usually
+//! normalized from 0.0 to 1.0. The curves here were determined by saving
+//! presets in Babylon and comparing the stored values to what the user
+//! interface displays. The calibration points are listed with each function
+//! and are checked by the tests in this module.
+//!
+//! Babylon doesn't display units for some controls, so their curves can't be
+//! determined that way. Those curves are provisional, which is stated in
+//! their documentation. They are plausible approximations that are useful
+//! for porting presets to other synthesizers, but they may change when they
+//! are confirmed.
+
+use crate::{DelayFilterMode, NoteDivision};
+
+/// Steepness of the curve used for the lower half of volume controls.
+const VOLUME_CURVE: f64 = 0.625;
+
+/// Loudest level of volume controls, in decibels.
+const VOLUME_MAX_DB: f64 = 10.0;
+
+/// Lowest frequency of the filter cutoff control, in hertz.
+const CUTOFF_MIN_HZ: f64 = 20.0;
+
+/// Highest frequency of the filter cutoff control, in hertz.
+const CUTOFF_MAX_HZ: f64 = 20_000.0;
+
+/// Q factor of the filters with the resonance control at 0.0, the Q of a
+/// Butterworth filter, which has no resonant peak.
+const RESONANCE_MIN_Q: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+/// Q factor of the filters with the resonance control at 1.0.
+const RESONANCE_MAX_Q: f64 = 10.0;
+
+/// Bit depth of the lo-fi effect with the bit rate control at 0.0.
+const BIT_DEPTH_MAX: f64 = 16.0;
+
+/// Bit depth of the lo-fi effect with the bit rate control at 1.0.
+const BIT_DEPTH_MIN: f64 = 4.0;
+
+/// Sample rate of the lo-fi effect with the sample rate control at 0.0, in
+/// hertz.
+const LOFI_SAMPLE_RATE_MAX_HZ: f64 = 44_100.0;
+
+/// Sample rate of the lo-fi effect with the sample rate control at 1.0, in
+/// hertz.
+const LOFI_SAMPLE_RATE_MIN_HZ: f64 = 1_000.0;
+
+/// Slowest rate of an LFO that is not synced to the tempo, in hertz.
+const LFO_MIN_HZ: f64 = 0.01;
+
+/// Fastest rate of an LFO that is not synced to the tempo, in hertz.
+const LFO_MAX_HZ: f64 = 20.0;
+
+/// Tempo synced divisions and the stored values that select them.
+///
+/// Babylon stores the position of the division in a list. Only the half note,
+/// sixteenth note and whole note triplet have been confirmed against presets
+/// saved by Babylon. The order of the other divisions and their values are
+/// guesses: the straight divisions continue the spacing of 0.051 between the
+/// half note and the sixteenth note, and the dotted and triplet divisions are
+/// spread evenly up to the whole note triplet. Every division must be listed.
+const SYNC_DIVISIONS: [(f64, NoteDivision); 18] = [
+    (0.206, NoteDivision::Whole), // Guess
+    (0.257, NoteDivision::Half),
+    (0.308, NoteDivision::Quarter), // Guess
+    (0.359, NoteDivision::Eighth),  // Guess
+    (0.410, NoteDivision::Sixteenth),
+    (0.461, NoteDivision::ThirtySecond),        // Guess
+    (0.506, NoteDivision::WholeDotted),         // Guess
+    (0.551, NoteDivision::HalfDotted),          // Guess
+    (0.596, NoteDivision::QuarterDotted),       // Guess
+    (0.640, NoteDivision::EighthDotted),        // Guess
+    (0.685, NoteDivision::SixteenthDotted),     // Guess
+    (0.730, NoteDivision::ThirtySecondDotted),  // Guess
+    (0.775, NoteDivision::ThirtySecondTriplet), // Guess
+    (0.820, NoteDivision::SixteenthTriplet),    // Guess
+    (0.865, NoteDivision::EighthTriplet),       // Guess
+    (0.910, NoteDivision::QuarterTriplet),      // Guess
+    (0.955, NoteDivision::HalfTriplet),         // Guess
+    (1.000, NoteDivision::WholeTriplet),
+];
+
+/// Convert a normalized volume or gain control to decibels.
+///
+/// The upper half of the range is linear in decibels from 0 dB at 0.5 to
+/// +10 dB at 1.0. The lower half follows an exponential taper in amplitude
+/// down to -inf dB at 0.0. Used by the master volume and the equalizer.
+///
+/// | Normalized | Decibels |
+/// |-----------:|---------:|
+/// |      0.000 |     -inf |
+/// |      0.007 |    -39.8 |
+/// |      0.194 |    -10.0 |
+/// |      0.200 |     -9.7 |
+/// |      0.500 |      0.0 |
+/// |      1.000 |    +10.0 |
+pub fn normalized_to_db(normalized: f64) -> f64 {
+    let normalized = normalized.clamp(0.0, 1.0);
+    if normalized <= 0.0 {
+        f64::NEG_INFINITY
+    } else if normalized < 0.5 {
+        let amplitude =
+            ((VOLUME_CURVE * normalized * 2.0).exp() - 1.0) / (VOLUME_CURVE.exp() - 1.0);
+        20.0 * amplitude.log10()
+    } else {
+        (normalized - 0.5) * 2.0 * VOLUME_MAX_DB
+    }
+}
+
+/// Convert decibels to a normalized volume or gain control, the inverse of
+/// [`normalized_to_db`]. Values louder than +10 dB are clamped.
+pub fn db_to_normalized(db: f64) -> f64 {
+    if db.is_nan() || db == f64::NEG_INFINITY {
+        0.0
+    } else if db < 0.0 {
+        let amplitude = 10.0_f64.powf(db / 20.0);
+        (amplitude * (VOLUME_CURVE.exp() - 1.0)).ln_1p() / VOLUME_CURVE / 2.0
+    } else {
+        (0.5 + db / VOLUME_MAX_DB / 2.0).min(1.0)
+    }
+}
+
+/// Convert a normalized filter cutoff control to hertz.
+///
+/// The cutoff is exponential from 20 Hz at 0.0 to 20 kHz at 1.0, which is
+/// fully open. Only the fully open position has been confirmed.
+pub fn control_to_cutoff_hz(control: f64) -> f64 {
+    CUTOFF_MIN_HZ * (CUTOFF_MAX_HZ / CUTOFF_MIN_HZ).powf(control.clamp(0.0, 1.0))
+}
+
+/// Convert a filter cutoff in hertz to a normalized control, the inverse of
+/// [`control_to_cutoff_hz`].
+pub fn cutoff_hz_to_control(hz: f64) -> f64 {
+    let hz = hz.clamp(CUTOFF_MIN_HZ, CUTOFF_MAX_HZ);
+    (hz / CUTOFF_MIN_HZ).ln() / (CUTOFF_MAX_HZ / CUTOFF_MIN_HZ).ln()
+}
+
+/// Convert a normalized filter resonance control to the Q factor of the
+/// filter.
+///
+/// This curve is provisional. Babylon doesn't display the Q, so it is
+/// assumed to be exponential from 0.707 at 0.0, which has no resonant peak,
+/// to 10 at 1.0.
+pub fn resonance_to_q(resonance: f64) -> f64 {
+    RESONANCE_MIN_Q * (RESONANCE_MAX_Q / RESONANCE_MIN_Q).powf(resonance.clamp(0.0, 1.0))
+}
+
+/// Convert the Q factor of a filter to a normalized resonance control, the
+/// inverse of [`resonance_to_q`].
+pub fn q_to_resonance(q: f64) -> f64 {
+    let q = q.clamp(RESONANCE_MIN_Q, RESONANCE_MAX_Q);
+    (q / RESONANCE_MIN_Q).ln() / (RESONANCE_MAX_Q / RESONANCE_MIN_Q).ln()
+}
+
+/// Convert the bit rate reduction control of the lo-fi effect to the bit
+/// depth it reduces to.
+///
+/// This curve is provisional. Babylon displays the control from 0 to 10
+/// without units, so the bit depth is assumed to fall linearly from 16 bits
+/// at 0.0 to 4 bits at 1.0.
+pub fn control_to_bit_depth(control: f64) -> f64 {
+    BIT_DEPTH_MAX - (BIT_DEPTH_MAX - BIT_DEPTH_MIN) * control.clamp(0.0, 1.0)
+}
+
+/// Convert a bit depth to the bit rate reduction control of the lo-fi
+/// effect, the inverse of [`control_to_bit_depth`].
+pub fn bit_depth_to_control(bits: f64) -> f64 {
+    let bits = bits.clamp(BIT_DEPTH_MIN, BIT_DEPTH_MAX);
+    (BIT_DEPTH_MAX - bits) / (BIT_DEPTH_MAX - BIT_DEPTH_MIN)
+}
+
+/// Convert the sample rate reduction control of the lo-fi effect to the
+/// sample rate it reduces to, in hertz.
+///
+/// This curve is provisional. Babylon displays the control from 0 to 10
+/// without units, so the sample rate is assumed to fall exponentially from
+/// 44.1 kHz at 0.0 to 1 kHz at 1.0.
+pub fn control_to_sample_rate_hz(control: f64) -> f64 {
+    LOFI_SAMPLE_RATE_MAX_HZ
+        * (LOFI_SAMPLE_RATE_MIN_HZ / LOFI_SAMPLE_RATE_MAX_HZ).powf(control.clamp(0.0, 1.0))
+}
+
+/// Convert a sample rate in hertz to the sample rate reduction control of
+/// the lo-fi effect, the inverse of [`control_to_sample_rate_hz`].
+pub fn sample_rate_hz_to_control(hz: f64) -> f64 {
+    let hz = hz.clamp(LOFI_SAMPLE_RATE_MIN_HZ, LOFI_SAMPLE_RATE_MAX_HZ);
+    (hz / LOFI_SAMPLE_RATE_MAX_HZ).ln() / (LOFI_SAMPLE_RATE_MIN_HZ / LOFI_SAMPLE_RATE_MAX_HZ).ln()
+}
+
+/// Convert the rate of an LFO that is not synced to the tempo to hertz.
+///
+/// This curve is provisional. The rate is assumed to be exponential from
+/// 0.01 Hz at 0.0 to 20 Hz at 1.0, like the filter cutoff, and has not been
+/// confirmed against presets saved by Babylon. LFOs that are synced to the tempo use
+/// [`sync_value_to_division`] instead.
+pub fn control_to_lfo_hz(control: f64) -> f64 {
+    LFO_MIN_HZ * (LFO_MAX_HZ / LFO_MIN_HZ).powf(control.clamp(0.0, 1.0))
+}
+
+/// Convert the rate of an LFO in hertz to a normalized control, the inverse
+/// of [`control_to_lfo_hz`].
+pub fn lfo_hz_to_control(hz: f64) -> f64 {
+    let hz = hz.clamp(LFO_MIN_HZ, LFO_MAX_HZ);
+    (hz / LFO_MIN_HZ).ln() / (LFO_MAX_HZ / LFO_MIN_HZ).ln()
+}
+
+/// Convert a delay time that is not synced to the tempo to milliseconds.
+/// Babylon stores these times in seconds, so 0.504 is 504 ms.
+pub fn control_to_ms(control: f64) -> f64 {
+    control * 1000.0
+}
+
+/// Convert milliseconds to a delay time that is not synced to the tempo, the
+/// inverse of [`control_to_ms`].
+pub fn ms_to_control(ms: f64) -> f64 {
+    ms / 1000.0
+}
+
+/// Convert a normalized control to the percentage Babylon displays.
+///
+/// Stereo width controls follow this convention, with 0% being mono and 100%
+/// being the full width of the stereo field. Babylon does not widen beyond
+/// the stereo field, so the width is never more than 100%.
+pub fn normalized_to_percent(normalized: f64) -> f64 {
+    normalized * 100.0
+}
+
+/// Convert a tempo synced time to the nearest note division.
+///
+/// | Stored | Division |
+/// |-------:|---------:|
+/// |  0.257 |      1/2 |
+/// |  0.410 |     1/16 |
+/// |  1.000 |     1/1T |
+pub fn sync_value_to_division(value: f64) -> NoteDivision {
+    let mut nearest = SYNC_DIVISIONS[0];
+    for entry in SYNC_DIVISIONS {
+        if (entry.0 - value).abs() < (nearest.0 - value).abs() {
+            nearest = entry;
+        }
+    }
+    nearest.1
+}
+
+/// Convert a note division to the tempo synced value that selects it, the
+/// inverse of [`sync_value_to_division`].
+pub fn division_to_sync_value(division: NoteDivision) -> f64 {
+    SYNC_DIVISIONS
+        .iter()
+        .find(|(_, d)| *d == division)
+        .map(|(value, _)| *value)
+        .expect("every note division is in SYNC_DIVISIONS")
+}
+
+/// Convert the stored value of the filter built into the delay and reverb to
+/// the filter mode. The stored value is the position of the mode in the
+/// list shown by Babylon, so 0.583 is a 400 Hz high pass.
+pub fn control_to_delay_filter_mode(control: f64) -> DelayFilterMode {
+    DelayFilterMode::from_or((control * 1000.0).round() as u32, DelayFilterMode::Off)
+}
+
+/// Convert the mode of the filter built into the delay and reverb to the
+/// stored value, the inverse of [`control_to_delay_filter_mode`].
+pub fn delay_filter_mode_to_control(mode: DelayFilterMode) -> f64 {
+    mode as u32 as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use approx::assert_relative_eq;
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn bit_depth() {
+        assert_eq!(control_to_bit_depth(0.0), 16.0);
+        assert_eq!(control_to_bit_depth(1.0), 4.0);
+        assert_eq!(bit_depth_to_control(1.0), 1.0);
+        for control in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let bits = control_to_bit_depth(control);
+            assert_relative_eq!(bit_depth_to_control(bits), control, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn cutoff() {
+        assert_relative_eq!(control_to_cutoff_hz(0.0), 20.0, epsilon = 0.0001);
+        assert_relative_eq!(control_to_cutoff_hz(1.0), 20_000.0, epsilon = 0.0001);
+        for control in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let hz = control_to_cutoff_hz(control);
+            assert_relative_eq!(cutoff_hz_to_control(hz), control, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn db() {
+        assert_eq!(normalized_to_db(0.0), f64::NEG_INFINITY);
+        assert_relative_eq!(normalized_to_db(0.007), -39.8, epsilon = 0.1);
+        assert_relative_eq!(normalized_to_db(0.194), -10.0, epsilon = 0.1);
+        assert_relative_eq!(normalized_to_db(0.2), -9.7, epsilon = 0.1);
+        assert_relative_eq!(normalized_to_db(0.5), 0.0, epsilon = 0.0001);
+        assert_relative_eq!(normalized_to_db(1.0), 10.0, epsilon = 0.0001);
+
+        assert_eq!(db_to_normalized(f64::NEG_INFINITY), 0.0);
+        assert_eq!(db_to_normalized(20.0), 1.0);
+        for normalized in [0.007, 0.194, 0.2, 0.5, 0.75, 1.0] {
+            let db = normalized_to_db(normalized);
+            assert_relative_eq!(db_to_normalized(db), normalized, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn delay_filter_mode() {
+        assert_eq!(control_to_delay_filter_mode(0.0), DelayFilterMode::Off);
+        assert_eq!(
+            control_to_delay_filter_mode(0.583),
+            DelayFilterMode::HighPass400
+        );
+        assert_eq!(
+            control_to_delay_filter_mode(1.0),
+            DelayFilterMode::BandPass150
+        );
+        for mode in DelayFilterMode::iter() {
+            let control = delay_filter_mode_to_control(mode);
+            assert_eq!(control_to_delay_filter_mode(control), mode);
+        }
+    }
+
+    #[test]
+    fn lfo_hz() {
+        assert_relative_eq!(control_to_lfo_hz(0.0), 0.01, epsilon = 0.0001);
+        assert_relative_eq!(control_to_lfo_hz(1.0), 20.0, epsilon = 0.0001);
+        for control in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let hz = control_to_lfo_hz(control);
+            assert_relative_eq!(lfo_hz_to_control(hz), control, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn ms() {
+        assert_relative_eq!(control_to_ms(0.504), 504.0, epsilon = 0.0001);
+        assert_relative_eq!(ms_to_control(504.0), 0.504, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn percent() {
+        assert_relative_eq!(normalized_to_percent(0.0), 0.0);
+        assert_relative_eq!(normalized_to_percent(0.8), 80.0);
+        assert_relative_eq!(normalized_to_percent(1.0), 100.0);
+    }
+
+    #[test]
+    fn q() {
+        assert_relative_eq!(resonance_to_q(0.0), FRAC_1_SQRT_2, epsilon = 0.0001);
+        assert_relative_eq!(resonance_to_q(1.0), 10.0, epsilon = 0.0001);
+        assert_eq!(q_to_resonance(100.0), 1.0);
+        for resonance in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let q = resonance_to_q(resonance);
+            assert_relative_eq!(q_to_resonance(q), resonance, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn sample_rate_hz() {
+        assert_relative_eq!(control_to_sample_rate_hz(0.0), 44_100.0, epsilon = 0.0001);
+        assert_relative_eq!(control_to_sample_rate_hz(1.0), 1_000.0, epsilon = 0.0001);
+        assert_eq!(sample_rate_hz_to_control(100.0), 1.0);
+        for control in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let hz = control_to_sample_rate_hz(control);
+            assert_relative_eq!(sample_rate_hz_to_control(hz), control, epsilon = 0.0001);
+        }
+    }
+
+    /// Every division is listed once, in order of the stored values.
+    #[test]
+    fn sync_divisions() {
+        for division in NoteDivision::iter() {
+            let count = SYNC_DIVISIONS
+                .iter()
+                .filter(|(_, d)| *d == division)
+                .count();
+            assert_eq!(count, 1, "{:?}", division);
+        }
+        assert!(SYNC_DIVISIONS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn sync_division() {
+        assert_eq!(sync_value_to_division(0.257), NoteDivision::Half);
+        assert_eq!(sync_value_to_division(0.410), NoteDivision::Sixteenth);
+        assert_eq!(sync_value_to_division(1.0), NoteDivision::WholeTriplet);
+        for division in NoteDivision::iter() {
+            let value = division_to_sync_value(division);
+            assert_eq!(sync_value_to_division(value), division);
+        }
+    }
+}
@@ -0,0 +1,493 @@
+use std::collections::HashMap;
+
+use crate::visit::TUNING_NOTES;
+use crate::{ParamVisitor, Preset};
+
+/// Differences in floating point values smaller than this, relative to the
+/// larger of the values, are ignored. Babylon stores single precision values
+/// so the last few decimal places are noise.
+const DIFF_TOLERANCE: f64 = 1e-6;
+
+/// A parameter that is different in two presets. See [`Preset::diff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParamDiff {
+    /// Path of the field of the [`Preset`] that holds the parameter, such as
+    /// `oscillators[0].volume`. Parameters that are not known are named by
+    /// their ID.
+    pub name: String,
+
+    /// ID of the parameter in the preset file, such as `OSCVol_1`. These are
+    /// the names used by [`ParamVisitor`] and [`Preset::set_raw_param`].
+    pub id: String,
+
+    /// Value in the preset being compared, or empty if the other preset has
+    /// a parameter this one doesn't, such as an extra modulation matrix
+    /// route.
+    pub left: String,
+
+    /// Value in the other preset, or empty if it doesn't have the parameter.
+    pub right: String,
+}
+
+impl ParamDiff {
+    fn new(id: String, left: Option<&Value>, right: Option<&Value>) -> Self {
+        let display =
+            |value: Option<&Value>| value.map(Value::to_display_string).unwrap_or_default();
+        ParamDiff {
+            name: field_path(&id).unwrap_or_else(|| id.clone()),
+            id,
+            left: display(left),
+            right: display(right),
+        }
+    }
+}
+
+/// Field of an [`Envelope`](crate::Envelope) from the part of a parameter
+/// ID after the prefix of the envelope, such as `EnvAttack`.
+fn envelope_field(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "EnvAttack" => "attack",
+        "AttCurveType" => "attack_curve",
+        "EnvDecay" => "decay",
+        "DecCurveType" => "decay_falloff",
+        "EnvSustain" => "sustain",
+        "EnvRelease" => "release",
+        "RelCurveType" => "release_falloff",
+        _ => return None,
+    })
+}
+
+/// Path of the field of an oscillator, LFO, modulation envelope or matrix
+/// route from the ID of its parameter without the number of the item.
+fn indexed_field_path(name: &str, index: usize) -> Option<String> {
+    if let Some(field) = name.strip_prefix("Mod").and_then(envelope_field) {
+        return Some(format!("mod_envelopes[{}].envelope.{}", index, field));
+    }
+    let (list, field) = match name {
+        "OSCSwitch" => ("oscillators", "enabled"),
+        "OSCWaveType" => ("oscillators", "waveform"),
+        "OSCInvert" => ("oscillators", "invert"),
+        "OSCPan" => ("oscillators", "pan"),
+        "OSCPhase" => ("oscillators", "phase"),
+        "OSCPitch" => ("oscillators", "pitch"),
+        "OSCFine" => ("oscillators", "fine_tuning"),
+        "OSCSemi" => ("oscillators", "semitone_tuning"),
+        "OSCOctave" => ("oscillators", "octave_tuning"),
+        "OSCReverse" => ("oscillators", "reverse"),
+        "OSCFreeRun" => ("oscillators", "free_run"),
+        "OSCSyncAll" => ("oscillators", "sync_all"),
+        "OSCVol" => ("oscillators", "volume"),
+        "OSCNumVoice" => ("oscillators", "unison.voices"),
+        "OSCDetune" => ("oscillators", "unison.detune"),
+        "OSCSpread" => ("oscillators", "unison.spread"),
+        "OSCUniMix" => ("oscillators", "unison.mix"),
+        "OSCAMSwitch" => ("oscillators", "am_enabled"),
+        "OSCAM" => ("oscillators", "am_amount"),
+        "OSCFMSwitch" => ("oscillators", "fm_enabled"),
+        "OSCFM" => ("oscillators", "fm_amount"),
+        "OSCRMSwitch" => ("oscillators", "rm_enabled"),
+        "OSCRM" => ("oscillators", "rm_amount"),
+        "LFOSwitch" => ("lfos", "enabled"),
+        "LFOWaveType" => ("lfos", "waveform"),
+        "LFOSync" => ("lfos", "sync"),
+        "LFOInvert" => ("lfos", "invert"),
+        "LFOReverse" => ("lfos", "reverse"),
+        "LFOMono" => ("lfos", "mono"),
+        "LFOFreeRun" => ("lfos", "free_run"),
+        "LFOFreq" => ("lfos", "frequency"),
+        "LFOPhase" => ("lfos", "phase"),
+        "ModEnvSwitch" => ("mod_envelopes", "enabled"),
+        "ModEnvCurveType" => ("mod_envelopes", "curve"),
+        "MatrixSource" => ("matrix", "source"),
+        "MatrixTarget" => ("matrix", "target"),
+        "MatrixAmount" => ("matrix", "amount"),
+        _ => return None,
+    };
+    Some(format!("{}[{}].{}", list, index, field))
+}
+
+/// Path of the field of a [`Preset`] that holds a parameter, such as
+/// `oscillators[0].volume` for `OSCVol_1`, or `None` if the parameter is not
+/// known.
+fn field_path(id: &str) -> Option<String> {
+    // Items in a list end with their number, starting from one.
+    if let Some((name, number)) = id.rsplit_once('_') {
+        if let Some(index) = number.parse::<usize>().ok().and_then(|n| n.checked_sub(1)) {
+            return indexed_field_path(name, index);
+        }
+    }
+    if let Some(field) = envelope_field(id) {
+        return Some(format!("envelope.{}", field));
+    }
+    if let Some(field) = id.strip_prefix("Filter").and_then(envelope_field) {
+        return Some(format!("filter.envelope.{}", field));
+    }
+    if let Some(note) = id.strip_prefix("Tune") {
+        let index = TUNING_NOTES.iter().position(|n| *n == note)?;
+        return Some(format!("tuning.tunings[{}]", index));
+    }
+    let field = match id {
+        "PresetName" => "name",
+        "PresetInfo" => "description",
+        "FX_Order" => "effect_order",
+        "Transpose" => "tuning.transpose",
+        "FilterSwitch" => "filter.enabled",
+        "FilterType" => "filter.mode",
+        "FilterRes" => "filter.resonance",
+        "FilterCut" => "filter.cutoff_frequency",
+        "FilterKey" => "filter.key_tracking",
+        "FilterEnv" => "filter.envelope_amount",
+        "FilterDriveSwitch" => "filter.effect_enabled",
+        "FilterDriveType" => "filter.effect_mode",
+        "FilterDrive" => "filter.effect_amount",
+        "OSCSwitch_N" => "noise.enabled",
+        "OSCWidth_N" => "noise.width",
+        "OSCPan_N" => "noise.pan",
+        "OSCVol_N" => "noise.volume",
+        "OSCColor_N" => "noise.color",
+        "VibSwitch" => "vibrato.enabled",
+        "VibAttack" => "vibrato.attack",
+        "VibFrequency" => "vibrato.frequency",
+        "VibDelay" => "vibrato.delay",
+        "ChorusSwitch" => "chorus.enabled",
+        "ChorusDepth" => "chorus.depth",
+        "ChorusMix" => "chorus.mix",
+        "ChorusPdelay" => "chorus.pre_delay",
+        "ChorusRatio" => "chorus.ratio",
+        "DelayLP" => "delay.filter_mode",
+        "DelaySwitch" => "delay.enabled",
+        "DelayMode" => "delay.ping_pong",
+        "DelayFeed" => "delay.feedback",
+        "DelaySync" => "delay.sync",
+        "DelayTime" => "delay.time",
+        "DelayMix" => "delay.mix",
+        "DistSwitch" => "distortion.enabled",
+        "DistGain" => "distortion.gain",
+        "EQSwitch" => "equalizer.enabled",
+        "EQHigh" => "equalizer.high_gain",
+        "EQLow" => "equalizer.low_gain",
+        "EQMid" => "equalizer.mid_gain",
+        "FXFilterSwitch" => "effect_filter.enabled",
+        "FXFilterType" => "effect_filter.mode",
+        "FXFilterRes" => "effect_filter.resonance",
+        "FXFilterCut" => "effect_filter.cutoff_frequency",
+        "LoFiSwitch" => "lofi.enabled",
+        "LoFiBitRate" => "lofi.bitrate",
+        "LoFiSampleRate" => "lofi.sample_rate",
+        "LoFiMix" => "lofi.mix",
+        "ReverbSwitch" => "reverb.enabled",
+        "ReverbDamp" => "reverb.dampen",
+        "ReverbRoom" => "reverb.room",
+        "ReverbLP" => "reverb.filter",
+        "ReverbWidth" => "reverb.width",
+        "ReverbMix" => "reverb.mix",
+        "MainVol" => "master_volume_normalized",
+        "MaxVoices" => "polyphony",
+        "PortaMode" => "portamento_mode",
+        "MidiPlayMode" => "midi_play_mode",
+        "Glide" => "glide",
+        "VeloCurve" => "velocity_curve",
+        "KeyTrackCurve" => "key_track_curve",
+        "PBRange" => "pitch_bend_range",
+        "LimitSwitch" => "limit_enabled",
+        "EnvCurveType" => "envelope_curve",
+        "FilterEnvCurveType" => "filter_envelope_curve",
+        "OSCSync21" => "hard_sync",
+        _ => return None,
+    };
+    Some(field.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Float(f64),
+    Text(String),
+}
+
+impl Value {
+    fn is_close(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => {
+                a == b || (a - b).abs() <= DIFF_TOLERANCE * a.abs().max(b.abs()).max(1.0)
+            }
+            _ => self == other,
+        }
+    }
+
+    fn is_within(&self, other: &Value, epsilon: f64) -> bool {
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => a == b || (a - b).abs() <= epsilon,
+            _ => self == other,
+        }
+    }
+
+    fn to_display_string(&self) -> String {
+        match self {
+            Value::Float(value) => value.to_string(),
+            Value::Text(text) => text.clone(),
+        }
+    }
+}
+
+/// Collects the value of every parameter of a preset.
+#[derive(Default)]
+struct ValueCollector {
+    values: Vec<(String, Value)>,
+}
+
+impl ParamVisitor for ValueCollector {
+    fn visit_float(&mut self, id: &str, value: f64, _min: f64, _max: f64) {
+        self.values.push((id.to_string(), Value::Float(value)));
+    }
+
+    fn visit_int(&mut self, id: &str, value: i64, _min: i64, _max: i64) {
+        self.values
+            .push((id.to_string(), Value::Text(value.to_string())));
+    }
+
+    fn visit_enum(&mut self, id: &str, variant: &str, _all_variants: &[&str]) {
+        self.values
+            .push((id.to_string(), Value::Text(variant.to_string())));
+    }
+
+    fn visit_bool(&mut self, id: &str, value: bool) {
+        self.values
+            .push((id.to_string(), Value::Text(value.to_string())));
+    }
+}
+
+impl Preset {
+    /// The parameters that are different in the other preset, in the order
+    /// they are read. The name, description and effect order are included
+    /// using the names of their attributes in the preset file. Floating
+    /// point values that differ only by rounding noise are considered equal.
+    ///
+    /// Parameters are paired by ID, so a parameter that only one of the
+    /// presets has, such as when one has fewer modulation matrix routes, is
+    /// a difference with an empty value on the other side. Parameters only
+    /// the other preset has are listed last.
+    pub fn diff(&self, other: &Preset) -> Vec<ParamDiff> {
+        let left = self.diff_values();
+        let right = other.diff_values();
+        let left_ids: HashMap<&str, &Value> = left
+            .iter()
+            .map(|(id, value)| (id.as_str(), value))
+            .collect();
+        let right_ids: HashMap<&str, &Value> = right
+            .iter()
+            .map(|(id, value)| (id.as_str(), value))
+            .collect();
+
+        let mut diffs = Vec::new();
+        for (id, left) in &left {
+            let right = right_ids.get(id.as_str()).copied();
+            if right.is_none_or(|right| !left.is_close(right)) {
+                diffs.push(ParamDiff::new(id.clone(), Some(left), right));
+            }
+        }
+        for (id, right) in &right {
+            if !left_ids.contains_key(id.as_str()) {
+                diffs.push(ParamDiff::new(id.clone(), None, Some(right)));
+            }
+        }
+        diffs
+    }
+
+    /// Whether the other preset is the same, allowing floating point values
+    /// to differ by up to `epsilon` in the units of the model, such as
+    /// milliseconds for times. Every other value, including the choices,
+    /// switches, names, root key, scale and unknown parameters, must be
+    /// exactly equal.
+    pub fn approx_eq(&self, other: &Preset, epsilon: f64) -> bool {
+        let left = self.diff_values();
+        let right = other.diff_values();
+        left.len() == right.len()
+            && left
+                .iter()
+                .zip(&right)
+                .all(|((left_name, left), (right_name, right))| {
+                    left_name == right_name && left.is_within(right, epsilon)
+                })
+            && self.metadata == other.metadata
+            && self.pch == other.pch
+            && self.tuning.root_key == other.tuning.root_key
+            && self.tuning.scale == other.tuning.scale
+            && self.extra_effect_order == other.extra_effect_order
+            && self.unknown_params == other.unknown_params
+            && self.unknown_attributes == other.unknown_attributes
+    }
+
+    fn diff_values(&self) -> Vec<(String, Value)> {
+        let effect_order = self
+            .effect_order
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut collector = ValueCollector::default();
+        collector.values.extend([
+            ("PresetName".to_string(), Value::Text(self.name.clone())),
+            (
+                "PresetInfo".to_string(),
+                Value::Text(self.description.clone().unwrap_or_default()),
+            ),
+            ("FX_Order".to_string(), Value::Text(effect_order)),
+        ]);
+        self.visit_params(&mut collector);
+        collector.values
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use uom::si::f64::Time;
+    use uom::si::time::millisecond;
+
+    use super::field_path;
+    use crate::{EffectType, ParamDiff, ParamVisitor, Preset};
+
+    fn param_diff(name: &str, id: &str, left: &str, right: &str) -> ParamDiff {
+        ParamDiff {
+            name: name.to_string(),
+            id: id.to_string(),
+            left: left.to_string(),
+            right: right.to_string(),
+        }
+    }
+
+    #[test]
+    fn diff() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let preset = Preset::read_file(path).unwrap();
+        assert!(preset.diff(&preset.clone()).is_empty());
+
+        let mut other = preset.clone();
+        other.name = "Changed".to_string();
+        other.oscillators[0].volume = 0.25;
+        other.filter.enabled = true;
+        other.move_effect(EffectType::Reverb, 0);
+        other.envelope.decay += other.envelope.decay * 1e-9;
+        let diffs = preset.diff(&other);
+        assert_eq!(
+            diffs,
+            vec![
+                param_diff("name", "PresetName", "init-1.0.4", "Changed"),
+                param_diff(
+                    "effect_order",
+                    "FX_Order",
+                    "Distortion, Lo-Fi, Filter, Chorus, Equalizer, Delay, Reverb",
+                    "Reverb, Distortion, Lo-Fi, Filter, Chorus, Equalizer, Delay"
+                ),
+                param_diff("filter.enabled", "FilterSwitch", "false", "true"),
+                param_diff("oscillators[0].volume", "OSCVol_1", "0.5", "0.25"),
+            ]
+        );
+    }
+
+    /// Parameters only one of the presets has are differences, and the
+    /// parameters after them are still compared with the same parameter.
+    #[test]
+    fn diff_missing() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let mut other = preset.clone();
+        other.matrix.pop();
+        other.chorus.mix = 0.75;
+        let diffs = preset.diff(&other);
+        assert_eq!(
+            diffs,
+            vec![
+                param_diff("matrix[7].source", "MatrixSource_8", "Off", ""),
+                param_diff("matrix[7].target", "MatrixTarget_8", "Off", ""),
+                param_diff("matrix[7].amount", "MatrixAmount_8", "0", ""),
+                param_diff("chorus.mix", "ChorusMix", "0.5", "0.75"),
+            ]
+        );
+
+        let diffs = other.diff(&preset);
+        assert_eq!(
+            diffs[0],
+            param_diff("chorus.mix", "ChorusMix", "0.75", "0.5")
+        );
+        assert_eq!(
+            diffs[1],
+            param_diff("matrix[7].source", "MatrixSource_8", "", "Off")
+        );
+    }
+
+    /// Every parameter is named by the path of its field.
+    #[test]
+    fn field_paths() {
+        struct PathChecker;
+
+        impl PathChecker {
+            fn check(&self, id: &str) {
+                assert!(field_path(id).is_some(), "No field path for {}", id);
+            }
+        }
+
+        impl ParamVisitor for PathChecker {
+            fn visit_float(&mut self, id: &str, _value: f64, _min: f64, _max: f64) {
+                self.check(id);
+            }
+
+            fn visit_int(&mut self, id: &str, _value: i64, _min: i64, _max: i64) {
+                self.check(id);
+            }
+
+            fn visit_enum(&mut self, id: &str, _variant: &str, _all_variants: &[&str]) {
+                self.check(id);
+            }
+
+            fn visit_bool(&mut self, id: &str, _value: bool) {
+                self.check(id);
+            }
+        }
+
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        Preset::read_file(path)
+            .unwrap()
+            .visit_params(&mut PathChecker);
+
+        assert_eq!(
+            field_path("ModEnvAttack_2").unwrap(),
+            "mod_envelopes[1].envelope.attack"
+        );
+        assert_eq!(
+            field_path("FilterEnvDecay").unwrap(),
+            "filter.envelope.decay"
+        );
+        assert_eq!(field_path("TuneCSharp").unwrap(), "tuning.tunings[4]");
+        assert_eq!(field_path("OSCVol_N").unwrap(), "noise.volume");
+        assert!(field_path("Unknown").is_none());
+        assert!(field_path("OSCVol_0").is_none());
+    }
+
+    #[test]
+    fn approx_eq() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let preset = Preset::read_file(path).unwrap();
+        assert!(preset.approx_eq(&preset.clone(), 0.0));
+
+        let mut other = preset.clone();
+        other.oscillators[0].volume += 1e-7;
+        other.envelope.attack += Time::new::<millisecond>(0.5);
+        assert_ne!(other, preset);
+        assert!(preset.approx_eq(&other, 1.0));
+        assert!(!preset.approx_eq(&other, 1e-3));
+
+        let mut other = preset.clone();
+        other.filter.enabled = !other.filter.enabled;
+        assert!(!preset.approx_eq(&other, 1.0));
+
+        let mut other = preset.clone();
+        other.tuning.root_key = 60;
+        assert!(!preset.approx_eq(&other, 1.0));
+
+        let mut other = preset.clone();
+        other.matrix.pop();
+        assert!(!preset.approx_eq(&other, 1.0));
+    }
+}
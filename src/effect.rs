@@ -3,11 +3,14 @@ use std::fmt::{Display, Formatter};
 
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
-use uom::si::f64::Ratio;
+use uom::si::f64::{Ratio, Time};
+use uom::si::ratio::percent;
+use uom::si::time::millisecond;
 
-use crate::Envelope;
+use crate::{conversions, Envelope, EnvelopeCurve, NoteDivision};
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Chorus {
     pub enabled: bool,
     pub depth: f64,
@@ -16,10 +19,30 @@ pub struct Chorus {
     pub mix: f64,
 }
 
+impl Default for Chorus {
+    fn default() -> Self {
+        Chorus {
+            enabled: false,
+            depth: 0.5,
+            pre_delay: 0.5,
+            ratio: 0.5,
+            mix: 0.5,
+        }
+    }
+}
+
 impl Effect for Chorus {
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Chorus)
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    fn is_audible(&self) -> bool {
+        self.enabled && self.mix > 0.0
+    }
 }
 
 /// Mode for the filter built into the delay effect.
@@ -28,6 +51,7 @@ impl Effect for Chorus {
 /// 1000 and converted to ints, because Babylon stores enumerations as floating
 /// point values. Listed in the order they appear in the Babylon user interface.
 #[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum DelayFilterMode {
     Off = 0,
@@ -36,7 +60,7 @@ pub enum DelayFilterMode {
     LowPass2500 = 125,
     LowPass1600 = 167,
     LowPass1000 = 208,
-    LowPass750 = 25,
+    LowPass750 = 250,
     LowPass400 = 292,
     LowPass200 = 333,
     HighPass4000 = 375,
@@ -63,6 +87,78 @@ impl DelayFilterMode {
             .find(|id| *id as u32 == mode_id)
             .unwrap_or(default)
     }
+
+    /// Type of filter, or `None` if the filter is off.
+    pub fn kind(&self) -> Option<DelayFilterKind> {
+        use DelayFilterMode::*;
+        match self {
+            Off => None,
+            LowPass5000 | LowPass3800 | LowPass2500 | LowPass1600 | LowPass1000 | LowPass750
+            | LowPass400 | LowPass200 => Some(DelayFilterKind::LowPass),
+            HighPass4000 | HighPass2000 | HighPass1200 | HighPass800 | HighPass600
+            | HighPass400 | HighPass250 | HighPass100 => Some(DelayFilterKind::HighPass),
+            BandPass3000 | BandPass1800 | BandPass1300 | BandPass1000 | BandPass700
+            | BandPass500 | BandPass300 | BandPass150 => Some(DelayFilterKind::BandPass),
+        }
+    }
+
+    /// Cutoff frequency of a low or high pass filter, or center frequency of
+    /// a band pass filter, in hertz. `None` if the filter is off.
+    pub fn cutoff_hz(&self) -> Option<u32> {
+        use DelayFilterMode::*;
+        let hz = match self {
+            Off => return None,
+            LowPass5000 => 5000,
+            LowPass3800 => 3800,
+            LowPass2500 => 2500,
+            LowPass1600 => 1600,
+            LowPass1000 => 1000,
+            LowPass750 => 750,
+            LowPass400 => 400,
+            LowPass200 => 200,
+            HighPass4000 => 4000,
+            HighPass2000 => 2000,
+            HighPass1200 => 1200,
+            HighPass800 => 800,
+            HighPass600 => 600,
+            HighPass400 => 400,
+            HighPass250 => 250,
+            HighPass100 => 100,
+            BandPass3000 => 3000,
+            BandPass1800 => 1800,
+            BandPass1300 => 1300,
+            BandPass1000 => 1000,
+            BandPass700 => 700,
+            BandPass500 => 500,
+            BandPass300 => 300,
+            BandPass150 => 150,
+        };
+        Some(hz)
+    }
+
+    /// The mode of the given kind with the frequency closest to `hz`, such
+    /// as when converting a preset from another synth. Frequencies are
+    /// compared by their ratio, so 300 Hz is closer to 400 Hz than 200 Hz.
+    pub fn nearest_from_hz(kind: DelayFilterKind, hz: f64) -> DelayFilterMode {
+        let hz = hz.max(1.0);
+        Self::iter()
+            .filter(|mode| mode.kind() == Some(kind))
+            .filter_map(|mode| mode.cutoff_hz().map(|cutoff| (mode, cutoff)))
+            .min_by(|(_, a), (_, b)| {
+                let distance = |cutoff: &u32| (*cutoff as f64 / hz).ln().abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .map(|(mode, _)| mode)
+            .unwrap_or(DelayFilterMode::Off)
+    }
+}
+
+/// Type of the filter built into the delay and reverb.
+#[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+pub enum DelayFilterKind {
+    LowPass,
+    HighPass,
+    BandPass,
 }
 
 impl Display for DelayFilterMode {
@@ -99,7 +195,8 @@ impl Display for DelayFilterMode {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Delay {
     pub enabled: bool,
     pub ping_pong: bool,
@@ -110,44 +207,213 @@ pub struct Delay {
     pub mix: f64,
 }
 
+impl Default for Delay {
+    fn default() -> Self {
+        Delay {
+            enabled: false,
+            ping_pong: false,
+            feedback: 0.3,
+            filter_mode: DelayFilterMode::Off,
+            sync: true,
+            time: 0.17,
+            mix: 0.2,
+        }
+    }
+}
+
+impl Delay {
+    /// The delay time as a note division when synced to the tempo, or
+    /// `None` when `time` is in seconds.
+    pub fn note_division(&self) -> Option<NoteDivision> {
+        self.sync
+            .then(|| conversions::sync_value_to_division(self.time))
+    }
+
+    /// Whether the built in filter shapes the delayed signal.
+    pub fn is_filtered(&self) -> bool {
+        self.filter_mode != DelayFilterMode::Off
+    }
+}
+
 impl Effect for Delay {
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Delay)
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    fn is_audible(&self) -> bool {
+        self.enabled && self.mix > 0.0
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Distortion {
     pub enabled: bool,
 
-    /// 0.0 to 10.0
+    /// From 0.0 to 1.0, stored as-is in the preset. Babylon displays the
+    /// gain from 0 to 10, see [`Distortion::gain_ui`].
     pub gain: f64,
 }
 
+impl Default for Distortion {
+    fn default() -> Self {
+        Distortion {
+            enabled: false,
+            gain: 0.2,
+        }
+    }
+}
+
+impl Distortion {
+    /// The gain from 0 to 10 as Babylon displays it.
+    pub fn gain_ui(&self) -> f64 {
+        self.gain * UI_SCALE
+    }
+
+    /// Set the gain from the value Babylon displays, clamped from 0 to 10.
+    pub fn set_gain_ui(&mut self, gain: f64) {
+        self.gain = (gain / UI_SCALE).clamp(0.0, 1.0);
+    }
+}
+
 impl Effect for Distortion {
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Distortion)
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    fn is_audible(&self) -> bool {
+        self.enabled && self.gain > 0.0
+    }
 }
 
 pub trait Effect {
+    /// The type of the effect in the effect chain, or `None` for parts of a
+    /// preset that are not in the chain, such as the noise generator. The
+    /// main filter of a preset is not in the chain either but shares its type
+    /// with the effect filter, so it is a [`EffectType::Filter`].
+    fn effect_type(&self) -> Option<EffectType> {
+        None
+    }
+
+    /// Name of the effect as displayed by Babylon.
+    fn name(&self) -> &'static str {
+        self.effect_type()
+            .map_or("", |effect_type| effect_type.name())
+    }
+
     fn is_enabled(&self) -> bool {
         false
     }
+
+    /// Whether the effect changes the sound, which requires it to be enabled
+    /// with a mix or amount above zero.
+    fn is_audible(&self) -> bool {
+        self.is_enabled()
+    }
 }
 
-#[derive(Debug)]
+/// Babylon displays the distortion gain and some of the lo-fi controls
+/// from 0 to 10 but stores them from 0.0 to 1.0.
+const UI_SCALE: f64 = 10.0;
+
+/// The equalizer has three fixed bands that cannot be tuned: a low shelf, a
+/// mid peak and a high shelf. Their frequencies are not stored in presets and
+/// Babylon doesn't display them, so [`Equalizer::LOW_HZ`],
+/// [`Equalizer::MID_HZ`] and [`Equalizer::HIGH_HZ`] are nominal placeholder
+/// values, not measured ones.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Equalizer {
     pub enabled: bool,
+
+    /// Gains are stored as-is from the preset, from 0.0 to 1.0 with 0.5
+    /// being flat. See [`Equalizer::high_db`] for the gain in decibels.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::percent"))]
     pub high_gain: Ratio,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::percent"))]
     pub low_gain: Ratio,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::percent"))]
     pub mid_gain: Ratio,
 }
 
+impl Default for Equalizer {
+    /// A flat equalizer.
+    fn default() -> Self {
+        Equalizer {
+            enabled: false,
+            high_gain: Ratio::new::<percent>(0.5),
+            low_gain: Ratio::new::<percent>(0.5),
+            mid_gain: Ratio::new::<percent>(0.5),
+        }
+    }
+}
+
+impl Equalizer {
+    /// Nominal corner frequency of the low shelf in hertz.
+    pub const LOW_HZ: f64 = 100.0;
+
+    /// Nominal center frequency of the mid peak in hertz.
+    pub const MID_HZ: f64 = 1_000.0;
+
+    /// Nominal corner frequency of the high shelf in hertz.
+    pub const HIGH_HZ: f64 = 10_000.0;
+
+    /// Gain of the low band in decibels, from -inf to +10 dB. See
+    /// [`conversions::normalized_to_db`].
+    pub fn low_db(&self) -> f64 {
+        conversions::normalized_to_db(self.low_gain.get::<percent>())
+    }
+
+    /// Gain of the mid band in decibels, from -inf to +10 dB.
+    pub fn mid_db(&self) -> f64 {
+        conversions::normalized_to_db(self.mid_gain.get::<percent>())
+    }
+
+    /// Gain of the high band in decibels, from -inf to +10 dB.
+    pub fn high_db(&self) -> f64 {
+        conversions::normalized_to_db(self.high_gain.get::<percent>())
+    }
+
+    /// Set the gain of the low band in decibels, clamped to at most +10 dB.
+    pub fn set_low_db(&mut self, db: f64) {
+        self.low_gain = Ratio::new::<percent>(conversions::db_to_normalized(db));
+    }
+
+    /// Set the gain of the mid band in decibels, clamped to at most +10 dB.
+    pub fn set_mid_db(&mut self, db: f64) {
+        self.mid_gain = Ratio::new::<percent>(conversions::db_to_normalized(db));
+    }
+
+    /// Set the gain of the high band in decibels, clamped to at most +10 dB.
+    pub fn set_high_db(&mut self, db: f64) {
+        self.high_gain = Ratio::new::<percent>(conversions::db_to_normalized(db));
+    }
+}
+
 impl Effect for Equalizer {
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Equalizer)
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    /// The equalizer is flat when every gain is at the center.
+    fn is_audible(&self) -> bool {
+        let flat = [self.low_gain, self.mid_gain, self.high_gain]
+            .iter()
+            .all(|gain| gain.get::<percent>() == 0.5);
+        self.enabled && !flat
+    }
 }
 
 /// Kinds of effects.
@@ -155,6 +421,7 @@ impl Effect for Equalizer {
 /// The discriminants of the items match the file format. This is the default
 /// ordering of the effects.
 #[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum EffectType {
     Distortion,
@@ -166,18 +433,54 @@ pub enum EffectType {
     Reverb,
 }
 
+impl EffectType {
+    /// Name of the effect as displayed by Babylon.
+    pub fn name(&self) -> &'static str {
+        use EffectType::*;
+        match self {
+            Distortion => "Distortion",
+            LoFi => "Lo-Fi",
+            Filter => "Filter",
+            Chorus => "Chorus",
+            Equalizer => "Equalizer",
+            Delay => "Delay",
+            Reverb => "Reverb",
+        }
+    }
+
+    /// Abbreviated name for compact displays.
+    pub fn short_name(&self) -> &'static str {
+        use EffectType::*;
+        match self {
+            Distortion => "Dist",
+            LoFi => "Lo-Fi",
+            Filter => "Filter",
+            Chorus => "Chorus",
+            Equalizer => "EQ",
+            Delay => "Delay",
+            Reverb => "Reverb",
+        }
+    }
+}
+
+impl Display for EffectType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 impl TryFrom<u32> for EffectType {
     type Error = String;
 
     fn try_from(effect_type_id: u32) -> Result<Self, Self::Error> {
-        Self::iter()
-            .find(|id| *id as u32 == effect_type_id)
+        Self::from_file_id(effect_type_id)
             .ok_or(format!("Unknown effect type ID {}", effect_type_id))
     }
 }
 
 /// The discriminants of the items match the file format.
 #[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum FilterMode {
     LowPass,
@@ -187,16 +490,23 @@ pub enum FilterMode {
     Peak,
 }
 
-impl FilterMode {
-    pub(crate) fn from_or(mode_id: u32, default: Self) -> Self {
-        Self::iter()
-            .find(|id| *id as u32 == mode_id)
-            .unwrap_or(default)
+impl Display for FilterMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use FilterMode::*;
+        let msg = match self {
+            LowPass => "Low Pass",
+            BandPass => "Band Pass",
+            HighPass => "High Pass",
+            Notch => "Notch",
+            Peak => "Peak",
+        };
+        f.write_str(msg)
     }
 }
 
 /// The discriminants of the items match the file format.
 #[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum FilterEffectMode {
     Off,
@@ -207,19 +517,33 @@ pub enum FilterEffectMode {
     SampleRateReduction,
 }
 
-impl FilterEffectMode {
-    pub(crate) fn from_or(mode_id: u32, default: FilterEffectMode) -> FilterEffectMode {
-        FilterEffectMode::iter()
-            .find(|id| *id as u32 == mode_id)
-            .unwrap_or(default)
+impl Display for FilterEffectMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use FilterEffectMode::*;
+        let msg = match self {
+            Off => "Off",
+            Saturation => "Saturation",
+            Overdrive => "Overdrive",
+            Distortion => "Distortion",
+            BitRateReduction => "Bit Rate Reduction",
+            SampleRateReduction => "Sample Rate Reduction",
+        };
+        f.write_str(msg)
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Filter {
     pub enabled: bool,
     pub mode: FilterMode,
+
+    /// Position of the resonance control from 0.0 to 1.0. Babylon doesn't
+    /// display the Q factor of the filter, see [`Filter::q`].
     pub resonance: f64,
+
+    /// Position of the cutoff control from 0.0 to 100.0, which is fully
+    /// open. Babylon stores it from 0.0 to 1.0. See [`Filter::cutoff_hz`].
     pub cutoff_frequency: f64,
     pub key_tracking: f64,
     pub envelope: Envelope,
@@ -230,62 +554,271 @@ pub struct Filter {
     /// How the effect is processed.
     pub effect_mode: FilterEffectMode,
     pub effect_enabled: bool,
+
+    /// Amount of drive from 0.0 to 1.0, displayed by Babylon as 0% to 100%.
     pub effect_amount: f64,
 }
 
+impl Default for Filter {
+    /// The main filter of a new preset.
+    fn default() -> Self {
+        Filter {
+            enabled: false,
+            mode: FilterMode::LowPass,
+            resonance: 0.0,
+            cutoff_frequency: 100.0,
+            key_tracking: 0.0,
+            envelope: Envelope {
+                attack: Time::new::<millisecond>(2.0),
+                attack_curve: EnvelopeCurve::Exponential1,
+                decay: Time::new::<millisecond>(150.0),
+                decay_falloff: EnvelopeCurve::Exponential1,
+                sustain: Ratio::new::<percent>(0.02),
+                release: Time::new::<millisecond>(4.0),
+                release_falloff: EnvelopeCurve::Exponential1,
+            },
+            envelope_amount: 0.0,
+            effect_mode: FilterEffectMode::Off,
+            effect_enabled: false,
+            effect_amount: 0.5,
+        }
+    }
+}
+
+impl Filter {
+    /// The cutoff frequency in hertz.
+    pub fn cutoff_hz(&self) -> f64 {
+        conversions::control_to_cutoff_hz(self.cutoff_frequency / 100.0)
+    }
+
+    /// Amount of drive as a percentage, as displayed by Babylon.
+    pub fn drive_amount_percent(&self) -> f64 {
+        conversions::normalized_to_percent(self.effect_amount)
+    }
+
+    /// The Q factor of the filter, from the provisional curve of
+    /// [`conversions::resonance_to_q`]. The main filter and the effect
+    /// filter use the same curve.
+    pub fn q(&self) -> f64 {
+        conversions::resonance_to_q(self.resonance)
+    }
+
+    /// Set the resonance from a Q factor, clamped to the range of
+    /// [`Filter::q`].
+    pub fn set_q(&mut self, q: f64) {
+        self.resonance = conversions::q_to_resonance(q);
+    }
+}
+
 impl Effect for Filter {
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Filter)
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LoFi {
     pub enabled: bool,
+
+    /// Bit rate reduction from 0.0 to 1.0, stored as-is in the preset.
+    /// Babylon displays it from 0 to 10 without units, see
+    /// [`LoFi::bitrate_ui`]. See [`LoFi::bit_depth`] for the provisional bit
+    /// depth.
     pub bitrate: f64,
 
-    // 0 to 10.0 in Babylon interface
+    /// Sample rate reduction from 0.0 to 1.0, stored as-is in the preset.
+    /// Babylon displays it from 0 to 10 without units, see
+    /// [`LoFi::sample_rate_ui`]. See [`LoFi::sample_rate_hz`] for the
+    /// provisional sample rate.
     pub sample_rate: f64,
 
-    // 0 to 10.0 in Babylon interface
+    /// From 0.0 to 1.0, stored as-is in the preset. Babylon displays it from
+    /// 0 to 10, see [`LoFi::mix_ui`].
     pub mix: f64,
 }
 
+impl Default for LoFi {
+    fn default() -> Self {
+        LoFi {
+            enabled: false,
+            bitrate: 1.0,
+            sample_rate: 1.0,
+            mix: 1.0,
+        }
+    }
+}
+
+impl LoFi {
+    /// The bit rate reduction from 0 to 10 as Babylon displays it.
+    pub fn bitrate_ui(&self) -> f64 {
+        self.bitrate * UI_SCALE
+    }
+
+    /// Set the bit rate reduction from the value Babylon displays, clamped
+    /// from 0 to 10.
+    pub fn set_bitrate_ui(&mut self, bitrate: f64) {
+        self.bitrate = (bitrate / UI_SCALE).clamp(0.0, 1.0);
+    }
+
+    /// The sample rate reduction from 0 to 10 as Babylon displays it.
+    pub fn sample_rate_ui(&self) -> f64 {
+        self.sample_rate * UI_SCALE
+    }
+
+    /// Set the sample rate reduction from the value Babylon displays,
+    /// clamped from 0 to 10.
+    pub fn set_sample_rate_ui(&mut self, sample_rate: f64) {
+        self.sample_rate = (sample_rate / UI_SCALE).clamp(0.0, 1.0);
+    }
+
+    /// The bit depth the effect reduces to, from the provisional curve of
+    /// [`conversions::control_to_bit_depth`].
+    pub fn bit_depth(&self) -> f64 {
+        conversions::control_to_bit_depth(self.bitrate)
+    }
+
+    /// Set the bit rate reduction from a bit depth, clamped to the range of
+    /// [`LoFi::bit_depth`].
+    pub fn set_bit_depth(&mut self, bits: f64) {
+        self.bitrate = conversions::bit_depth_to_control(bits);
+    }
+
+    /// The sample rate the effect reduces to in hertz, from the provisional
+    /// curve of [`conversions::control_to_sample_rate_hz`].
+    pub fn sample_rate_hz(&self) -> f64 {
+        conversions::control_to_sample_rate_hz(self.sample_rate)
+    }
+
+    /// Set the sample rate reduction from a sample rate in hertz, clamped to
+    /// the range of [`LoFi::sample_rate_hz`].
+    pub fn set_sample_rate_hz(&mut self, hz: f64) {
+        self.sample_rate = conversions::sample_rate_hz_to_control(hz);
+    }
+
+    /// The mix from 0 to 10 as Babylon displays it.
+    pub fn mix_ui(&self) -> f64 {
+        self.mix * UI_SCALE
+    }
+
+    /// Set the mix from the value Babylon displays, clamped from 0 to 10.
+    pub fn set_mix_ui(&mut self, mix: f64) {
+        self.mix = (mix / UI_SCALE).clamp(0.0, 1.0);
+    }
+}
+
 impl Effect for LoFi {
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::LoFi)
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    fn is_audible(&self) -> bool {
+        self.enabled && self.mix > 0.0
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Reverb {
     pub enabled: bool,
     pub dampen: f64,
     pub filter: f64,
     pub room: f64,
+
+    /// Stereo width from 0.0 for mono to 1.0 for the full stereo field.
     pub width: f64,
     pub mix: f64,
 }
 
+impl Default for Reverb {
+    fn default() -> Self {
+        Reverb {
+            enabled: false,
+            dampen: 0.3,
+            filter: 0.0,
+            room: 0.3,
+            width: 0.8,
+            mix: 0.2,
+        }
+    }
+}
+
+impl Reverb {
+    /// Mode of the built in filter, which has the same choices as the delay.
+    pub fn filter_mode(&self) -> DelayFilterMode {
+        conversions::control_to_delay_filter_mode(self.filter)
+    }
+
+    /// Whether the built in filter shapes the reverberated signal.
+    pub fn is_filtered(&self) -> bool {
+        self.filter_mode() != DelayFilterMode::Off
+    }
+
+    /// Cutoff or center frequency of the built in filter in hertz, or `None`
+    /// if the filter is off. See [`DelayFilterMode::cutoff_hz`].
+    pub fn filter_cutoff_hz(&self) -> Option<u32> {
+        self.filter_mode().cutoff_hz()
+    }
+
+    /// Damping as a percentage, as displayed by Babylon.
+    pub fn dampen_percent(&self) -> f64 {
+        conversions::normalized_to_percent(self.dampen)
+    }
+
+    /// Room size as a percentage, as displayed by Babylon.
+    pub fn room_percent(&self) -> f64 {
+        conversions::normalized_to_percent(self.room)
+    }
+
+    /// Stereo width as a percentage, as displayed by Babylon.
+    pub fn width_percent(&self) -> f64 {
+        conversions::normalized_to_percent(self.width)
+    }
+
+    /// Mix as a percentage, as displayed by Babylon.
+    pub fn mix_percent(&self) -> f64 {
+        conversions::normalized_to_percent(self.mix)
+    }
+}
+
 impl Effect for Reverb {
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Reverb)
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    fn is_audible(&self) -> bool {
+        self.enabled && self.mix > 0.0
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use std::io::Result;
+    use std::f64::consts::FRAC_1_SQRT_2;
     use std::path::Path;
 
     use approx::assert_relative_eq;
     use strum::IntoEnumIterator;
     use uom::si::ratio::percent;
 
-    use crate::{DelayFilterMode, EffectType, FilterMode, Preset};
+    use crate::{
+        BabylonError, DelayFilterKind, DelayFilterMode, Effect, EffectType, FilterEffectMode,
+        FilterMode, NoteDivision, Preset,
+    };
 
-    fn read_preset(filename: &str) -> Result<Preset> {
-        let path = &Path::new("tests").join("effects").join(&filename);
+    fn read_preset(filename: &str) -> Result<Preset, BabylonError> {
+        let path = &Path::new("tests").join("effects").join(filename);
         Preset::read_file(path)
     }
 
@@ -293,6 +826,7 @@ mod test {
     fn delay() {
         let preset = read_preset("delay-ping_pong_off-1.0.2.bab").unwrap();
         assert!(!preset.delay.ping_pong);
+        assert!(!preset.delay.is_filtered());
 
         let preset = read_preset("delay-ping_pong_on-1.0.2.bab").unwrap();
         assert!(preset.delay.ping_pong);
@@ -302,6 +836,7 @@ mod test {
         assert!(preset.delay.sync);
         assert_eq!(preset.delay.time, 1.0);
         assert_eq!(preset.delay.filter_mode, DelayFilterMode::HighPass100);
+        assert!(preset.delay.is_filtered());
 
         let preset = read_preset("delay-time504-syncoff-1.0.3.bab").unwrap();
         assert!(!preset.delay.sync);
@@ -316,6 +851,21 @@ mod test {
         assert_eq!(preset.delay.filter_mode, DelayFilterMode::BandPass3000);
     }
 
+    #[test]
+    fn delay_note_division() {
+        let preset = read_preset("delay-time1t-hp100-ping_pong-1.0.3.bab").unwrap();
+        assert_eq!(
+            preset.delay.note_division(),
+            Some(NoteDivision::WholeTriplet)
+        );
+        let preset = read_preset("delay-timehalf-lp200-1.0.3.bab").unwrap();
+        assert_eq!(preset.delay.note_division(), Some(NoteDivision::Half));
+        let preset = read_preset("delay-timesixteenth-bp3000-1.0.3.bab").unwrap();
+        assert_eq!(preset.delay.note_division(), Some(NoteDivision::Sixteenth));
+        let preset = read_preset("delay-time504-syncoff-1.0.3.bab").unwrap();
+        assert_eq!(preset.delay.note_division(), None);
+    }
+
     #[test]
     fn delay_filter_mode() {
         let preset = read_preset("delay-band_pass_150-1.0.4.bab").unwrap();
@@ -335,11 +885,87 @@ mod test {
         assert_eq!(preset.delay.filter_mode, DelayFilterMode::LowPass200);
     }
 
+    #[test]
+    fn delay_filter_mode_frequency() {
+        assert_eq!(DelayFilterMode::Off.kind(), None);
+        assert_eq!(DelayFilterMode::Off.cutoff_hz(), None);
+        assert_eq!(
+            DelayFilterMode::LowPass5000.kind(),
+            Some(DelayFilterKind::LowPass)
+        );
+        assert_eq!(DelayFilterMode::LowPass5000.cutoff_hz(), Some(5000));
+        assert_eq!(
+            DelayFilterMode::BandPass3000.kind(),
+            Some(DelayFilterKind::BandPass)
+        );
+        assert_eq!(DelayFilterMode::HighPass250.cutoff_hz(), Some(250));
+
+        // The frequency is in the name the mode is displayed with.
+        for mode in DelayFilterMode::iter() {
+            if let Some(hz) = mode.cutoff_hz() {
+                assert!(mode.to_string().contains(&format!(" {} Hz", hz)));
+                let kind = mode.kind().unwrap();
+                assert_eq!(DelayFilterMode::nearest_from_hz(kind, hz as f64), mode);
+            }
+        }
+
+        assert_eq!(
+            DelayFilterMode::nearest_from_hz(DelayFilterKind::LowPass, 300.0),
+            DelayFilterMode::LowPass400
+        );
+        assert_eq!(
+            DelayFilterMode::nearest_from_hz(DelayFilterKind::HighPass, 20_000.0),
+            DelayFilterMode::HighPass4000
+        );
+        assert_eq!(
+            DelayFilterMode::nearest_from_hz(DelayFilterKind::BandPass, 0.0),
+            DelayFilterMode::BandPass150
+        );
+    }
+
     #[test]
     fn distortion() {
         let preset = read_preset("distortion-gain5-1.0.3.bab").unwrap();
         assert!(preset.distortion.enabled);
         assert_eq!(preset.distortion.gain, 0.5);
+        assert_eq!(preset.distortion.gain_ui(), 5.0);
+
+        let mut distortion = preset.distortion.clone();
+        distortion.set_gain_ui(2.0);
+        assert_relative_eq!(distortion.gain, 0.2);
+        distortion.set_gain_ui(11.0);
+        assert_eq!(distortion.gain, 1.0);
+    }
+
+    #[test]
+    fn lofi_ui() {
+        let preset = read_preset("distortion-gain5-1.0.3.bab").unwrap();
+        let mut lofi = preset.lofi.clone();
+        assert_eq!(lofi.bitrate_ui(), 10.0);
+        assert_eq!(lofi.sample_rate_ui(), 10.0);
+        assert_eq!(lofi.mix_ui(), 10.0);
+        lofi.set_bitrate_ui(7.5);
+        assert_relative_eq!(lofi.bitrate, 0.75);
+        lofi.set_bitrate_ui(11.0);
+        assert_eq!(lofi.bitrate, 1.0);
+        lofi.set_sample_rate_ui(2.5);
+        assert_relative_eq!(lofi.sample_rate, 0.25);
+        lofi.set_mix_ui(-1.0);
+        assert_eq!(lofi.mix, 0.0);
+    }
+
+    #[test]
+    fn lofi_units() {
+        let preset = read_preset("distortion-gain5-1.0.3.bab").unwrap();
+        let mut lofi = preset.lofi.clone();
+        assert_eq!(lofi.bit_depth(), 4.0);
+        assert_relative_eq!(lofi.sample_rate_hz(), 1_000.0, epsilon = 0.0001);
+        lofi.set_bit_depth(10.0);
+        assert_relative_eq!(lofi.bitrate, 0.5);
+        lofi.set_sample_rate_hz(22_050.0);
+        assert_relative_eq!(lofi.sample_rate_hz(), 22_050.0, epsilon = 0.0001);
+        lofi.set_sample_rate_hz(96_000.0);
+        assert_eq!(lofi.sample_rate, 0.0);
     }
 
     #[test]
@@ -350,19 +976,102 @@ mod test {
         assert_eq!(preset.effect_position(EffectType::Equalizer).unwrap(), 2);
     }
 
+    #[test]
+    fn effect_name_and_type() {
+        let preset = read_preset("effect-order-reversed-1.0.2.bab").unwrap();
+        for (effect_type, effect) in preset.effects() {
+            assert_eq!(effect.effect_type(), Some(effect_type));
+            assert_eq!(effect.name(), effect_type.to_string());
+        }
+        assert_eq!(preset.lofi.name(), "Lo-Fi");
+        assert_eq!(preset.noise.effect_type(), None);
+        assert_eq!(preset.noise.name(), "Noise");
+    }
+
+    #[test]
+    fn effects() {
+        let preset = read_preset("effect-order-reversed-1.0.2.bab").unwrap();
+        let effects: Vec<_> = preset.effects().collect();
+        assert_eq!(effects.len(), 7);
+        assert_eq!(effects[0].0, preset.effect_order[0]);
+        assert_eq!(effects[0].0, EffectType::Reverb);
+        assert_eq!(effects[0].1.is_enabled(), preset.reverb.enabled);
+        assert_eq!((&preset).into_iter().count(), 7);
+    }
+
+    #[test]
+    fn effect_order_extra_slot() {
+        let preset = read_preset("effect-order-extra-slot-1.0.4.bab").unwrap();
+        let expected_effect_order: Vec<EffectType> = EffectType::iter().collect();
+        assert_eq!(&preset.effect_order, &expected_effect_order);
+        assert_eq!(&preset.extra_effect_order, &[7]);
+    }
+
+    #[test]
+    fn effects_in_order() {
+        let preset = read_preset("effect-order-reversed-1.0.2.bab").unwrap();
+        let effect_types: Vec<Option<EffectType>> = preset
+            .effects_in_order()
+            .iter()
+            .map(|effect| effect.effect_type())
+            .collect();
+        let expected: Vec<Option<EffectType>> =
+            preset.effect_order.iter().copied().map(Some).collect();
+        assert_eq!(effect_types, expected);
+        assert_eq!(expected.first(), Some(&Some(EffectType::Reverb)));
+    }
+
+    #[test]
+    fn effects_in_order_filtered() {
+        let preset = read_preset("delay-ping_pong_on-1.0.2.bab").unwrap();
+        assert_eq!(
+            preset.effects_in_order_filtered(false),
+            vec![EffectType::Delay]
+        );
+        assert_eq!(preset.effects_in_order_filtered(true), preset.effect_order);
+    }
+
+    #[test]
+    fn enabled_effects() {
+        let mut preset = read_preset("delay-ping_pong_on-1.0.2.bab").unwrap();
+        let enabled: Vec<EffectType> = preset.enabled_effects().map(|(t, _)| t).collect();
+        assert_eq!(enabled, vec![EffectType::Delay]);
+
+        preset.filter.enabled = true;
+        assert_eq!(preset.enabled_effects().count(), 1);
+        preset.effect_filter.enabled = true;
+        let enabled: Vec<EffectType> = preset.enabled_effects().map(|(t, _)| t).collect();
+        assert_eq!(enabled, vec![EffectType::Filter, EffectType::Delay]);
+    }
+
     #[test]
     fn equalizer() {
         let preset = read_preset("equalizer-l-10-m5-h-10-1.0.3.bab").unwrap();
-        assert!(preset.equalizer.enabled);
-        assert_eq!(preset.equalizer.low_gain.get::<percent>(), 0.5);
-        assert_eq!(preset.equalizer.mid_gain.get::<percent>(), 0.5);
-        assert_eq!(preset.equalizer.high_gain.get::<percent>(), 0.5);
+        let equalizer = &preset.equalizer;
+        assert!(equalizer.enabled);
+        assert_relative_eq!(equalizer.low_gain.get::<percent>(), 0.194, epsilon = 0.0001);
+        assert_relative_eq!(equalizer.mid_gain.get::<percent>(), 0.733, epsilon = 0.0001);
+        assert_eq!(equalizer.high_gain.get::<percent>(), 1.0);
+        assert_relative_eq!(equalizer.low_db(), -10.0, epsilon = 0.01);
+        assert_relative_eq!(equalizer.high_db(), 10.0);
+
+        // The fixture name rounds the mid gain Babylon displayed to +5 dB.
+        assert_relative_eq!(equalizer.mid_db(), 4.66, epsilon = 0.01);
+
+        let mut equalizer = equalizer.clone();
+        equalizer.set_low_db(f64::NEG_INFINITY);
+        assert_eq!(equalizer.low_gain.get::<percent>(), 0.0);
+        equalizer.set_mid_db(0.0);
+        assert_eq!(equalizer.mid_gain.get::<percent>(), 0.5);
+        equalizer.set_high_db(20.0);
+        assert_eq!(equalizer.high_db(), 10.0);
     }
 
     #[test]
     fn filter() {
         let preset = read_preset("filter-bandpass-1.0.2.bab").unwrap();
         assert_eq!(preset.filter.mode, FilterMode::BandPass);
+        assert_eq!(preset.filter.mode.to_string(), "Band Pass");
         assert_eq!(preset.filter.cutoff_frequency, 100.0);
 
         let preset = read_preset("filter-highpass-1.0.2.bab").unwrap();
@@ -373,16 +1082,74 @@ mod test {
 
         let preset = read_preset("filter-peak-1.0.2.bab").unwrap();
         assert_eq!(preset.filter.mode, FilterMode::Peak);
+
+        let preset = read_preset("filter-drive75-overdrive-1.0.4.bab").unwrap();
+        assert!(preset.filter.enabled);
+        assert_eq!(preset.filter.effect_mode, FilterEffectMode::Overdrive);
+        assert_eq!(
+            FilterEffectMode::BitRateReduction.to_string(),
+            "Bit Rate Reduction"
+        );
+        assert_relative_eq!(preset.filter.effect_amount, 0.75, epsilon = 0.0001);
+        assert_relative_eq!(preset.filter.drive_amount_percent(), 75.0, epsilon = 0.0001);
+    }
+
+    /// The resonance of the effect filter of the init preset pins the
+    /// provisional Q curve.
+    #[test]
+    fn filter_q() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let mut preset = Preset::read_file(path).unwrap();
+        assert_eq!(preset.filter.resonance, 0.0);
+        assert_relative_eq!(preset.filter.q(), FRAC_1_SQRT_2, epsilon = 0.0001);
+        assert_relative_eq!(preset.effect_filter.resonance, 0.1, epsilon = 0.0001);
+        assert_relative_eq!(preset.effect_filter.q(), 0.9216, epsilon = 0.0001);
+
+        preset.filter.set_q(preset.effect_filter.q());
+        assert_relative_eq!(preset.filter.resonance, 0.1, epsilon = 0.0001);
+        preset.filter.set_q(100.0);
+        assert_eq!(preset.filter.resonance, 1.0);
+    }
+
+    /// The main filter and the effect filter use the same scale.
+    #[test]
+    fn filter_cutoff_scale() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let xml = std::fs::read_to_string(path).unwrap().replace(
+            r#"<PARAM id="FilterCut" value="1.0"/>"#,
+            r#"<PARAM id="FilterCut" value="0.5"/>"#,
+        );
+        let preset: Preset = xml.parse().unwrap();
+        assert_eq!(preset.filter.cutoff_frequency, 50.0);
+        assert_eq!(preset.effect_filter.cutoff_frequency, 50.0);
+        assert_eq!(preset.filter.cutoff_hz(), preset.effect_filter.cutoff_hz());
+        assert_relative_eq!(preset.filter.cutoff_hz(), 632.456, epsilon = 0.001);
     }
 
     #[test]
     fn reverb() {
-        let preset = read_preset("reverb-r100-w0-d50-m34-hp400-1.0.3.bab").unwrap();
+        let mut preset = read_preset("reverb-r100-w0-d50-m34-hp400-1.0.3.bab").unwrap();
         assert!(preset.reverb.enabled);
         assert_relative_eq!(preset.reverb.room, 1.0, epsilon = 0.0001);
         assert_relative_eq!(preset.reverb.width, 0.0, epsilon = 0.0001);
         assert_relative_eq!(preset.reverb.dampen, 0.50, epsilon = 0.0001);
         assert_relative_eq!(preset.reverb.mix, 0.34, epsilon = 0.0001);
         assert_relative_eq!(preset.reverb.filter, 0.583, epsilon = 0.0001);
+        assert_eq!(preset.reverb.filter_mode(), DelayFilterMode::HighPass400);
+        assert!(preset.reverb.is_filtered());
+        assert_eq!(preset.reverb.filter_cutoff_hz(), Some(400));
+        assert_relative_eq!(preset.reverb.room_percent(), 100.0, epsilon = 0.0001);
+        assert_relative_eq!(preset.reverb.width_percent(), 0.0, epsilon = 0.0001);
+        assert_relative_eq!(preset.reverb.dampen_percent(), 50.0, epsilon = 0.0001);
+        assert_relative_eq!(preset.reverb.mix_percent(), 34.0, epsilon = 0.0001);
+        assert!(preset.reverb.is_audible());
+
+        preset.reverb.mix = 0.0;
+        assert!(preset.reverb.is_enabled());
+        assert!(!preset.reverb.is_audible());
+
+        let preset = read_preset("effect-order-reversed-1.0.2.bab").unwrap();
+        assert!(!preset.reverb.is_filtered());
+        assert_eq!(preset.reverb.filter_cutoff_hz(), None);
     }
 }
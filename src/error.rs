@@ -0,0 +1,123 @@
+use std::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+/// Errors from reading presets, parsing their values and editing them.
+#[derive(Debug, Error)]
+pub enum BabylonError {
+    /// The preset could not be read from its source, such as when the file
+    /// does not exist.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// The preset is not well formed XML or is not a Babylon preset.
+    #[error("Unable to read {preset}: {error}")]
+    Xml {
+        /// Where the preset was read from, such as the path of the file.
+        preset: String,
+
+        #[source]
+        error: serde_xml_rs::Error,
+    },
+
+    /// The preset is empty or only whitespace.
+    #[error("Unable to read {preset}: The preset is empty")]
+    Empty {
+        /// Where the preset was read from, such as the path of the file.
+        preset: String,
+    },
+
+    /// The preset ends before the end of its parameters, such as when a file
+    /// was not completely written or downloaded.
+    #[error("Unable to read {preset}: The preset is truncated, it ends before </PluginParamTree>")]
+    Truncated {
+        /// Where the preset was read from, such as the path of the file.
+        preset: String,
+
+        #[source]
+        error: serde_xml_rs::Error,
+    },
+
+    /// An effect slot holds an effect type that is not known.
+    #[error(
+        "Unable to read {preset}: Unknown effect type ID {effect_type_id} in attribute {attribute}"
+    )]
+    UnknownEffectType {
+        /// Where the preset was read from, such as the path of the file.
+        preset: String,
+
+        /// Name of the attribute of the effect slot, such as `FX_Order_2`.
+        attribute: String,
+
+        effect_type_id: u32,
+    },
+
+    /// A waveform name that is not the name of any waveform.
+    #[error("Unknown waveform {0:?}")]
+    UnknownWaveform(String),
+
+    /// A portamento mode name that is not the name of any portamento mode.
+    #[error("Unknown portamento mode {0:?}")]
+    UnknownPortamentoMode(String),
+
+    /// A note name that is not a letter from A to G, an optional sharp or
+    /// flat and an octave.
+    #[error("Unknown note name {0:?}")]
+    UnknownNoteName(String),
+
+    /// A parameter that is read into the preset was set as a parameter that
+    /// is not recognized. See
+    /// [`Preset::set_raw_param`](crate::Preset::set_raw_param).
+    #[error("Parameter {0:?} is part of the preset and can't be set as a raw parameter")]
+    KnownParam(String),
+
+    /// There is no oscillator at an index, where zero is the first
+    /// oscillator.
+    #[error("No oscillator with index {0}")]
+    NoOscillator(usize),
+
+    /// The zip archive could not be read.
+    #[cfg(feature = "zip")]
+    #[error(transparent)]
+    Zip(zip::result::ZipError),
+}
+
+/// A problem found while reading a preset that did not prevent it from being
+/// read. See [`Preset::read_file_verbose`](crate::Preset::read_file_verbose).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Warning {
+    /// A parameter that is not known, possibly from a newer version of
+    /// Babylon. It is kept in [`Preset::unknown_params`](crate::Preset::unknown_params).
+    UnknownParam { id: String, value: Option<String> },
+
+    /// An attribute that is not known, possibly from a newer version of
+    /// Babylon. It is kept in
+    /// [`Preset::unknown_attributes`](crate::Preset::unknown_attributes).
+    UnknownAttribute { name: String, value: String },
+
+    /// A parameter that selects one of a list of choices, such as the
+    /// waveform of an oscillator or the mode of a filter, has a value that
+    /// is not any of them. The default choice is used instead.
+    UnknownChoice {
+        /// ID of the parameter, such as `OSCWaveType_1` or `FilterType`.
+        id: String,
+
+        value: u32,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::UnknownParam { id, value } => {
+                write!(f, "Unrecognized parameter {} is {:?}", id, value)
+            }
+            Warning::UnknownAttribute { name, value } => {
+                write!(f, "Unrecognized attribute {} is {:?}", name, value)
+            }
+            Warning::UnknownChoice { id, value } => {
+                write!(f, "Unknown choice {} for parameter {}", value, id)
+            }
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Morphing between presets.
+
+use uom::si::f64::{Frequency, Ratio, Time};
+
+use crate::{
+    Chorus, Delay, Distortion, Envelope, Equalizer, Filter, Lfo, LoFi, MatrixItem,
+    ModulatorEnvelope, Noise, Oscillator, Preset, Reverb, Tuning, Unison, Vibrato,
+};
+
+/// A blend of two values of the same type.
+trait Interpolate {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self;
+}
+
+/// The first value before the midpoint and the second from it on, for values
+/// that can't be blended.
+fn snap<T: Clone>(a: &T, b: &T, t: f64) -> T {
+    if t < 0.5 {
+        a.clone()
+    } else {
+        b.clone()
+    }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a * (1.0 - t) + b * t
+}
+
+fn interpolate_all<T: Interpolate>(a: &[T], b: &[T], t: f64) -> Vec<T> {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| T::interpolate(a, b, t))
+        .collect()
+}
+
+impl Interpolate for Time {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        *a * (1.0 - t) + *b * t
+    }
+}
+
+impl Interpolate for Frequency {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        *a * (1.0 - t) + *b * t
+    }
+}
+
+impl Interpolate for Ratio {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        *a * (1.0 - t) + *b * t
+    }
+}
+
+impl Interpolate for Envelope {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Envelope {
+            attack: Time::interpolate(&a.attack, &b.attack, t),
+            attack_curve: snap(&a.attack_curve, &b.attack_curve, t),
+            decay: Time::interpolate(&a.decay, &b.decay, t),
+            decay_falloff: snap(&a.decay_falloff, &b.decay_falloff, t),
+            sustain: Ratio::interpolate(&a.sustain, &b.sustain, t),
+            release: Time::interpolate(&a.release, &b.release, t),
+            release_falloff: snap(&a.release_falloff, &b.release_falloff, t),
+        }
+    }
+}
+
+impl Interpolate for Filter {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Filter {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            mode: snap(&a.mode, &b.mode, t),
+            resonance: lerp(a.resonance, b.resonance, t),
+            cutoff_frequency: lerp(a.cutoff_frequency, b.cutoff_frequency, t),
+            key_tracking: lerp(a.key_tracking, b.key_tracking, t),
+            envelope: Envelope::interpolate(&a.envelope, &b.envelope, t),
+            envelope_amount: lerp(a.envelope_amount, b.envelope_amount, t),
+            effect_mode: snap(&a.effect_mode, &b.effect_mode, t),
+            effect_enabled: snap(&a.effect_enabled, &b.effect_enabled, t),
+            effect_amount: lerp(a.effect_amount, b.effect_amount, t),
+        }
+    }
+}
+
+impl Interpolate for Unison {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Unison {
+            voices: snap(&a.voices, &b.voices, t),
+            detune: lerp(a.detune, b.detune, t),
+            spread: lerp(a.spread, b.spread, t),
+            mix: lerp(a.mix, b.mix, t),
+        }
+    }
+}
+
+impl Interpolate for Oscillator {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Oscillator {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            waveform: snap(&a.waveform, &b.waveform, t),
+            invert: snap(&a.invert, &b.invert, t),
+            pan: lerp(a.pan, b.pan, t),
+            phase: lerp(a.phase, b.phase, t),
+            pitch: lerp(a.pitch, b.pitch, t),
+            fine_tuning: snap(&a.fine_tuning, &b.fine_tuning, t),
+            semitone_tuning: snap(&a.semitone_tuning, &b.semitone_tuning, t),
+            octave_tuning: snap(&a.octave_tuning, &b.octave_tuning, t),
+            reverse: snap(&a.reverse, &b.reverse, t),
+            free_run: snap(&a.free_run, &b.free_run, t),
+            sync_all: snap(&a.sync_all, &b.sync_all, t),
+            volume: lerp(a.volume, b.volume, t),
+            unison: Unison::interpolate(&a.unison, &b.unison, t),
+            am_enabled: snap(&a.am_enabled, &b.am_enabled, t),
+            am_amount: lerp(a.am_amount, b.am_amount, t),
+            fm_enabled: snap(&a.fm_enabled, &b.fm_enabled, t),
+            fm_amount: lerp(a.fm_amount, b.fm_amount, t),
+            rm_enabled: snap(&a.rm_enabled, &b.rm_enabled, t),
+            rm_amount: lerp(a.rm_amount, b.rm_amount, t),
+        }
+    }
+}
+
+impl Interpolate for Noise {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Noise {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            color: snap(&a.color, &b.color, t),
+            width: lerp(a.width, b.width, t),
+            pan: lerp(a.pan, b.pan, t),
+            volume: lerp(a.volume, b.volume, t),
+        }
+    }
+}
+
+impl Interpolate for Lfo {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Lfo {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            waveform: snap(&a.waveform, &b.waveform, t),
+            sync: snap(&a.sync, &b.sync, t),
+            invert: snap(&a.invert, &b.invert, t),
+            reverse: snap(&a.reverse, &b.reverse, t),
+            mono: snap(&a.mono, &b.mono, t),
+            free_run: snap(&a.free_run, &b.free_run, t),
+            frequency: lerp(a.frequency, b.frequency, t),
+            phase: lerp(a.phase, b.phase, t),
+        }
+    }
+}
+
+impl Interpolate for ModulatorEnvelope {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        ModulatorEnvelope {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            envelope: Envelope::interpolate(&a.envelope, &b.envelope, t),
+            curve: lerp(a.curve, b.curve, t),
+        }
+    }
+}
+
+impl Interpolate for Vibrato {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Vibrato {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            attack: Time::interpolate(&a.attack, &b.attack, t),
+            delay: Time::interpolate(&a.delay, &b.delay, t),
+            frequency: Frequency::interpolate(&a.frequency, &b.frequency, t),
+        }
+    }
+}
+
+impl Interpolate for MatrixItem {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        MatrixItem {
+            source: snap(&a.source, &b.source, t),
+            target: snap(&a.target, &b.target, t),
+            amount: lerp(a.amount, b.amount, t),
+        }
+    }
+}
+
+impl Interpolate for Tuning {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        let mut tunings = [0.0; 12];
+        for (index, tuning) in tunings.iter_mut().enumerate() {
+            *tuning = lerp(a.tunings[index], b.tunings[index], t);
+        }
+        Tuning {
+            transpose: lerp(a.transpose, b.transpose, t),
+            root_key: snap(&a.root_key, &b.root_key, t),
+            scale: snap(&a.scale, &b.scale, t),
+            tunings,
+        }
+    }
+}
+
+impl Interpolate for Chorus {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Chorus {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            depth: lerp(a.depth, b.depth, t),
+            pre_delay: lerp(a.pre_delay, b.pre_delay, t),
+            ratio: lerp(a.ratio, b.ratio, t),
+            mix: lerp(a.mix, b.mix, t),
+        }
+    }
+}
+
+impl Interpolate for Delay {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        // Synced and unsynced times have different units so they can only be
+        // blended when both delays are the same.
+        let time = if a.sync == b.sync {
+            lerp(a.time, b.time, t)
+        } else {
+            snap(&a.time, &b.time, t)
+        };
+        Delay {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            ping_pong: snap(&a.ping_pong, &b.ping_pong, t),
+            feedback: lerp(a.feedback, b.feedback, t),
+            filter_mode: snap(&a.filter_mode, &b.filter_mode, t),
+            sync: snap(&a.sync, &b.sync, t),
+            time,
+            mix: lerp(a.mix, b.mix, t),
+        }
+    }
+}
+
+impl Interpolate for Distortion {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Distortion {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            gain: lerp(a.gain, b.gain, t),
+        }
+    }
+}
+
+impl Interpolate for Equalizer {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Equalizer {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            high_gain: Ratio::interpolate(&a.high_gain, &b.high_gain, t),
+            low_gain: Ratio::interpolate(&a.low_gain, &b.low_gain, t),
+            mid_gain: Ratio::interpolate(&a.mid_gain, &b.mid_gain, t),
+        }
+    }
+}
+
+impl Interpolate for LoFi {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        LoFi {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            bitrate: lerp(a.bitrate, b.bitrate, t),
+            sample_rate: lerp(a.sample_rate, b.sample_rate, t),
+            mix: lerp(a.mix, b.mix, t),
+        }
+    }
+}
+
+impl Interpolate for Reverb {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Reverb {
+            enabled: snap(&a.enabled, &b.enabled, t),
+            dampen: lerp(a.dampen, b.dampen, t),
+
+            // The filter is a choice from a list.
+            filter: snap(&a.filter, &b.filter, t),
+            room: lerp(a.room, b.room, t),
+            width: lerp(a.width, b.width, t),
+            mix: lerp(a.mix, b.mix, t),
+        }
+    }
+}
+
+impl Preset {
+    /// A blend of two presets for morphing between them, where `t` is from
+    /// 0.0 for preset `a` to 1.0 for preset `b`.
+    ///
+    /// Continuous parameters are interpolated linearly. Parameters that
+    /// can't be blended, such as switches, waveforms, whole numbers and the
+    /// effect order, are taken from `a` when `t` is less than 0.5 and from
+    /// `b` otherwise. The name and description switch at the midpoint too.
+    pub fn interpolate(a: &Preset, b: &Preset, t: f64) -> Preset {
+        let t = t.clamp(0.0, 1.0);
+        Preset {
+            name: snap(&a.name, &b.name, t),
+            description: snap(&a.description, &b.description, t),
+            metadata: snap(&a.metadata, &b.metadata, t),
+            master_volume_normalized: lerp(
+                a.master_volume_normalized,
+                b.master_volume_normalized,
+                t,
+            ),
+            polyphony: snap(&a.polyphony, &b.polyphony, t),
+            portamento_mode: snap(&a.portamento_mode, &b.portamento_mode, t),
+            midi_play_mode: snap(&a.midi_play_mode, &b.midi_play_mode, t),
+            glide: lerp(a.glide, b.glide, t),
+            velocity_curve: lerp(a.velocity_curve, b.velocity_curve, t),
+            key_track_curve: lerp(a.key_track_curve, b.key_track_curve, t),
+            pitch_bend_range: lerp(a.pitch_bend_range, b.pitch_bend_range, t),
+            limit_enabled: snap(&a.limit_enabled, &b.limit_enabled, t),
+            tuning: Tuning::interpolate(&a.tuning, &b.tuning, t),
+            pch: snap(&a.pch, &b.pch, t),
+            envelope: Envelope::interpolate(&a.envelope, &b.envelope, t),
+            envelope_curve: lerp(a.envelope_curve, b.envelope_curve, t),
+            filter: Filter::interpolate(&a.filter, &b.filter, t),
+            filter_envelope_curve: lerp(a.filter_envelope_curve, b.filter_envelope_curve, t),
+            oscillators: interpolate_all(&a.oscillators, &b.oscillators, t),
+            hard_sync: snap(&a.hard_sync, &b.hard_sync, t),
+            noise: Noise::interpolate(&a.noise, &b.noise, t),
+            lfos: interpolate_all(&a.lfos, &b.lfos, t),
+            mod_envelopes: interpolate_all(&a.mod_envelopes, &b.mod_envelopes, t),
+            vibrato: Vibrato::interpolate(&a.vibrato, &b.vibrato, t),
+            matrix: interpolate_all(&a.matrix, &b.matrix, t),
+            effect_order: snap(&a.effect_order, &b.effect_order, t),
+            extra_effect_order: snap(&a.extra_effect_order, &b.extra_effect_order, t),
+            chorus: Chorus::interpolate(&a.chorus, &b.chorus, t),
+            delay: Delay::interpolate(&a.delay, &b.delay, t),
+            distortion: Distortion::interpolate(&a.distortion, &b.distortion, t),
+            equalizer: Equalizer::interpolate(&a.equalizer, &b.equalizer, t),
+            effect_filter: Filter::interpolate(&a.effect_filter, &b.effect_filter, t),
+            lofi: LoFi::interpolate(&a.lofi, &b.lofi, t),
+            reverb: Reverb::interpolate(&a.reverb, &b.reverb, t),
+            unknown_params: snap(&a.unknown_params, &b.unknown_params, t),
+            unknown_attributes: snap(&a.unknown_attributes, &b.unknown_attributes, t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use approx::assert_relative_eq;
+
+    use crate::Preset;
+
+    #[test]
+    fn interpolate() {
+        let a = Preset::read_file(Path::new("tests").join("init-1.0.2.bab")).unwrap();
+        let mut b = Preset::read_file(Path::new("tests").join("envelopes-1.0.2.bab")).unwrap();
+        b.filter.cutoff_frequency = 50.0;
+        assert_relative_eq!(a.filter.cutoff_frequency, 100.0);
+
+        let start = Preset::interpolate(&a, &b, 0.0);
+        assert_eq!(format!("{:?}", start), format!("{:?}", a));
+
+        let end = Preset::interpolate(&a, &b, 1.0);
+        assert_eq!(format!("{:?}", end), format!("{:?}", b));
+
+        let middle = Preset::interpolate(&a, &b, 0.5);
+        assert_relative_eq!(middle.filter.cutoff_frequency, 75.0);
+        assert_eq!(middle.name, b.name);
+    }
+}
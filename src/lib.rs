@@ -15,11 +15,15 @@
 //! println!("Polyphony: {}", preset.polyphony);
 //! ```
 
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Formatter};
 use std::fs::File;
-use std::io::{BufReader, Error, ErrorKind};
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Error, Read};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use log::warn;
@@ -28,42 +32,343 @@ use serde_xml_rs::de::from_reader;
 use strum::IntoEnumIterator;
 use strum_macros::{AsRefStr, EnumIter};
 use uom::num::Zero;
-use uom::si::f64::{Ratio, Time};
+use uom::si::f64::{Frequency, Ratio, Time};
+use uom::si::frequency::hertz;
 use uom::si::ratio::percent;
 use uom::si::time::{millisecond, second};
 
+pub use builder::*;
+pub use diff::*;
 pub use effect::*;
-
+pub use error::*;
+pub use modulation::*;
+pub use note::*;
+#[cfg(feature = "rand")]
+pub use randomize::*;
+pub use summary::*;
+pub use validate::*;
+pub use visit::*;
+
+#[cfg(feature = "zip")]
+mod archive;
+mod builder;
+pub mod conversions;
+mod diff;
 mod effect;
-
-const MODULATION_MATRIX_SIZE: usize = 8;
+mod error;
+mod interpolate;
+mod midi;
+mod modulation;
+mod mts;
+mod note;
+#[cfg(feature = "rand")]
+mod randomize;
+#[cfg(feature = "serde")]
+mod serde_units;
+mod sfz;
+mod summary;
+mod validate;
+mod visit;
+mod write;
+
+/// Number of routes in the modulation matrix. Babylon only reads presets with
+/// exactly this many, including the unused ones.
+pub const MODULATION_MATRIX_SIZE: usize = 8;
+
+/// Lowest octave an oscillator can be tuned to.
+pub const OSCILLATOR_OCTAVE_MIN: i32 = -4;
+
+/// Highest octave an oscillator can be tuned to.
+pub const OSCILLATOR_OCTAVE_MAX: i32 = 4;
+
+/// Number of effect slots in the effect chain.
+const EFFECT_SLOT_COUNT: usize = 7;
+
+/// Frequency of the note beat frequencies are calculated for, A4 in hertz.
+const REFERENCE_FREQUENCY_HZ: f64 = 440.0;
+
+/// Gain added by the filter drive when fully driven, in decibels.
+const FILTER_DRIVE_MAX_DB: f64 = 12.0;
+
+/// Gain added by each step of the distortion gain as Babylon displays it, in
+/// decibels.
+const DISTORTION_DB_PER_GAIN: f64 = 2.0;
+
+/// Largest difference between a stored envelope curve value and the exact
+/// value of a curve, about half the spacing between the curves.
+const ENVELOPE_CURVE_TOLERANCE: f64 = 0.03;
+
+/// Prefix of the attributes that hold the effect type ID in each effect slot.
+const EFFECT_SLOT_PREFIX: &str = "FX_Order_";
+
+/// Separator between the stages of the effect chain and the signal flow.
+const CHAIN_SEPARATOR: &str = " -> ";
 
 /// The standard Preset Info text if the user does not change it.  It is treated as blank.
 const PRESET_INFO_DEFAULT: &str = "Preset Info";
 
+/// ID of the parameter for the color of the noise. No version of Babylon up
+/// to 1.0.4 has one, so the ID is an assumption that follows the other
+/// noise parameters. It is only written for colors other than white.
+const NOISE_COLOR_PARAM_ID: &str = "OSCColor_N";
+
+/// Identifiers of every parameter that is read from a preset.
+pub const KNOWN_PARAM_IDS: &[&str] = &[
+    "EnvAttack",
+    "AttCurveType",
+    "EnvDecay",
+    "DecCurveType",
+    "EnvSustain",
+    "EnvRelease",
+    "RelCurveType",
+    "TuneA",
+    "TuneASharp",
+    "TuneB",
+    "TuneC",
+    "TuneCSharp",
+    "TuneD",
+    "TuneDSharp",
+    "TuneE",
+    "TuneF",
+    "TuneFSharp",
+    "TuneG",
+    "TuneGSharp",
+    "Transpose",
+    "PCH",
+    "FilterEnvAttack",
+    "FilterAttCurveType",
+    "FilterEnvDecay",
+    "FilterDecCurveType",
+    "FilterEnvSustain",
+    "FilterEnvRelease",
+    "FilterRelCurveType",
+    "FilterSwitch",
+    "FilterType",
+    "FilterRes",
+    "FilterCut",
+    "FilterKey",
+    "FilterEnv",
+    "FilterDriveSwitch",
+    "FilterDriveType",
+    "FilterDrive",
+    "OSCSwitch_1",
+    "OSCWaveType_1",
+    "OSCInvert_1",
+    "OSCPan_1",
+    "OSCPhase_1",
+    "OSCPitch_1",
+    "OSCFine_1",
+    "OSCSemi_1",
+    "OSCOctave_1",
+    "OSCReverse_1",
+    "OSCFreeRun_1",
+    "OSCSyncAll_1",
+    "OSCVol_1",
+    "OSCNumVoice_1",
+    "OSCDetune_1",
+    "OSCSpread_1",
+    "OSCUniMix_1",
+    "OSCAMSwitch_1",
+    "OSCAM_1",
+    "OSCFMSwitch_1",
+    "OSCFM_1",
+    "OSCRMSwitch_1",
+    "OSCRM_1",
+    "OSCSwitch_2",
+    "OSCWaveType_2",
+    "OSCInvert_2",
+    "OSCPan_2",
+    "OSCPhase_2",
+    "OSCPitch_2",
+    "OSCFine_2",
+    "OSCSemi_2",
+    "OSCOctave_2",
+    "OSCReverse_2",
+    "OSCFreeRun_2",
+    "OSCSyncAll_2",
+    "OSCVol_2",
+    "OSCNumVoice_2",
+    "OSCDetune_2",
+    "OSCSpread_2",
+    "OSCUniMix_2",
+    "OSCAMSwitch_2",
+    "OSCAM_2",
+    "OSCFMSwitch_2",
+    "OSCFM_2",
+    "OSCRMSwitch_2",
+    "OSCRM_2",
+    "OSCSwitch_3",
+    "OSCWaveType_3",
+    "OSCInvert_3",
+    "OSCPan_3",
+    "OSCPhase_3",
+    "OSCPitch_3",
+    "OSCFine_3",
+    "OSCSemi_3",
+    "OSCOctave_3",
+    "OSCReverse_3",
+    "OSCFreeRun_3",
+    "OSCVol_3",
+    "OSCSwitch_N",
+    "OSCWidth_N",
+    "OSCPan_N",
+    "OSCVol_N",
+    NOISE_COLOR_PARAM_ID,
+    "LFOSwitch_1",
+    "LFOWaveType_1",
+    "LFOSync_1",
+    "LFOInvert_1",
+    "LFOReverse_1",
+    "LFOMono_1",
+    "LFOFreeRun_1",
+    "LFOFreq_1",
+    "LFOPhase_1",
+    "LFOSwitch_2",
+    "LFOWaveType_2",
+    "LFOSync_2",
+    "LFOInvert_2",
+    "LFOReverse_2",
+    "LFOMono_2",
+    "LFOFreeRun_2",
+    "LFOFreq_2",
+    "LFOPhase_2",
+    "ModEnvSwitch_1",
+    "ModEnvCurveType_1",
+    "ModEnvAttack_1",
+    "ModAttCurveType_1",
+    "ModEnvDecay_1",
+    "ModDecCurveType_1",
+    "ModEnvSustain_1",
+    "ModEnvRelease_1",
+    "ModRelCurveType_1",
+    "ModEnvSwitch_2",
+    "ModEnvCurveType_2",
+    "ModEnvAttack_2",
+    "ModAttCurveType_2",
+    "ModEnvDecay_2",
+    "ModDecCurveType_2",
+    "ModEnvSustain_2",
+    "ModEnvRelease_2",
+    "ModRelCurveType_2",
+    "VibSwitch",
+    "VibAttack",
+    "VibFrequency",
+    "VibDelay",
+    "MatrixSource_1",
+    "MatrixTarget_1",
+    "MatrixAmount_1",
+    "MatrixSource_2",
+    "MatrixTarget_2",
+    "MatrixAmount_2",
+    "MatrixSource_3",
+    "MatrixTarget_3",
+    "MatrixAmount_3",
+    "MatrixSource_4",
+    "MatrixTarget_4",
+    "MatrixAmount_4",
+    "MatrixSource_5",
+    "MatrixTarget_5",
+    "MatrixAmount_5",
+    "MatrixSource_6",
+    "MatrixTarget_6",
+    "MatrixAmount_6",
+    "MatrixSource_7",
+    "MatrixTarget_7",
+    "MatrixAmount_7",
+    "MatrixSource_8",
+    "MatrixTarget_8",
+    "MatrixAmount_8",
+    "ChorusSwitch",
+    "ChorusDepth",
+    "ChorusMix",
+    "ChorusPdelay",
+    "ChorusRatio",
+    "DelayLP",
+    "DelaySwitch",
+    "DelayMode",
+    "DelayFeed",
+    "DelaySync",
+    "DelayTime",
+    "DelayMix",
+    "DistSwitch",
+    "DistGain",
+    "EQSwitch",
+    "EQHigh",
+    "EQLow",
+    "EQMid",
+    "FXFilterSwitch",
+    "FXFilterType",
+    "FXFilterRes",
+    "FXFilterCut",
+    "LoFiSwitch",
+    "LoFiBitRate",
+    "LoFiSampleRate",
+    "LoFiMix",
+    "ReverbSwitch",
+    "ReverbDamp",
+    "ReverbRoom",
+    "ReverbLP",
+    "ReverbWidth",
+    "ReverbMix",
+    "MainVol",
+    "MaxVoices",
+    "PortaMode",
+    "MidiPlayMode",
+    "Glide",
+    "VeloCurve",
+    "KeyTrackCurve",
+    "PBRange",
+    "LimitSwitch",
+    "EnvCurveType",
+    "FilterEnvCurveType",
+    "OSCSync21",
+];
+
 /// ADSR-style envelope.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Envelope {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::milliseconds"))]
     pub attack: Time,
 
     #[doc(alias = "attack_slope")]
-    pub attack_curve: f64,
+    pub attack_curve: EnvelopeCurve,
 
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::milliseconds"))]
     pub decay: Time,
 
     #[doc(alias = "decay_slope")]
-    pub decay_falloff: f64,
+    pub decay_falloff: EnvelopeCurve,
 
     /// A percentage, not milliseconds
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::percent"))]
     pub sustain: Ratio,
 
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::milliseconds"))]
     pub release: Time,
 
     #[doc(alias = "release_slope")]
-    pub release_falloff: f64,
+    pub release_falloff: EnvelopeCurve,
+}
+
+impl Default for Envelope {
+    /// The amplifier envelope of a new preset.
+    fn default() -> Self {
+        Envelope {
+            attack: Time::new::<millisecond>(2.0),
+            attack_curve: EnvelopeCurve::Exponential1,
+            decay: Time::new::<millisecond>(150.0),
+            decay_falloff: EnvelopeCurve::Exponential1,
+            sustain: Ratio::new::<percent>(0.9),
+            release: Time::new::<millisecond>(4.0),
+            release_falloff: EnvelopeCurve::Exponential1,
+        }
+    }
 }
 
-#[derive(Debug)]
+/// Shape of a stage of an envelope.
+#[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EnvelopeCurve {
     Linear,
     Exponential1,
@@ -84,6 +389,27 @@ pub enum EnvelopeCurve {
 }
 
 impl EnvelopeCurve {
+    /// The curve with the stored value, the inverse of [`EnvelopeCurve::value`].
+    /// Stored values may be slightly different from the exact values so the
+    /// nearest curve is chosen, as long as it is within half the distance to
+    /// the next curve.
+    pub fn from_value(value: f64) -> Option<EnvelopeCurve> {
+        let curve = EnvelopeCurve::nearest(value);
+        ((curve.value() - value).abs() < ENVELOPE_CURVE_TOLERANCE).then_some(curve)
+    }
+
+    /// The curve with the stored value closest to the value, no matter how
+    /// far away it is.
+    pub fn nearest(value: f64) -> EnvelopeCurve {
+        EnvelopeCurve::iter()
+            .min_by(|a, b| {
+                let distance = |curve: &EnvelopeCurve| (curve.value() - value).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or(EnvelopeCurve::Linear)
+    }
+
+    /// The value stored in presets for the curve.
     pub fn value(self) -> f64 {
         use EnvelopeCurve::*;
         match self {
@@ -127,7 +453,8 @@ impl Display for EnvelopeCurve {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Lfo {
     pub enabled: bool,
     pub waveform: Waveform,
@@ -140,27 +467,284 @@ pub struct Lfo {
     pub phase: f64,
 }
 
-#[derive(Debug)]
+impl Default for Lfo {
+    fn default() -> Self {
+        Lfo {
+            enabled: false,
+            waveform: Waveform::Sine,
+            sync: true,
+            invert: false,
+            reverse: false,
+            mono: false,
+            free_run: false,
+            frequency: 0.35,
+            phase: 0.0,
+        }
+    }
+}
+
+impl Lfo {
+    /// How the LFO is timed, resolving the combinations of `sync`,
+    /// `free_run` and `mono` that can be stored but contradict each other.
+    ///
+    /// Syncing to the tempo takes precedence because the position in the
+    /// song then determines the phase, so free running has no effect. An
+    /// LFO that is not synced runs freely if either `free_run` or `mono` is
+    /// set, because a single mono LFO is shared by every voice and can't be
+    /// restarted by each note. Otherwise it restarts with each note.
+    pub fn effective_mode(&self) -> LfoMode {
+        if self.sync {
+            LfoMode::Synced
+        } else if self.free_run || self.mono {
+            LfoMode::FreeRunning
+        } else {
+            LfoMode::Retriggered
+        }
+    }
+
+    /// The rate of the LFO, which is a note division when synced to the
+    /// tempo and a frequency otherwise. Both are stored in `frequency`.
+    ///
+    /// The rate is approximate. The frequency comes from the provisional
+    /// curve of [`conversions::control_to_lfo_hz`], and only some of the
+    /// note divisions have been confirmed, see
+    /// [`conversions::sync_value_to_division`].
+    pub fn rate(&self) -> LfoRate {
+        if self.sync {
+            LfoRate::Division(conversions::sync_value_to_division(self.frequency))
+        } else {
+            LfoRate::Hz(conversions::control_to_lfo_hz(self.frequency))
+        }
+    }
+}
+
+/// Rate of an LFO. See [`Lfo::rate`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LfoRate {
+    /// Approximate cycles per second when not synced to the tempo. See
+    /// [`Lfo::rate`].
+    Hz(f64),
+
+    /// Length of a cycle when synced to the tempo.
+    Division(NoteDivision),
+}
+
+/// How the phase of an LFO is determined. See [`Lfo::effective_mode`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LfoMode {
+    /// Follows the tempo and position of the host.
+    Synced,
+
+    /// Runs continuously regardless of the notes played.
+    FreeRunning,
+
+    /// Restarts at the phase each time a note is played.
+    Retriggered,
+}
+
+/// Route in the modulation matrix.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MatrixItem {
-    pub source: u32,
-    pub target: u32,
+    pub source: ModSource,
+    pub target: ModTarget,
     pub amount: f64,
 }
 
-/// White noise generator.
-#[derive(Debug)]
+impl MatrixItem {
+    /// A route is unused if both its source and target are off.
+    pub fn is_unused(&self) -> bool {
+        self.source == ModSource::Off && self.target == ModTarget::Off
+    }
+}
+
+impl Default for MatrixItem {
+    /// An unused route.
+    fn default() -> Self {
+        MatrixItem {
+            source: ModSource::Off,
+            target: ModTarget::Off,
+            amount: 0.0,
+        }
+    }
+}
+
+/// Color of the noise generator.
+///
+/// Babylon up to 1.0.4 only has white noise. The discriminants are the IDs
+/// of the colors, see [`Noise::color`].
+#[derive(Copy, Clone, Debug, Default, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(u32)]
+pub enum NoiseColor {
+    #[default]
+    White,
+}
+
+impl Display for NoiseColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoiseColor::White => f.write_str("White"),
+        }
+    }
+}
+
+/// Noise generator.
+///
+/// Babylon up to 1.0.4 only has white noise and its presets have no
+/// parameter for the color, only the switch, width, pan and volume.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Noise {
     pub enabled: bool,
+
+    /// Read from an `OSCColor_N` parameter if a preset has one, which is an
+    /// assumption because no version of Babylon has written it yet.
+    pub color: NoiseColor,
+
+    /// Stereo width from 0.0 for mono to 1.0 for the full stereo field.
     pub width: f64,
     pub pan: f64,
     pub volume: f64,
 }
 
-impl Effect for Noise {}
+impl Default for Noise {
+    fn default() -> Self {
+        Noise {
+            enabled: false,
+            color: NoiseColor::White,
+            width: 1.0,
+            pan: 0.5,
+            volume: 0.32,
+        }
+    }
+}
+
+impl Noise {
+    /// Stereo width as a percentage, as displayed by Babylon.
+    pub fn width_percent(&self) -> f64 {
+        conversions::normalized_to_percent(self.width)
+    }
+}
+
+/// The noise generator is not in the effect chain, so it has no effect type.
+impl Effect for Noise {
+    fn name(&self) -> &'static str {
+        "Noise"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn is_audible(&self) -> bool {
+        self.enabled && self.volume > 0.0
+    }
+}
+
+/// Musical length of a time that is synced to the tempo.
+#[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+pub enum NoteDivision {
+    Whole,
+    WholeDotted,
+    WholeTriplet,
+    Half,
+    HalfDotted,
+    HalfTriplet,
+    Quarter,
+    QuarterDotted,
+    QuarterTriplet,
+    Eighth,
+    EighthDotted,
+    EighthTriplet,
+    Sixteenth,
+    SixteenthDotted,
+    SixteenthTriplet,
+    ThirtySecond,
+    ThirtySecondDotted,
+    ThirtySecondTriplet,
+}
+
+impl Display for NoteDivision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use NoteDivision::*;
+        let s = match self {
+            Whole => "1/1",
+            WholeDotted => "1/1D",
+            WholeTriplet => "1/1T",
+            Half => "1/2",
+            HalfDotted => "1/2D",
+            HalfTriplet => "1/2T",
+            Quarter => "1/4",
+            QuarterDotted => "1/4D",
+            QuarterTriplet => "1/4T",
+            Eighth => "1/8",
+            EighthDotted => "1/8D",
+            EighthTriplet => "1/8T",
+            Sixteenth => "1/16",
+            SixteenthDotted => "1/16D",
+            SixteenthTriplet => "1/16T",
+            ThirtySecond => "1/32",
+            ThirtySecondDotted => "1/32D",
+            ThirtySecondTriplet => "1/32T",
+        };
+        f.write_str(s)
+    }
+}
+
+impl NoteDivision {
+    /// The nearest division to a stored tempo synced value. See
+    /// [`conversions::sync_value_to_division`].
+    pub fn from_normalized(value: f64) -> NoteDivision {
+        conversions::sync_value_to_division(value)
+    }
+
+    /// The stored tempo synced value that selects the division.
+    pub fn to_normalized(&self) -> f64 {
+        conversions::division_to_sync_value(*self)
+    }
+
+    /// Length of the division in beats, where a beat is a quarter note.
+    /// Dotted divisions are half again as long and triplets are two thirds
+    /// as long.
+    pub fn beats(&self) -> f64 {
+        use NoteDivision::*;
+        let (beats, modifier) = match self {
+            Whole => (4.0, 1.0),
+            WholeDotted => (4.0, 1.5),
+            WholeTriplet => (4.0, 2.0 / 3.0),
+            Half => (2.0, 1.0),
+            HalfDotted => (2.0, 1.5),
+            HalfTriplet => (2.0, 2.0 / 3.0),
+            Quarter => (1.0, 1.0),
+            QuarterDotted => (1.0, 1.5),
+            QuarterTriplet => (1.0, 2.0 / 3.0),
+            Eighth => (0.5, 1.0),
+            EighthDotted => (0.5, 1.5),
+            EighthTriplet => (0.5, 2.0 / 3.0),
+            Sixteenth => (0.25, 1.0),
+            SixteenthDotted => (0.25, 1.5),
+            SixteenthTriplet => (0.25, 2.0 / 3.0),
+            ThirtySecond => (0.125, 1.0),
+            ThirtySecondDotted => (0.125, 1.5),
+            ThirtySecondTriplet => (0.125, 2.0 / 3.0),
+        };
+        beats * modifier
+    }
+
+    /// Length of the division in seconds at a tempo in beats per minute.
+    pub fn to_seconds(&self, bpm: f64) -> f64 {
+        self.beats() * 60.0 / bpm
+    }
+}
 
 /// The third oscillator doesn't have all the capabilities of the first two
-/// oscillators because the first two route to the third.
-#[derive(Debug)]
+/// oscillators because the first two route to the third. Babylon doesn't
+/// store its sync to all, unison, AM, FM or RM settings, so they have no
+/// effect and are left at their defaults. See
+/// [`Oscillator::supports_modulation`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Oscillator {
     pub enabled: bool,
     pub waveform: Waveform,
@@ -168,14 +752,25 @@ pub struct Oscillator {
     pub pan: f64,
     pub phase: f64,
 
+    /// Continuous offset in semitones.
     pub pitch: f64,
+
+    /// Offset in cents.
     pub fine_tuning: i32,
     pub semitone_tuning: i32,
     pub octave_tuning: i32,
 
     pub reverse: bool,
     pub free_run: bool,
+
+    /// The "Sync All" switch, stored as `OSCSyncAll`. Only the first two
+    /// oscillators have it. It is stored separately from
+    /// [`Preset::hard_sync`] and doesn't change it. See
+    /// [`Preset::sync_config`].
     pub sync_all: bool,
+
+    /// Volume from 0.0 to 1.0, stored as-is in the preset. New presets have
+    /// a volume of 0.5.
     pub volume: f64,
     pub unison: Unison,
 
@@ -192,8 +787,118 @@ pub struct Oscillator {
     pub rm_amount: f64,
 }
 
+impl Default for Oscillator {
+    /// A disabled oscillator, like the second and third oscillators of a new
+    /// preset.
+    fn default() -> Self {
+        Oscillator {
+            enabled: false,
+            waveform: Waveform::Sine,
+            invert: false,
+            pan: 0.5,
+            phase: 0.0,
+            pitch: 0.0,
+            fine_tuning: 0,
+            semitone_tuning: 0,
+            octave_tuning: 0,
+            reverse: false,
+            free_run: false,
+            sync_all: false,
+            volume: 0.5,
+            unison: Unison::default(),
+            am_enabled: false,
+            am_amount: 0.0,
+            fm_enabled: false,
+            fm_amount: 0.0,
+            rm_enabled: false,
+            rm_amount: 0.0,
+        }
+    }
+}
+
+impl Oscillator {
+    /// Whether the oscillator at the index, where zero is the first
+    /// oscillator, has the sync to all, unison, AM, FM and RM settings. Only
+    /// the first two oscillators do.
+    pub fn supports_modulation(index: usize) -> bool {
+        index < 2
+    }
+
+    /// Reset the settings that only the first two oscillators have to their
+    /// defaults.
+    pub(crate) fn reset_modulation(&mut self) {
+        let default = Oscillator::default();
+        self.sync_all = default.sync_all;
+        self.unison = default.unison;
+        self.am_enabled = default.am_enabled;
+        self.am_amount = default.am_amount;
+        self.fm_enabled = default.fm_enabled;
+        self.fm_amount = default.fm_amount;
+        self.rm_enabled = default.rm_enabled;
+        self.rm_amount = default.rm_amount;
+    }
+
+    /// Ratio of the frequency of the oscillator to the note being played.
+    pub fn frequency_ratio(&self) -> f64 {
+        self.tuning().frequency_ratio()
+    }
+
+    /// The combined offset of the pitch, octave, semitone and fine tuning
+    /// in cents.
+    pub fn total_cents(&self) -> f64 {
+        self.tuning().total_cents()
+    }
+
+    /// The combined offset of the pitch, octave, semitone and fine tuning
+    /// in semitones.
+    pub fn total_semitones(&self) -> f64 {
+        self.tuning().total_semitones()
+    }
+
+    /// The pitch, octave, semitone and fine tuning together.
+    pub fn tuning(&self) -> OscillatorTuning {
+        OscillatorTuning {
+            pitch: self.pitch,
+            octave: self.octave_tuning,
+            semitone: self.semitone_tuning,
+            fine: self.fine_tuning,
+        }
+    }
+}
+
+/// The tuning of an oscillator relative to the note being played.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OscillatorTuning {
+    /// Continuous offset in semitones.
+    pub pitch: f64,
+    pub octave: i32,
+    pub semitone: i32,
+
+    /// Offset in cents.
+    pub fine: i32,
+}
+
+impl OscillatorTuning {
+    /// The combined offset in cents.
+    pub fn total_cents(&self) -> f64 {
+        self.total_semitones() * 100.0
+    }
+
+    /// The combined offset in semitones.
+    pub fn total_semitones(&self) -> f64 {
+        self.pitch + (self.octave * 12) as f64 + self.semitone as f64 + self.fine as f64 / 100.0
+    }
+
+    /// Ratio of the frequency to the note being played, so 2.0 is an octave
+    /// higher.
+    pub fn frequency_ratio(&self) -> f64 {
+        2.0_f64.powf(self.total_semitones() / 12.0)
+    }
+}
+
 /// The discriminants of the items match the file format.
 #[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u32)]
 pub enum MidiPlayMode {
     Normal,
@@ -205,23 +910,49 @@ pub enum MidiPlayMode {
     Cheat2,
 }
 
-impl MidiPlayMode {
-    fn from_or(mode_id: u32, default: MidiPlayMode) -> MidiPlayMode {
-        MidiPlayMode::iter()
-            .find(|id| *id as u32 == mode_id)
-            .unwrap_or(default)
+impl Display for MidiPlayMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use MidiPlayMode::*;
+        let s = match self {
+            Normal => "Normal",
+            Cheat1 => "Mute Off-Key",
+            Cheat2 => "Replace Off-Key",
+        };
+        f.write_str(s)
     }
 }
 
-#[derive(Debug)]
+/// Envelope in the modulation matrix.
+///
+/// Babylon 1.0.2 does not always save the decay and release curves of the
+/// second modulator envelope, storing E1 instead of the curves that were
+/// chosen. The curves are read as they are stored because a stored E1 can't
+/// be told apart from a chosen one.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ModulatorEnvelope {
     pub enabled: bool,
     pub envelope: Envelope,
     pub curve: f64,
 }
 
+impl Default for ModulatorEnvelope {
+    fn default() -> Self {
+        ModulatorEnvelope {
+            enabled: false,
+            envelope: Envelope {
+                attack: Time::new::<millisecond>(1.0),
+                release: Time::new::<millisecond>(1.0),
+                ..Envelope::default()
+            },
+            curve: 0.14,
+        }
+    }
+}
+
 /// The discriminants of the items match the file format.
 #[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u32)]
 pub enum PortamentoMode {
     Poly,
@@ -231,33 +962,170 @@ pub enum PortamentoMode {
     PortaPoly,
 }
 
-impl PortamentoMode {
-    fn from_or(mode_id: u32, default: PortamentoMode) -> PortamentoMode {
+impl FromStr for PortamentoMode {
+    type Err = BabylonError;
+
+    /// Parse the name of a portamento mode as it is displayed, ignoring case
+    /// and surrounding whitespace.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let trimmed = name.trim();
         PortamentoMode::iter()
-            .find(|id| *id as u32 == mode_id)
-            .unwrap_or(default)
+            .find(|mode| mode.to_string().eq_ignore_ascii_case(trimmed))
+            .ok_or_else(|| BabylonError::UnknownPortamentoMode(name.to_string()))
+    }
+}
+
+impl Display for PortamentoMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use PortamentoMode::*;
+        let s = match self {
+            Poly => "Poly",
+            Legato => "Legato",
+            LegatoNoRetrigger => "Legato (No Retrigger)",
+            Porta => "Porta",
+            PortaPoly => "Porta Poly",
+        };
+        f.write_str(s)
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Tuning {
     pub transpose: f64,
+
+    /// MIDI note number of the root key of the scale. See
+    /// [`Tuning::root_key_name`].
     pub root_key: u32,
-    pub scale: u32,
+    pub scale: Scale,
 
     /// Octave of values starting at A natural.
     pub tunings: [f64; 12],
 }
 
-#[derive(Debug)]
+impl Tuning {
+    /// Whether every note is in twelve tone equal temperament, with no per
+    /// note offsets and a transpose of a whole number of semitones. Offsets
+    /// too small to hear, such as the ones in some factory presets, are
+    /// ignored.
+    pub fn is_equal_temperament(&self) -> bool {
+        const TOLERANCE: f64 = 0.0001;
+        self.tunings.iter().all(|tuning| tuning.abs() < TOLERANCE)
+            && (self.transpose - self.transpose.round()).abs() < TOLERANCE
+    }
+
+    /// Remove any microtuning by clearing the per note offsets and rounding
+    /// the transpose to the nearest semitone. Returns whether anything
+    /// changed.
+    pub fn snap_to_equal_temperament(&mut self) -> bool {
+        let transpose = self.transpose.round();
+        let changed = self.transpose != transpose || self.tunings.iter().any(|t| *t != 0.0);
+        self.transpose = transpose;
+        self.tunings = [0.0; 12];
+        changed
+    }
+}
+
+/// The scale selected for the tuning, from the `Scale` and `CustomScale`
+/// attributes of the preset.
+///
+/// Every preset seen so far stores 0 for both, which is the chromatic scale
+/// with the per note tunings applied. Only the chromatic scale and custom
+/// scales are identified. There are no presets on hand that use the named
+/// scales Babylon offers, so their stored values have not been matched to
+/// their names and they are kept as [`Scale::Other`] so they can be written
+/// back.
+///
+/// How the two attributes combine is an assumption, see
+/// [`Scale::from_attributes`]. Whatever they mean, both values are kept so
+/// every scale is written back unchanged.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Scale {
+    #[default]
+    Chromatic,
+
+    /// A scale Babylon provides that has not been identified, by its stored
+    /// value. It is displayed as "Scale" and the value, such as "Scale 5",
+    /// rather than by the name Babylon shows.
+    Other(u32),
+
+    /// A scale created by the user, by the value of `CustomScale`.
+    Custom {
+        /// The stored value of the `Scale` attribute. Which value selects
+        /// the custom scales is not known, so it is kept to be written back.
+        scale: u32,
+
+        /// The stored value of the `CustomScale` attribute, assumed to
+        /// identify which of the custom scales is selected. It is never 0,
+        /// see [`Scale::from_attributes`].
+        index: u32,
+    },
+}
+
+impl Scale {
+    /// The scale stored in the `Scale` and `CustomScale` attributes.
+    ///
+    /// The classification is an assumption because no preset on hand uses a
+    /// scale other than chromatic. Both attributes being 0 is the chromatic
+    /// scale. A `CustomScale` other than 0 is assumed to select a custom
+    /// scale, and 0 is assumed to mean no custom scale. If `CustomScale` is
+    /// instead an index starting from 0, the first custom scale is read as
+    /// [`Scale::Other`], which still writes it back unchanged.
+    pub fn from_attributes(scale: u32, custom_scale: u32) -> Self {
+        match (scale, custom_scale) {
+            (0, 0) => Scale::Chromatic,
+            (scale, 0) => Scale::Other(scale),
+            (scale, index) => Scale::Custom { scale, index },
+        }
+    }
+
+    /// The values of the `Scale` and `CustomScale` attributes, the inverse of
+    /// [`Scale::from_attributes`].
+    pub fn attributes(&self) -> (u32, u32) {
+        match *self {
+            Scale::Chromatic => (0, 0),
+            Scale::Other(scale) => (scale, 0),
+            Scale::Custom { scale, index } => (scale, index),
+        }
+    }
+}
+
+impl Display for Scale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scale::Chromatic => f.write_str("Chromatic"),
+            Scale::Other(scale) => write!(f, "Scale {}", scale),
+            Scale::Custom { index, .. } => write!(f, "Custom {}", index),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Vibrato {
     pub enabled: bool,
-    pub attack: f64,
-    pub delay: f64,
-    pub frequency: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::milliseconds"))]
+    pub attack: Time,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::milliseconds"))]
+    pub delay: Time,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::hertz"))]
+    pub frequency: Frequency,
+}
+
+impl Default for Vibrato {
+    fn default() -> Self {
+        Vibrato {
+            enabled: false,
+            attack: Time::new::<millisecond>(232.0),
+            delay: Time::new::<millisecond>(232.0),
+            frequency: Frequency::new::<hertz>(6.1),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Unison {
     /// The first voice is the original signal.
     pub voices: u32,
@@ -266,8 +1134,21 @@ pub struct Unison {
     pub mix: f64,
 }
 
+impl Default for Unison {
+    /// A single voice.
+    fn default() -> Self {
+        Unison {
+            voices: 1,
+            detune: 0.2,
+            spread: 0.5,
+            mix: 1.0,
+        }
+    }
+}
+
 /// The discriminants of the items match the file format.
 #[derive(AsRefStr, Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u32)]
 pub enum Waveform {
     Sine,
@@ -539,11 +1420,265 @@ pub enum Waveform {
     Duck3,
 }
 
+/// Adds conversions between the items of enumerations and the IDs that
+/// select them in preset files, which are their discriminants.
+macro_rules! impl_file_id {
+    ($($name:ident),+) => {
+        $(
+            impl $name {
+                /// The ID that selects the item in a preset file.
+                pub fn file_id(&self) -> u32 {
+                    *self as u32
+                }
+
+                /// The item selected by an ID in a preset file, if there is
+                /// one.
+                pub fn from_file_id(id: u32) -> Option<Self> {
+                    Self::iter().find(|item| item.file_id() == id)
+                }
+            }
+        )+
+    };
+}
+
+impl_file_id!(
+    EffectType,
+    FilterEffectMode,
+    FilterMode,
+    MidiPlayMode,
+    ModSource,
+    ModTarget,
+    NoiseColor,
+    PortamentoMode,
+    Waveform
+);
+
 impl Waveform {
-    fn from_or(waveform_id: u32, default: Waveform) -> Waveform {
+    /// The group the waveform belongs to. Every waveform is in exactly one
+    /// family.
+    pub fn family(&self) -> WaveformFamily {
+        use Waveform::*;
+        match self {
+            Sine | SineRoot1_5 | SineRoot2 | SineRoot3 | SineRoot4 | SinePower1_5 | SinePower2
+            | SinePower3 | SinePower4 | SineAm1 | SineAm2 | SineAm3 | SineAm4 | SineAm5 => {
+                WaveformFamily::Sine
+            }
+            SineFmA1 | SineFmA2 | SineFmA3 | SineFmA4 | SineFmA5 | SineFmA6 | SineFmB1
+            | SineFmB2 | SineFmB3 | SineFmB4 | SineFmB5 | SineFmC1 | SineFmC2 | SineFmC3
+            | SineFmC4 | SineFmC5 | SineFmC6 | SineFmC7 | SineFmC8 | SineFmD1 | SineFmD2
+            | SineFmD3 | SineFmD4 | SineFmD5 | SineFmD6 | SineFmD7 | SineFmD8 | SineFmD9
+            | SineFmD10 | SineFmD11 | SineFmD12 | SineFmD13 | SineFmD14 | SineFmD15 => {
+                WaveformFamily::SineFm
+            }
+            SineFmKick1 | SineFmKick2 | SineFmKick3 | SineFmKick4 | SineFmKick5 | SineFmKick6
+            | SineFmKick7 | SineFmKick8 | SineFmKick9 | SineFmKick10 | SineFmKick11
+            | SineFmKick12 => WaveformFamily::Kick,
+            Triangle | TriangleRoot2 | TriangleRoot3 | TriangleRoot4 | TriangleRoot5 => {
+                WaveformFamily::Triangle
+            }
+            Saw | SawPower1 | SawPower2 | SawSine1 | SawSine2 | SawSine3 | Saw2x => {
+                WaveformFamily::Saw
+            }
+            Square | SquareSmooth1 | SquareSmooth2 | SquareHalfRoot | SquareHalfRootPower
+            | SquarePower | SquareDoublePower1 | SquareDoublePower2 | SquareAttackPower
+            | SquareTristate1 | SquareTristate2 | SquareTristate3 | SquareTristate4
+            | SquareTristate5 | SquareTristate6 | SquareFm1 | SquareFm2 | SquareFm3 | SquareFm4
+            | SquareFm5 | SquareFm6 | SquareFm7 | SquareFm8 => WaveformFamily::Square,
+            Pulse1 | Pulse2 | Pulse3 | Pulse4 | PulseSquare | PulseSquareSmooth | PulseSmooth1
+            | PulseSmooth2 => WaveformFamily::Pulse,
+            Voice1 | Voice2 | Voice3 | Voice4 | Voice5 | Voice6 | Voice7 | Voice8 | Voice9
+            | Voice10 | Voice11 | Voice12 | Voice13 | Voice14 | Voice15 | Voice16 | Voice17
+            | Voice18 | Voice19 | Voice20 | Voice21 | Voice22 | Voice23 | Voice24 | Voice25
+            | Voice26 | Voice27 | Voice28 | Voice29 | Voice30 => WaveformFamily::Voice,
+            FormantA1 | FormantA2 | FormantA3 | FormantA4 | FormantA5 | FormantA6 | FormantA7
+            | FormantA8 | FormantB1 | FormantB2 | FormantB3 | FormantB4 | FormantB5 | FormantB6
+            | FormantB7 | FormantB8 => WaveformFamily::Formant,
+            SyntheticVoice1 | SyntheticVoice2 | SyntheticVoice3 | SyntheticVoice4
+            | SyntheticVoice5 | SyntheticVoice6 | SyntheticVoice7 | SyntheticVoice8
+            | SyntheticVoice9 | SyntheticVoice10 | SyntheticVoice11 | SyntheticVoice12
+            | SyntheticVoice13 | SyntheticVoice14 | SyntheticVoice15 | SyntheticVoice16
+            | SyntheticVoice17 | SyntheticVoice18 | SyntheticVoice19 | SyntheticVoice20
+            | SyntheticVoice21 | SyntheticVoice22 | SyntheticVoice23 | SyntheticVoice24
+            | SyntheticVoice25 | SyntheticVoice26 | SyntheticVoice27 | SyntheticVoice28
+            | SyntheticVoice29 => WaveformFamily::SyntheticVoice,
+            Organ1 | Organ2 | Organ3 | Organ4 | Organ5 | Organ6 | Organ7 | Organ8 | Organ9
+            | Organ10 | Organ11 | Organ12 | Organ13 | Organ14 | Organ15 | Organ16 | Organ17
+            | Organ18 | Organ19 | Organ20 | Organ21 | Organ22 | Organ23 => WaveformFamily::Organ,
+            EPiano1 | EPiano2 | EPiano3 | EPiano4 | Key1 | Key2 | Key3 | Rhode => {
+                WaveformFamily::Keys
+            }
+            DistGuitar1 | DistGuitar2 | Brass1 | Brass2 => WaveformFamily::Instrument,
+            Chip1 | Chip2 | Chip3 | Chip4 | Chip5 | Chip6 | Chip7 => WaveformFamily::Chip,
+            Gritty1 | Gritty2 | Gritty3 | Gritty4 | Gritty5 | Gritty6 => WaveformFamily::Gritty,
+            Dirty1A | Dirty1B | Dirty1C | Dirty2A | Dirty2B | Dirty2C | Dirty3A | Dirty3B
+            | Dirty3C | Dirty4A | Dirty4B | Dirty4C | Dirty5A | Dirty5B | Dirty5C | Dirty6A
+            | Dirty6B | Dirty6C | Dirty7A | Dirty7B | Dirty7C | Dirty8A | Dirty8B | Dirty8C => {
+                WaveformFamily::Dirty
+            }
+            Gate1 | Gate2 | Gate3 | Gate4 => WaveformFamily::Gate,
+            Duck1 | Duck2 | Duck3 => WaveformFamily::Duck,
+        }
+    }
+
+    /// Recordings of voices. Synthetic voices are in their own family.
+    pub fn is_voice(&self) -> bool {
+        self.family() == WaveformFamily::Voice
+    }
+
+    pub fn is_organ(&self) -> bool {
+        self.family() == WaveformFamily::Organ
+    }
+
+    pub fn is_formant(&self) -> bool {
+        self.family() == WaveformFamily::Formant
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.family() == WaveformFamily::Dirty
+    }
+
+    pub fn is_chip(&self) -> bool {
+        self.family() == WaveformFamily::Chip
+    }
+
+    /// Waveforms that are built from recordings, such as voices and organs,
+    /// rather than calculated.
+    pub fn is_sample_based(&self) -> bool {
+        (Waveform::Voice1 as u32..=Waveform::Dirty8C as u32).contains(&(*self as u32))
+    }
+
+    /// The relative strength of the harmonics of the waveform, for drawing
+    /// an approximate spectrum.
+    pub fn harmonic_profile(&self) -> HarmonicProfile {
+        use Waveform::*;
+        match self {
+            Sine => HarmonicProfile::Fundamental,
+            Triangle => HarmonicProfile::OddInverseSquare,
+            Saw | Saw2x => HarmonicProfile::AllInverse,
+            Square => HarmonicProfile::OddInverse,
+            Pulse1 | Pulse2 | Pulse3 | Pulse4 => HarmonicProfile::Pulse,
+            waveform if waveform.is_sample_based() => HarmonicProfile::Sampled,
+            _ => HarmonicProfile::Complex,
+        }
+    }
+}
+
+/// A group of related waveforms. See [`Waveform::family`].
+#[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+pub enum WaveformFamily {
+    /// Sine waves and sines shaped by roots, powers and amplitude modulation.
+    Sine,
+
+    /// Sine waves with frequency modulation.
+    SineFm,
+
+    /// Frequency modulated sines for kick drums.
+    Kick,
+
+    Triangle,
+
+    Saw,
+
+    Square,
+
+    Pulse,
+
+    /// Recordings of voices.
+    Voice,
+
+    /// Vowel-like formants.
+    Formant,
+
+    /// Synthesized voices.
+    SyntheticVoice,
+
+    Organ,
+
+    /// Electric pianos and other keyboards.
+    Keys,
+
+    /// Other instruments, such as guitars and brass.
+    Instrument,
+
+    /// Video game chip sounds.
+    Chip,
+
+    Gritty,
+
+    Dirty,
+
+    /// Rhythmic gating.
+    Gate,
+
+    /// Rhythmic ducking.
+    Duck,
+}
+
+impl Display for WaveformFamily {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use WaveformFamily::*;
+        let s = match self {
+            Sine => "Sine",
+            SineFm => "Sine FM",
+            Kick => "Kick",
+            Triangle => "Triangle",
+            Saw => "Saw",
+            Square => "Square",
+            Pulse => "Pulse",
+            Voice => "Voice",
+            Formant => "Formant",
+            SyntheticVoice => "Synthetic Voice",
+            Organ => "Organ",
+            Keys => "Keys",
+            Instrument => "Instrument",
+            Chip => "Chip",
+            Gritty => "Gritty",
+            Dirty => "Dirty",
+            Gate => "Gate",
+            Duck => "Duck",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Relative harmonic content of a waveform. See
+/// [`Waveform::harmonic_profile`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HarmonicProfile {
+    /// Only the fundamental, like a sine wave.
+    Fundamental,
+
+    /// Odd harmonics falling off at 1/n², like a triangle wave.
+    OddInverseSquare,
+
+    /// Every harmonic falling off at 1/n, like a saw wave.
+    AllInverse,
+
+    /// Odd harmonics falling off at 1/n, like a square wave.
+    OddInverse,
+
+    /// Harmonics falling off at 1/n with gaps that depend on the width of
+    /// the pulse.
+    Pulse,
+
+    /// Calculated by shaping, modulating or combining simpler waveforms, so
+    /// the harmonics don't follow a simple series.
+    Complex,
+
+    /// Built from a recording so the harmonics are not known.
+    Sampled,
+}
+
+impl FromStr for Waveform {
+    type Err = BabylonError;
+
+    /// Parse the name of a waveform as it is displayed, ignoring case and
+    /// surrounding whitespace.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let trimmed = name.trim();
         Waveform::iter()
-            .find(|id| *id as u32 == waveform_id)
-            .unwrap_or(default)
+            .find(|waveform| waveform.to_string().eq_ignore_ascii_case(trimmed))
+            .ok_or_else(|| BabylonError::UnknownWaveform(name.to_string()))
     }
 }
 
@@ -728,7 +1863,7 @@ impl Display for Waveform {
             SyntheticVoice26 => "Synthetic Voice 26",
             SyntheticVoice27 => "Synthetic Voice 27",
             SyntheticVoice28 => "Synthetic Voice 28",
-            SyntheticVoice29 => "Synthetic Voice 39",
+            SyntheticVoice29 => "Synthetic Voice 29",
             Organ1 => "Organ 1",
             Organ2 => "Organ 2",
             Organ3 => "Organ 3",
@@ -813,7 +1948,49 @@ impl Display for Waveform {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// How the stored value of a parameter relates to its knob, so the knob can
+/// be drawn from the right starting point.
+///
+/// | Parameters                                  | Scale                 |
+/// |---------------------------------------------|-----------------------|
+/// | Oscillator and noise pan                    | Bipolar, center 0.5   |
+/// | Equalizer gains                             | Bipolar, center 0.5   |
+/// | Oscillator pitch, fine, semitone and octave | Bipolar, center 0.0   |
+/// | Transpose and per note tuning               | Bipolar, center 0.0   |
+/// | Modulation matrix amounts                   | Bipolar, center 0.0   |
+/// | Everything else, including delay feedback   | Unipolar              |
+///
+/// Switches and selections such as waveforms are unipolar.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParamScale {
+    /// The knob starts at the lowest value.
+    Unipolar,
+
+    /// The knob starts at the center and turns both ways.
+    Bipolar { center: f64 },
+}
+
+impl ParamScale {
+    /// Scale of a parameter given its identifier, or `None` if the parameter
+    /// is not in [`KNOWN_PARAM_IDS`].
+    pub fn of(param_id: &str) -> Option<ParamScale> {
+        if !KNOWN_PARAM_IDS.contains(&param_id) {
+            return None;
+        }
+        let name = param_id.split('_').next().unwrap_or(param_id);
+        let scale = match name {
+            "OSCPan" | "EQHigh" | "EQLow" | "EQMid" => ParamScale::Bipolar { center: 0.5 },
+            "OSCPitch" | "OSCFine" | "OSCSemi" | "OSCOctave" | "Transpose" | "MatrixAmount" => {
+                ParamScale::Bipolar { center: 0.0 }
+            }
+            name if name.starts_with("Tune") => ParamScale::Bipolar { center: 0.0 },
+            _ => ParamScale::Unipolar,
+        };
+        Some(scale)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename = "PARAM")]
 pub struct Param {
     pub id: String,
@@ -825,92 +2002,153 @@ impl Param {
         self.value.as_ref().and_then(|v| v.parse::<T>().ok())
     }
 
-    /// Convert the value into a boolean. Babylon stores booleans as a floating
+    /// Convert the value into a `f64`, rejecting values such as `nan` and
+    /// `inf` that are not finite so they do not poison the model.
+    pub fn value_finite_f64(&self) -> Option<f64> {
+        self.value_into().filter(|v: &f64| v.is_finite())
+    }
+
+    /// Convert the value into a boolean. Babylon stores booleans as a floating
     /// point value (!) so traditional conversion methods don't work.
     fn value_bool(&self) -> Option<bool> {
-        self.value_into().map(|v: f64| (v - 1.0).abs() < 0.0000001)
+        self.value_finite_f64().map(|v| (v - 1.0).abs() < 0.0000001)
     }
 
     /// Convert the value into a `i32`. Babylon stores integers as a floating
     /// point values is several places.
     fn value_i32(&self) -> Option<i32> {
-        self.value_into().map(|v: f64| v as i32)
+        self.value_finite_f64().map(|v| v as i32)
     }
 
     fn value_u32(&self) -> Option<u32> {
-        self.value_into().map(|v: f64| v as u32)
+        self.value_finite_f64().map(|v| v as u32)
     }
 }
 
 /// The Babylon preset as it's stored in XML. This is converted to a [`Preset`].
-#[derive(Debug, Deserialize, Serialize)]
-struct PluginParamTree {
+///
+/// Created from a preset with [`Preset::to_param_tree`] so the raw
+/// parameters can be adjusted before the tree is written with
+/// [`PluginParamTree::write`].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PluginParamTree {
     // EnvLock, FilterLock, FXLock, PortamentoLock and TunerLock are not read because
     // they effect the next preset loaded in Babylon and not the current preset.  It is
-    // unclear why they would be stored in the preset file in the first place.
-    #[serde(rename = "Scale")]
-    scale: u32,
+    // unclear why they would be stored in the preset file in the first place. They are
+    // kept in the unknown attributes of the preset so they can be written back.
+    //
+    // Every attribute is optional so presets from versions of Babylon before 1.0.2,
+    // which may not have all of them, can still be read.
+    #[serde(rename = "Scale", default)]
+    pub scale: u32,
 
-    #[serde(rename = "CustomScale")]
-    custom_scale: u32,
+    #[serde(rename = "CustomScale", default)]
+    pub custom_scale: u32,
 
-    #[serde(rename = "Root")]
-    root_key: u32,
+    #[serde(rename = "Root", default)]
+    pub root_key: u32,
 
     /// The preset ID doesn't appear to have a logical use. The preset IDs
     /// in the factory presets don't seem to follow any pattern.
     #[serde(rename = "PresetID")]
-    preset_id: Option<i32>, // -1 appears in some
+    pub preset_id: Option<i32>, // -1 appears in some
 
     /// The preset folder doesn't appear to have a logical use. The folder
     /// numbers in the factory presets don't seem to follow any pattern.
     #[serde(rename = "PresetFolder")]
-    preset_folder: Option<u32>,
+    pub preset_folder: Option<u32>,
 
-    #[serde(rename = "PresetName")]
-    preset_name: String,
+    #[serde(rename = "PresetName", default)]
+    pub preset_name: String,
 
     #[serde(rename = "PresetInfo")]
-    preset_info: String,
-
-    #[serde(rename = "FX_Order_0")]
-    fx_order0: Option<u32>,
+    pub preset_info: Option<String>,
 
-    #[serde(rename = "FX_Order_1")]
-    fx_order1: Option<u32>,
+    // Babylon does not store the author, creation date or category yet. The
+    // names are guesses based on the naming of the other attributes.
+    #[serde(rename = "PresetAuthor")]
+    pub preset_author: Option<String>,
 
-    #[serde(rename = "FX_Order_2")]
-    fx_order2: Option<u32>,
+    #[serde(rename = "PresetDate")]
+    pub preset_date: Option<String>,
 
-    #[serde(rename = "FX_Order_3")]
-    fx_order3: Option<u32>,
+    #[serde(rename = "PresetCategory")]
+    pub preset_category: Option<String>,
 
-    #[serde(rename = "FX_Order_4")]
-    fx_order4: Option<u32>,
+    #[serde(rename = "PARAM", default)]
+    pub params: Vec<Param>,
 
-    #[serde(rename = "FX_Order_5")]
-    fx_order5: Option<u32>,
+    /// Attributes without a dedicated field, including the effect slots
+    /// `FX_Order_0`, `FX_Order_1`, etc.
+    #[serde(flatten)]
+    pub attributes: BTreeMap<String, String>,
+}
 
-    #[serde(rename = "FX_Order_6")]
-    fx_order6: Option<u32>,
+impl PluginParamTree {
+    /// Remove the effect slot attributes, returning the effect type ID in each
+    /// slot. Slots with values that are not numbers are ignored.
+    fn remove_effect_slots(&mut self) -> BTreeMap<usize, u32> {
+        let mut slots = BTreeMap::new();
+        self.attributes.retain(|name, value| {
+            let slot = name
+                .strip_prefix(EFFECT_SLOT_PREFIX)
+                .and_then(|index| index.parse::<usize>().ok());
+            match (slot, value.parse::<u32>()) {
+                (Some(slot), Ok(effect_type_id)) => {
+                    slots.insert(slot, effect_type_id);
+                    false
+                }
+                _ => true,
+            }
+        });
+        slots
+    }
+}
 
-    #[serde(rename = "PARAM", default)]
-    params: Vec<Param>,
+/// The parameters of a [`PluginParamTree`] indexed by their identifiers so
+/// each one can be removed without searching all of them. The parameters that
+/// were not removed are put back in the tree, in their original order, when
+/// the index is dropped.
+struct ParamIndex<'a> {
+    tree: &'a mut PluginParamTree,
+    params: Vec<Option<Param>>,
+
+    /// Positions in `params` of the parameters with each identifier, last
+    /// first so the first parameter with a repeated identifier is removed
+    /// first.
+    positions: HashMap<String, Vec<usize>>,
 }
 
-impl PluginParamTree {
+impl<'a> ParamIndex<'a> {
+    fn new(tree: &'a mut PluginParamTree) -> Self {
+        let params: Vec<Option<Param>> = tree.params.drain(..).map(Some).collect();
+        let mut positions: HashMap<String, Vec<usize>> = HashMap::with_capacity(params.len());
+        for (position, param) in params.iter().enumerate().rev() {
+            if let Some(param) = param {
+                positions
+                    .entry(param.id.clone())
+                    .or_default()
+                    .push(position);
+            }
+        }
+        Self {
+            tree,
+            params,
+            positions,
+        }
+    }
+
     /// Remove a parameter with the given identifier, returning it.
     fn remove(&mut self, id: &str) -> Option<Param> {
-        let index_result = self.params.iter().position(|param| param.id == id);
-        match index_result {
-            Some(index) => Some(self.params.remove(index)),
-            None => None,
-        }
+        let position = self.positions.get_mut(id)?.pop()?;
+        self.params[position].take()
     }
 
-    fn remove_or<T: FromStr>(&mut self, id: &str, default: T) -> T {
+    /// Remove a floating point parameter, using the default if the value is
+    /// not finite.
+    fn remove_f64_or(&mut self, id: &str, default: f64) -> f64 {
         match self.remove(id) {
-            Some(param) => param.value_into().unwrap_or(default),
+            Some(param) => param.value_finite_f64().unwrap_or(default),
             None => default,
         }
     }
@@ -922,19 +2160,24 @@ impl PluginParamTree {
         }
     }
 
+    /// Remove an envelope curve parameter. Values that are not exactly the
+    /// value of a curve are read as the nearest curve.
+    fn remove_envelope_curve_or(&mut self, id: &str, default: EnvelopeCurve) -> EnvelopeCurve {
+        EnvelopeCurve::nearest(self.remove_f64_or(id, default.value()))
+    }
+
     fn remove_milliseconds_or(&mut self, id: &str, default: f64) -> Time {
-        let millis: f64 = match self.remove(id) {
-            Some(param) => param.value_into().unwrap_or(default),
-            None => default,
-        };
+        let millis = self.remove_f64_or(id, default);
         Time::new::<millisecond>(millis)
     }
 
+    fn remove_hertz_or(&mut self, id: &str, default: f64) -> Frequency {
+        let hz = self.remove_f64_or(id, default);
+        Frequency::new::<hertz>(hz)
+    }
+
     fn remove_percent_or(&mut self, id: &str, default: f64) -> Ratio {
-        let pct: f64 = match self.remove(id) {
-            Some(param) => param.value_into().unwrap_or(default),
-            None => default,
-        };
+        let pct = self.remove_f64_or(id, default);
         Ratio::new::<percent>(pct)
     }
 
@@ -951,14 +2194,125 @@ impl PluginParamTree {
             None => default,
         }
     }
+
+    /// Remove a parameter that selects an item of an enumeration by the
+    /// discriminant of the item. If no item has the stored discriminant the
+    /// default is used and a warning is added.
+    fn remove_choice_or<E: Copy + IntoEnumIterator>(
+        &mut self,
+        id: &str,
+        default: E,
+        discriminant: fn(E) -> u32,
+        warnings: &mut Vec<Warning>,
+    ) -> E {
+        let value = self.remove_u32_or(id, discriminant(default));
+        E::iter()
+            .find(|item| discriminant(*item) == value)
+            .unwrap_or_else(|| {
+                warnings.push(Warning::UnknownChoice {
+                    id: id.to_string(),
+                    value,
+                });
+                default
+            })
+    }
+}
+
+impl Deref for ParamIndex<'_> {
+    type Target = PluginParamTree;
+
+    fn deref(&self) -> &Self::Target {
+        self.tree
+    }
+}
+
+impl DerefMut for ParamIndex<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.tree
+    }
+}
+
+impl Drop for ParamIndex<'_> {
+    fn drop(&mut self) {
+        self.tree.params = self.params.drain(..).flatten().collect();
+    }
+}
+
+/// Whether a description is blank or the placeholder Babylon uses for
+/// presets without one. Babylon versions differ in the case and whitespace
+/// of the placeholder.
+fn is_default_description(info: &str) -> bool {
+    let info = info.trim();
+    info.is_empty() || info.eq_ignore_ascii_case(PRESET_INFO_DEFAULT)
+}
+
+fn log_warnings(warnings: &[Warning], source: &str) {
+    for warning in warnings {
+        warn!("{} while reading {}", warning, source);
+    }
+}
+
+/// Encoding of the byte order mark some editors add to the start of a file.
+const BYTE_ORDER_MARK: &[u8] = "\u{feff}".as_bytes();
+
+/// Whether the XML of a preset that could not be read starts a parameter tree
+/// but never ends it.
+fn is_truncated(xml: &[u8]) -> bool {
+    let contains = |tag: &[u8]| xml.windows(tag.len()).any(|window| window == tag);
+    contains(b"<PluginParamTree") && !contains(b"</PluginParamTree>")
+}
+
+/// Paths of the presets in a directory, sorted. Files are considered presets
+/// if they have the `.bab` extension.
+fn preset_paths(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("bab"))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// How the oscillators of a preset are synced, with the settings that have no
+/// effect left out. See [`Preset::sync_config`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncConfig {
+    /// Oscillator 2 restarts whenever oscillator 1 does.
+    pub hard_sync: bool,
+
+    /// Indices of the enabled oscillators with [`Oscillator::sync_all`]
+    /// set, where zero is the first oscillator.
+    pub sync_all: Vec<usize>,
+}
+
+/// Information about a preset that does not affect the sound.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PresetMetadata {
+    pub author: Option<String>,
+
+    /// Date the preset was created, as stored in the preset.
+    pub created: Option<String>,
+
+    pub category: Option<String>,
 }
 
 // Converted from a `PluginParamTree` into a more usable model.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Preset {
     pub name: String,
     pub description: Option<String>,
 
+    /// The author, creation date and category. Presets saved by the current
+    /// versions of Babylon do not have any so it is usually `None`.
+    pub metadata: Option<PresetMetadata>,
+
     /// The master volume from 0.0 to 1.0. The value 0.0 maps to -inf dB,
     /// 0.5 maps to 0.0 dB and 1.0 maps to 10.0 dB.
     #[doc(alias = "main_volume")]
@@ -968,6 +2322,9 @@ pub struct Preset {
     pub portamento_mode: PortamentoMode,
     pub midi_play_mode: MidiPlayMode,
     pub glide: f64,
+
+    /// How strongly the velocity of notes affects their level, from 0.0 to
+    /// 1.0. See [`Preset::velocity_response`].
     pub velocity_curve: f64,
     pub key_track_curve: f64,
     pub pitch_bend_range: f64,
@@ -975,6 +2332,12 @@ pub struct Preset {
     /// Limit the output to 0 dB using soft clipping
     pub limit_enabled: bool,
     pub tuning: Tuning,
+
+    /// Raw value of the `PCH` parameter. What it does is not known and
+    /// presets store it without a value, so it is kept as-is and written
+    /// back unchanged.
+    pub pch: Option<String>,
+
     pub envelope: Envelope,
     pub envelope_curve: f64,
     pub filter: Filter,
@@ -984,6 +2347,9 @@ pub struct Preset {
     pub oscillators: Vec<Oscillator>,
 
     /// Sync oscillator 2 to oscillator 1.  Oscillator 2 resets when oscillator 1 does.
+    /// It is only audible when both are enabled, see
+    /// [`Preset::hard_sync_active`], and is independent of
+    /// [`Oscillator::sync_all`].
     pub hard_sync: bool,
     pub noise: Noise,
 
@@ -995,6 +2361,11 @@ pub struct Preset {
 
     // Effects
     pub effect_order: Vec<EffectType>,
+
+    /// Effect type IDs in effect slots after the ones this library
+    /// understands, such as from a newer version of Babylon.
+    pub extra_effect_order: Vec<u32>,
+
     pub chorus: Chorus,
     pub delay: Delay,
     pub distortion: Distortion,
@@ -1002,9 +2373,163 @@ pub struct Preset {
     pub effect_filter: Filter,
     pub lofi: LoFi,
     pub reverb: Reverb,
+
+    /// Parameters that are not recognized, such as ones from a newer version
+    /// of Babylon. They are kept so they are written back unchanged.
+    pub unknown_params: Vec<Param>,
+
+    /// Attributes of the preset that are not recognized, by name. These
+    /// include the lock settings, such as `EnvLock`, that Babylon stores in
+    /// presets but only apply to the next preset loaded.
+    pub unknown_attributes: BTreeMap<String, String>,
 }
 
 impl Preset {
+    /// The effect in the effect chain of the given type. The effect filter,
+    /// not the main filter, is the filter in the effect chain.
+    pub fn effect(&self, effect_type: EffectType) -> &dyn Effect {
+        match effect_type {
+            EffectType::Distortion => &self.distortion,
+            EffectType::LoFi => &self.lofi,
+            EffectType::Filter => &self.effect_filter,
+            EffectType::Chorus => &self.chorus,
+            EffectType::Equalizer => &self.equalizer,
+            EffectType::Delay => &self.delay,
+            EffectType::Reverb => &self.reverb,
+        }
+    }
+
+    /// Fingerprint of everything that affects the sound of the preset, so the
+    /// name and description are ignored. Presets with the same fingerprint
+    /// sound the same. Fingerprints may change between versions of this
+    /// library so they should not be stored.
+    pub fn fingerprint(&self) -> u64 {
+        // Destructured so new fields can't be overlooked.
+        let Preset {
+            name: _,
+            description: _,
+            metadata: _,
+            pch: _,
+            unknown_params: _,
+            unknown_attributes: _,
+            master_volume_normalized,
+            polyphony,
+            portamento_mode,
+            midi_play_mode,
+            glide,
+            velocity_curve,
+            key_track_curve,
+            pitch_bend_range,
+            limit_enabled,
+            tuning,
+            envelope,
+            envelope_curve,
+            filter,
+            filter_envelope_curve,
+            oscillators,
+            hard_sync,
+            noise,
+            lfos,
+            mod_envelopes,
+            vibrato,
+            matrix,
+            effect_order,
+            extra_effect_order,
+            chorus,
+            delay,
+            distortion,
+            equalizer,
+            effect_filter,
+            lofi,
+            reverb,
+        } = self;
+
+        // Floating point values can't be hashed directly so their debug
+        // representation is hashed instead.
+        let sound = format!(
+            "{:?}",
+            (
+                (
+                    master_volume_normalized,
+                    polyphony,
+                    portamento_mode,
+                    midi_play_mode,
+                    glide,
+                    velocity_curve,
+                    key_track_curve,
+                    pitch_bend_range,
+                ),
+                (
+                    limit_enabled,
+                    tuning,
+                    envelope,
+                    envelope_curve,
+                    filter,
+                    filter_envelope_curve,
+                ),
+                (oscillators, hard_sync, noise),
+                (lfos, mod_envelopes, vibrato, matrix),
+                (
+                    effect_order,
+                    extra_effect_order,
+                    chorus,
+                    delay,
+                    distortion,
+                    equalizer,
+                    effect_filter,
+                    lofi,
+                    reverb,
+                ),
+            )
+        );
+        let mut hasher = DefaultHasher::new();
+        sound.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Enable or disable the effect in the effect chain of the given type.
+    pub fn set_effect_enabled(&mut self, effect_type: EffectType, enabled: bool) {
+        match effect_type {
+            EffectType::Distortion => self.distortion.enabled = enabled,
+            EffectType::LoFi => self.lofi.enabled = enabled,
+            EffectType::Filter => self.effect_filter.enabled = enabled,
+            EffectType::Chorus => self.chorus.enabled = enabled,
+            EffectType::Equalizer => self.equalizer.enabled = enabled,
+            EffectType::Delay => self.delay.enabled = enabled,
+            EffectType::Reverb => self.reverb.enabled = enabled,
+        }
+    }
+
+    /// Iterate over the effects in the order they are processed.
+    pub fn effects(&self) -> Effects<'_> {
+        Effects {
+            preset: self,
+            effect_types: self.effect_order.iter(),
+        }
+    }
+
+    /// The effects in the order they are processed. The filter in the effect
+    /// chain is [`Preset::effect_filter`], not the main filter.
+    pub fn effects_in_order(&self) -> Vec<&dyn Effect> {
+        self.effects().map(|(_, effect)| effect).collect()
+    }
+
+    /// The effects that are enabled, in the order they are processed. The
+    /// filter in the effect chain is [`Preset::effect_filter`], so the main
+    /// filter being enabled does not matter.
+    pub fn enabled_effects(&self) -> impl Iterator<Item = (EffectType, &dyn Effect)> {
+        self.effects().filter(|(_, effect)| effect.is_enabled())
+    }
+
+    /// The effect types in the order they are processed, optionally leaving
+    /// out the effects that are disabled.
+    pub fn effects_in_order_filtered(&self, include_disabled: bool) -> Vec<EffectType> {
+        self.effects()
+            .filter(|(_, effect)| include_disabled || effect.is_enabled())
+            .map(|(effect_type, _)| effect_type)
+            .collect()
+    }
+
     /// Where in the effect order the effect type occurs.
     pub fn effect_position(&self, effect_type: EffectType) -> Option<u8> {
         self.effect_order
@@ -1013,80 +2538,556 @@ impl Preset {
             .map(|pos| pos as u8)
     }
 
-    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Preset, Error> {
+    /// Move an effect to a new position in the effect order, shifting the
+    /// effects in between. Returns `false` and leaves the order unchanged if
+    /// the effect is not in the order or the position is out of range.
+    pub fn move_effect(&mut self, effect_type: EffectType, to: usize) -> bool {
+        match self.effect_position(effect_type) {
+            Some(from) if to < self.effect_order.len() => {
+                let effect_type = self.effect_order.remove(from as usize);
+                self.effect_order.insert(to, effect_type);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Swap the positions of two effects in the effect order. Returns
+    /// `false` and leaves the order unchanged if either effect is not in the
+    /// order.
+    pub fn swap_effects(&mut self, a: EffectType, b: EffectType) -> bool {
+        match (self.effect_position(a), self.effect_position(b)) {
+            (Some(a), Some(b)) => {
+                self.effect_order.swap(a as usize, b as usize);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Shift the octave of a single oscillator, clamped to the range
+    /// Babylon supports. The first oscillator has index zero.
+    pub fn shift_oscillator_octave(
+        &mut self,
+        osc_index: usize,
+        octaves: i32,
+    ) -> Result<(), BabylonError> {
+        let oscillator = self
+            .oscillators
+            .get_mut(osc_index)
+            .ok_or(BabylonError::NoOscillator(osc_index))?;
+        oscillator.octave_tuning = oscillator
+            .octave_tuning
+            .saturating_add(octaves)
+            .clamp(OSCILLATOR_OCTAVE_MIN, OSCILLATOR_OCTAVE_MAX);
+        Ok(())
+    }
+
+    /// A rough estimate of the output level in decibels, to find presets that
+    /// will be very quiet or clip. It is calculated from the settings, not
+    /// measured, so it is only useful for comparing presets.
+    ///
+    /// The volumes of the enabled oscillators and the noise are converted to
+    /// amplitudes with the curve of the master volume and summed, as if every
+    /// source was in phase. The gains of each later stage are then added in
+    /// decibels:
+    ///
+    /// * The filter drive adds up to 12 dB when saturating, overdriving or
+    ///   distorting.
+    /// * The distortion adds 2 dB per step of gain.
+    /// * The equalizer adds the gain of its loudest band.
+    /// * The master volume adds its gain.
+    ///
+    /// Presets without any enabled sources are -inf dB.
+    pub fn estimated_output_gain_db(&self) -> f64 {
+        let mut volumes: Vec<f64> = self
+            .oscillators
+            .iter()
+            .filter(|oscillator| oscillator.enabled)
+            .map(|oscillator| oscillator.volume)
+            .collect();
+        if self.noise.enabled {
+            volumes.push(self.noise.volume);
+        }
+        let amplitude: f64 = volumes
+            .iter()
+            .map(|volume| 10.0_f64.powf(conversions::normalized_to_db(*volume) / 20.0))
+            .sum();
+        let mut gain_db = 20.0 * amplitude.log10();
+
+        let filter = &self.filter;
+        let driven = matches!(
+            filter.effect_mode,
+            FilterEffectMode::Saturation
+                | FilterEffectMode::Overdrive
+                | FilterEffectMode::Distortion
+        );
+        if filter.enabled && filter.effect_enabled && driven {
+            gain_db += filter.effect_amount.clamp(0.0, 1.0) * FILTER_DRIVE_MAX_DB;
+        }
+
+        if self.distortion.is_audible() {
+            gain_db += self.distortion.gain_ui() * DISTORTION_DB_PER_GAIN;
+        }
+
+        if self.equalizer.is_audible() {
+            let equalizer = &self.equalizer;
+            gain_db += [equalizer.low_db(), equalizer.mid_db(), equalizer.high_db()]
+                .into_iter()
+                .fold(f64::NEG_INFINITY, f64::max);
+        }
+
+        gain_db + self.master_volume_db()
+    }
+
+    /// Level of a note played at a velocity from 0.0 to 1.0, as a factor
+    /// from 0.0 to 1.0.
+    ///
+    /// The velocity curve bends the response, with 0.5 being linear, higher
+    /// values exaggerating the difference between soft and hard notes and
+    /// lower values reducing it. The exact curve Babylon uses has not been
+    /// measured, so this is an approximation.
+    pub fn velocity_response(&self, velocity: f64) -> f64 {
+        let exponent = 4.0_f64.powf(self.velocity_curve.clamp(0.0, 1.0) * 2.0 - 1.0);
+        velocity.clamp(0.0, 1.0).powf(exponent)
+    }
+
+    /// The main envelope as heard for a note played at a velocity from 0.0
+    /// to 1.0. Velocity scales the sustain level by the
+    /// [velocity response](Preset::velocity_response) and doesn't change
+    /// the times or curves.
+    pub fn envelope_at_velocity(&self, velocity: f64) -> Envelope {
+        let mut envelope = self.envelope.clone();
+        envelope.sustain *= self.velocity_response(velocity);
+        envelope
+    }
+
+    /// The enabled effects in the order they are processed, such as
+    /// `Dist -> Delay -> Reverb`. Empty if no effects are enabled.
+    pub fn effect_chain_string(&self) -> String {
+        let names: Vec<&str> = self
+            .enabled_effects()
+            .map(|(effect_type, _)| effect_type.short_name())
+            .collect();
+        names.join(CHAIN_SEPARATOR)
+    }
+
+    /// Summary of the path the sound takes through the preset, such as
+    /// `Oscillator 1 -> Filter -> Amplifier -> Reverb (off) -> Master volume`.
+    ///
+    /// The topology of Babylon is fixed. The enabled oscillators and the
+    /// noise are mixed into the filter, shaped by the amplifier envelope and
+    /// then pass through every effect in the effect order. Disabled effects
+    /// and filters are marked as off. There is no per oscillator or per
+    /// effect output routing.
+    pub fn signal_flow_description(&self) -> String {
+        let mut sources: Vec<String> = self
+            .oscillators
+            .iter()
+            .enumerate()
+            .filter(|(_, oscillator)| oscillator.enabled)
+            .map(|(index, _)| format!("Oscillator {}", index + 1))
+            .collect();
+        if self.noise.enabled {
+            sources.push("Noise".to_string());
+        }
+        if sources.is_empty() {
+            sources.push("Silence".to_string());
+        }
+
+        let mut stages = vec![sources.join(" + ")];
+        stages.push(Self::signal_flow_stage("Filter", self.filter.enabled));
+        stages.push("Amplifier".to_string());
+        for effect_type in &self.effect_order {
+            let enabled = self.effect(*effect_type).is_enabled();
+            stages.push(Self::signal_flow_stage(&effect_type.to_string(), enabled));
+        }
+        if self.limit_enabled {
+            stages.push("Limiter".to_string());
+        }
+        stages.push("Master volume".to_string());
+        stages.join(CHAIN_SEPARATOR)
+    }
+
+    fn signal_flow_stage(name: &str, enabled: bool) -> String {
+        if enabled {
+            name.to_string()
+        } else {
+            format!("{} (off)", name)
+        }
+    }
+
+    /// Rate in hertz that two oscillators beat against each other when A4 is
+    /// played. Small rates thicken the sound and zero means the oscillators
+    /// are in unison. `None` if either oscillator is disabled or does not
+    /// exist.
+    pub fn oscillator_beat_frequency(&self, a: usize, b: usize) -> Option<f64> {
+        let a = self.oscillators.get(a).filter(|osc| osc.enabled)?;
+        let b = self.oscillators.get(b).filter(|osc| osc.enabled)?;
+        Some(REFERENCE_FREQUENCY_HZ * (a.frequency_ratio() - b.frequency_ratio()).abs())
+    }
+
+    /// The distinct sample based waveforms used by the oscillators, in
+    /// oscillator order.
+    pub fn sampled_waveforms_used(&self) -> Vec<Waveform> {
+        let mut waveforms = Vec::new();
+        for oscillator in &self.oscillators {
+            let waveform = oscillator.waveform;
+            if waveform.is_sample_based() && !waveforms.contains(&waveform) {
+                waveforms.push(waveform);
+            }
+        }
+        waveforms
+    }
+
+    /// Hard sync is only audible when both of the oscillators it links are
+    /// enabled, so it is never active with fewer than two oscillators.
+    pub fn hard_sync_active(&self) -> bool {
+        self.hard_sync
+            && self.oscillators.len() >= 2
+            && self.oscillators[..2].iter().all(|osc| osc.enabled)
+    }
+
+    /// The syncing of the oscillators that is audible. Hard sync only
+    /// applies when both of the first two oscillators are enabled, and sync
+    /// to all is ignored on disabled oscillators and on the oscillators that
+    /// don't have it, see [`Oscillator::supports_modulation`].
+    pub fn sync_config(&self) -> SyncConfig {
+        let sync_all = self
+            .oscillators
+            .iter()
+            .enumerate()
+            .filter(|(index, oscillator)| {
+                Oscillator::supports_modulation(*index) && oscillator.enabled && oscillator.sync_all
+            })
+            .map(|(index, _)| index)
+            .collect();
+        SyncConfig {
+            hard_sync: self.hard_sync_active(),
+            sync_all,
+        }
+    }
+
+    /// Waveform of the loudest enabled oscillator, which represents the
+    /// character of the preset. If oscillators are equally loud the first one
+    /// wins. `None` if every oscillator is disabled.
+    pub fn primary_waveform(&self) -> Option<Waveform> {
+        let mut primary: Option<&Oscillator> = None;
+        for oscillator in self.oscillators.iter().filter(|osc| osc.enabled) {
+            if primary.is_none_or(|loudest| oscillator.volume > loudest.volume) {
+                primary = Some(oscillator);
+            }
+        }
+        primary.map(|oscillator| oscillator.waveform)
+    }
+
+    /// The preset with the effect of the given type enabled or disabled.
+    pub fn with_effect_enabled(mut self, effect_type: EffectType, enabled: bool) -> Preset {
+        self.set_effect_enabled(effect_type, enabled);
+        self
+    }
+
+    /// The preset with the master volume set in decibels.
+    pub fn with_master_volume_db(mut self, db: f64) -> Preset {
+        self.set_master_volume_db(db);
+        self
+    }
+
+    /// The master volume in decibels, from -inf dB to +10 dB. See
+    /// [`conversions::normalized_to_db`] for the curve.
+    pub fn master_volume_db(&self) -> f64 {
+        conversions::normalized_to_db(self.master_volume_normalized)
+    }
+
+    /// Set the master volume in decibels. Volumes louder than +10 dB are
+    /// clamped.
+    pub fn set_master_volume_db(&mut self, db: f64) {
+        self.master_volume_normalized = conversions::db_to_normalized(db);
+    }
+
+    /// The preset with a different name.
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Preset {
+        self.name = name.into();
+        self
+    }
+
+    /// The preset with a different number of voices.
+    pub fn with_polyphony(mut self, polyphony: u32) -> Preset {
+        self.polyphony = polyphony;
+        self
+    }
+
+    /// Value of a parameter that is not recognized, from
+    /// [`Preset::unknown_params`]. Parameters that are read into the preset,
+    /// which are listed in [`KNOWN_PARAM_IDS`], are never found. `None` if
+    /// there is no such parameter or it has no value.
+    pub fn get_raw_param(&self, id: &str) -> Option<&str> {
+        self.unknown_params
+            .iter()
+            .find(|param| param.id == id)
+            .and_then(|param| param.value.as_deref())
+    }
+
+    /// Set the value of a parameter that is not recognized so it is written
+    /// with the preset, adding it to [`Preset::unknown_params`] if it isn't
+    /// there. Parameters that are read into the preset, which are listed in
+    /// [`KNOWN_PARAM_IDS`], can't be set because they would be written twice.
+    pub fn set_raw_param(&mut self, id: &str, value: String) -> Result<(), BabylonError> {
+        if KNOWN_PARAM_IDS.contains(&id) {
+            return Err(BabylonError::KnownParam(id.to_string()));
+        }
+        match self.unknown_params.iter_mut().find(|param| param.id == id) {
+            Some(param) => param.value = Some(value),
+            None => self.unknown_params.push(Param {
+                id: id.to_string(),
+                value: Some(value),
+            }),
+        }
+        Ok(())
+    }
+
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Preset, BabylonError> {
+        let input = File::open(&path)?;
+        Preset::read_named(input, &path.as_ref().to_string_lossy())
+    }
+
+    /// Read a preset from a file, returning the problems found in it
+    /// instead of logging them. [`Preset::read_file`] logs the same warnings
+    /// with the `log` crate.
+    pub fn read_file_verbose<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(Preset, Vec<Warning>), BabylonError> {
         let input = File::open(&path)?;
-        let reader = BufReader::new(input);
+        Preset::read_named_verbose(input, &path.as_ref().to_string_lossy())
+    }
+
+    /// Read every preset in a directory, without descending into
+    /// subdirectories. Files are considered presets if they have the `.bab`
+    /// extension. Each path is returned with the result of reading it, in
+    /// order of the paths, so one preset that can't be read doesn't prevent
+    /// reading the rest. If the directory can't be listed the only result is
+    /// the directory with the error.
+    pub fn read_dir<P: AsRef<Path>>(dir: P) -> Vec<(PathBuf, Result<Preset, BabylonError>)> {
+        let dir = dir.as_ref();
+        let paths = match preset_paths(dir) {
+            Ok(paths) => paths,
+            Err(error) => return vec![(dir.to_path_buf(), Err(error.into()))],
+        };
+        paths
+            .into_iter()
+            .map(|path| {
+                let result = Preset::read_file(&path);
+                (path, result)
+            })
+            .collect()
+    }
 
-        let mut param_tree: PluginParamTree = match from_reader(reader) {
-            Ok(param_tree) => param_tree,
-            Err(error) => return Err(Error::new(ErrorKind::InvalidData, error)),
+    /// Read every preset in a directory like [`Preset::read_dir`], reading
+    /// the files on multiple threads. The results are in the same order as
+    /// [`Preset::read_dir`] regardless of the order the files are read.
+    #[cfg(feature = "rayon")]
+    pub fn read_dir_parallel<P: AsRef<Path>>(
+        dir: P,
+    ) -> Vec<(PathBuf, Result<Preset, BabylonError>)> {
+        use rayon::prelude::*;
+
+        let dir = dir.as_ref();
+        let paths = match preset_paths(dir) {
+            Ok(paths) => paths,
+            Err(error) => return vec![(dir.to_path_buf(), Err(error.into()))],
         };
+        paths
+            .into_par_iter()
+            .map(|path| {
+                let result = Preset::read_file(&path);
+                (path, result)
+            })
+            .collect()
+    }
+
+    /// Read a preset from any source, such as a buffer in memory or the body
+    /// of a network response. Prefer [`Preset::read_file`] for files so
+    /// the path is included in warnings and errors.
+    pub fn read<R: Read>(input: R) -> Result<Preset, BabylonError> {
+        Preset::read_named(input, "preset")
+    }
+
+    /// Read a preset, using the name of the source in warnings and errors.
+    fn read_named<R: Read>(input: R, source: &str) -> Result<Preset, BabylonError> {
+        let (preset, warnings) = Preset::read_named_verbose(input, source)?;
+        log_warnings(&warnings, source);
+        Ok(preset)
+    }
 
+    /// Read a preset from a file along with a copy of every parameter in the
+    /// file, in the order they appear, before any of them are converted.
+    /// This is useful for investigating parameters that are not understood
+    /// or are read unexpectedly. Warnings are logged like
+    /// [`Preset::read_file`]. The copy is only made by this method so other
+    /// reads do not use the memory.
+    pub fn read_file_with_raw<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(Preset, Vec<Param>), BabylonError> {
+        let input = File::open(&path)?;
+        let source = path.as_ref().to_string_lossy();
+        let param_tree = Preset::read_param_tree(input, &source)?;
+        let raw_params = param_tree.params.clone();
+        let (preset, warnings) = Preset::convert_param_tree(param_tree, &source)?;
+        log_warnings(&warnings, &source);
+        Ok((preset, raw_params))
+    }
+
+    /// Read a preset, returning the warnings instead of logging them.
+    fn read_named_verbose<R: Read>(
+        input: R,
+        source: &str,
+    ) -> Result<(Preset, Vec<Warning>), BabylonError> {
+        let param_tree = Preset::read_param_tree(input, source)?;
+        Preset::convert_param_tree(param_tree, source)
+    }
+
+    /// Read the parameter tree of a preset without converting it.
+    fn read_param_tree<R: Read>(input: R, source: &str) -> Result<PluginParamTree, BabylonError> {
+        let mut xml = Vec::new();
+        BufReader::new(input).read_to_end(&mut xml)?;
+        let content = xml.strip_prefix(BYTE_ORDER_MARK).unwrap_or(&xml);
+        if content.iter().all(u8::is_ascii_whitespace) {
+            return Err(BabylonError::Empty {
+                preset: source.to_string(),
+            });
+        }
+
+        from_reader(xml.as_slice()).map_err(|error| {
+            let preset = source.to_string();
+            if is_truncated(content) {
+                BabylonError::Truncated { preset, error }
+            } else {
+                BabylonError::Xml { preset, error }
+            }
+        })
+    }
+
+    /// Convert a parameter tree to a preset, keeping the parameters and
+    /// attributes that are not recognized and warning about them.
+    fn convert_param_tree(
+        mut param_tree: PluginParamTree,
+        source: &str,
+    ) -> Result<(Preset, Vec<Warning>), BabylonError> {
+        let mut warnings = Vec::new();
+        let mut preset = Preset::from_param_tree(&mut param_tree, source, &mut warnings)?;
+
+        warnings.extend(param_tree.params.iter().map(|param| Warning::UnknownParam {
+            id: param.id.clone(),
+            value: param.value.clone(),
+        }));
+        preset.unknown_params = param_tree.params;
+
+        warnings.extend(param_tree.attributes.iter().map(|(name, value)| {
+            Warning::UnknownAttribute {
+                name: name.clone(),
+                value: value.clone(),
+            }
+        }));
+        preset.unknown_attributes = param_tree.attributes;
+
+        Ok((preset, warnings))
+    }
+
+    /// Convert the parameter tree to a preset. The parameters that are used are
+    /// removed from the tree, leaving the ones that are not recognized. The
+    /// source is where the tree was read from, for errors. Values that are
+    /// not understood are replaced by defaults and added to the warnings.
+    fn from_param_tree(
+        tree: &mut PluginParamTree,
+        source: &str,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Preset, BabylonError> {
+        let param_tree = &mut ParamIndex::new(tree);
         let name = param_tree.preset_name.clone();
-        let description: String = param_tree.preset_info.clone();
-        let description = (description.as_str() != PRESET_INFO_DEFAULT).then_some(description);
+        let description = param_tree
+            .preset_info
+            .clone()
+            .filter(|info| !is_default_description(info));
+        let metadata = PresetMetadata {
+            author: param_tree.preset_author.clone(),
+            created: param_tree.preset_date.clone(),
+            category: param_tree.preset_category.clone(),
+        };
+        let has_metadata =
+            metadata.author.is_some() || metadata.created.is_some() || metadata.category.is_some();
+        let metadata = has_metadata.then_some(metadata);
 
         let envelope = Envelope {
             attack: param_tree.remove_milliseconds_or("EnvAttack", 2.0),
-            attack_curve: param_tree.remove_or("AttCurveType", 0.07),
+            attack_curve: param_tree
+                .remove_envelope_curve_or("AttCurveType", EnvelopeCurve::Exponential1),
             decay: param_tree.remove_milliseconds_or("EnvDecay", 150.0),
-            decay_falloff: param_tree.remove_or("DecCurveType", 0.07),
+            decay_falloff: param_tree
+                .remove_envelope_curve_or("DecCurveType", EnvelopeCurve::Exponential1),
             sustain: param_tree.remove_percent_or("EnvSustain", 0.9),
             release: param_tree.remove_milliseconds_or("EnvRelease", 4.0),
-            release_falloff: param_tree.remove_or("RelCurveType", 0.07),
+            release_falloff: param_tree
+                .remove_envelope_curve_or("RelCurveType", EnvelopeCurve::Exponential1),
         };
 
         let mut tunings = [0.0; 12];
-        tunings[0] = param_tree.remove_or("TuneA", 0.0);
-        tunings[1] = param_tree.remove_or("TuneASharp", 0.0);
-        tunings[2] = param_tree.remove_or("TuneB", 0.0);
-        tunings[3] = param_tree.remove_or("TuneC", 0.0);
-        tunings[4] = param_tree.remove_or("TuneCSharp", 0.0);
-        tunings[5] = param_tree.remove_or("TuneD", 0.0);
-        tunings[6] = param_tree.remove_or("TuneDSharp", 0.0);
-        tunings[7] = param_tree.remove_or("TuneE", 0.0);
-        tunings[8] = param_tree.remove_or("TuneF", 0.0);
-        tunings[9] = param_tree.remove_or("TuneFSharp", 0.0);
-        tunings[10] = param_tree.remove_or("TuneG", 0.0);
-        tunings[11] = param_tree.remove_or("TuneGSharp", 0.0);
+        tunings[0] = param_tree.remove_f64_or("TuneA", 0.0);
+        tunings[1] = param_tree.remove_f64_or("TuneASharp", 0.0);
+        tunings[2] = param_tree.remove_f64_or("TuneB", 0.0);
+        tunings[3] = param_tree.remove_f64_or("TuneC", 0.0);
+        tunings[4] = param_tree.remove_f64_or("TuneCSharp", 0.0);
+        tunings[5] = param_tree.remove_f64_or("TuneD", 0.0);
+        tunings[6] = param_tree.remove_f64_or("TuneDSharp", 0.0);
+        tunings[7] = param_tree.remove_f64_or("TuneE", 0.0);
+        tunings[8] = param_tree.remove_f64_or("TuneF", 0.0);
+        tunings[9] = param_tree.remove_f64_or("TuneFSharp", 0.0);
+        tunings[10] = param_tree.remove_f64_or("TuneG", 0.0);
+        tunings[11] = param_tree.remove_f64_or("TuneGSharp", 0.0);
         let tuning = Tuning {
-            transpose: param_tree.remove_or("Transpose", 0.0),
+            transpose: param_tree.remove_f64_or("Transpose", 0.0),
             root_key: param_tree.root_key,
-            scale: param_tree.scale,
+            scale: Scale::from_attributes(param_tree.scale, param_tree.custom_scale),
             tunings,
         };
 
         // No idea what this is for. There isn't any difference in the interface regardless
         // of the value. "PCH" is often short for "pitch".
-        let _ = param_tree.remove_or("PCH", 0.0);
+        let pch = param_tree.remove("PCH").and_then(|param| param.value);
 
         let filter_envelope = Envelope {
             attack: param_tree.remove_milliseconds_or("FilterEnvAttack", 2.0),
-            attack_curve: param_tree.remove_or("FilterAttCurveType", 0.07),
+            attack_curve: param_tree
+                .remove_envelope_curve_or("FilterAttCurveType", EnvelopeCurve::Exponential1),
             decay: param_tree.remove_milliseconds_or("FilterEnvDecay", 150.0),
-            decay_falloff: param_tree.remove_or("FilterDecCurveType", 0.07),
+            decay_falloff: param_tree
+                .remove_envelope_curve_or("FilterDecCurveType", EnvelopeCurve::Exponential1),
             sustain: param_tree.remove_percent_or("FilterEnvSustain", 0.02),
-            release: param_tree.remove_milliseconds_or("FilterEnvRelease", 23.0),
-            release_falloff: param_tree.remove_or("FilterRelCurveType", 0.07),
+            release: param_tree.remove_milliseconds_or("FilterEnvRelease", 4.0),
+            release_falloff: param_tree
+                .remove_envelope_curve_or("FilterRelCurveType", EnvelopeCurve::Exponential1),
         };
 
         let filter = Filter {
             enabled: param_tree.remove_bool_or("FilterSwitch", false),
-            mode: FilterMode::from_or(
-                param_tree.remove_u32_or("FilterType", FilterMode::LowPass as u32),
+            mode: param_tree.remove_choice_or(
+                "FilterType",
                 FilterMode::LowPass,
+                |item| item.file_id(),
+                warnings,
             ),
-            resonance: param_tree.remove_or("FilterRes", 0.0),
-            cutoff_frequency: param_tree.remove_or("FilterCut", 1.0) * 100.0,
-            key_tracking: param_tree.remove_or("FilterKey", 0.0),
+            resonance: param_tree.remove_f64_or("FilterRes", 0.0),
+            cutoff_frequency: param_tree.remove_f64_or("FilterCut", 1.0) * 100.0,
+            key_tracking: param_tree.remove_f64_or("FilterKey", 0.0),
             envelope: filter_envelope,
-            envelope_amount: param_tree.remove_or("FilterEnv", 0.0),
+            envelope_amount: param_tree.remove_f64_or("FilterEnv", 0.0),
             effect_enabled: param_tree.remove_bool_or("FilterDriveSwitch", false),
-            effect_mode: FilterEffectMode::from_or(
-                param_tree.remove_u32_or("FilterDriveType", FilterEffectMode::Off as u32),
+            effect_mode: param_tree.remove_choice_or(
+                "FilterDriveType",
                 FilterEffectMode::Off,
+                |item| item.file_id(),
+                warnings,
             ),
-            effect_amount: param_tree.remove_or("FilterDrive", 0.5),
+            effect_amount: param_tree.remove_f64_or("FilterDrive", 0.5),
         };
 
         //
@@ -1095,52 +3096,66 @@ impl Preset {
 
         let mut oscillators = Vec::new();
         for index in 1..=3 {
-            let oscillator = Oscillator {
-                enabled: param_tree.remove_bool_or(format!("OSCSwitch_{}", index).as_str(), true),
-                waveform: Waveform::from_or(
-                    param_tree.remove_u32_or(
-                        format!("OSCWaveType_{}", index).as_str(),
-                        Waveform::Sine as u32,
-                    ),
+            let mut oscillator = Oscillator {
+                enabled: param_tree
+                    .remove_bool_or(format!("OSCSwitch_{}", index).as_str(), index == 1),
+                waveform: param_tree.remove_choice_or(
+                    format!("OSCWaveType_{}", index).as_str(),
                     Waveform::Sine,
+                    |item| item.file_id(),
+                    warnings,
                 ),
                 invert: param_tree.remove_bool_or(format!("OSCInvert_{}", index).as_str(), false),
-                pan: param_tree.remove_or(format!("OSCPan_{}", index).as_str(), 0.5),
-                phase: param_tree.remove_or(format!("OSCPhase_{}", index).as_str(), 0.0),
-                pitch: param_tree.remove_or(format!("OSCPitch_{}", index).as_str(), 0.0),
+                pan: param_tree.remove_f64_or(format!("OSCPan_{}", index).as_str(), 0.5),
+                phase: param_tree.remove_f64_or(format!("OSCPhase_{}", index).as_str(), 0.0),
+                pitch: param_tree.remove_f64_or(format!("OSCPitch_{}", index).as_str(), 0.0),
                 fine_tuning: param_tree.remove_i32_or(format!("OSCFine_{}", index).as_str(), 0),
                 semitone_tuning: param_tree.remove_i32_or(format!("OSCSemi_{}", index).as_str(), 0),
                 octave_tuning: param_tree.remove_i32_or(format!("OSCOctave_{}", index).as_str(), 0),
                 reverse: param_tree.remove_bool_or(format!("OSCReverse_{}", index).as_str(), false),
                 free_run: param_tree
                     .remove_bool_or(format!("OSCFreeRun_{}", index).as_str(), false),
-                sync_all: param_tree
-                    .remove_bool_or(format!("OSCSyncAll_{}", index).as_str(), false),
-                volume: param_tree.remove_or(format!("OSCVol_{}", index).as_str(), 0.294),
-                unison: Unison {
-                    voices: param_tree.remove_u32_or(format!("OSCNumVoice_{}", index).as_str(), 1),
-                    detune: param_tree.remove_or(format!("OSCDetune_{}", index).as_str(), 0.2),
-                    spread: param_tree.remove_or(format!("OSCSpread_{}", index).as_str(), 0.5),
-                    mix: param_tree.remove_or(format!("OSCUniMix_{}", index).as_str(), 1.0),
-                },
-                am_enabled: param_tree
-                    .remove_bool_or(format!("OSCAMSwitch_{}", index).as_str(), false),
-                am_amount: param_tree.remove_or(format!("OSCAM_{}", index).as_str(), 0.0),
-                fm_enabled: param_tree
-                    .remove_bool_or(format!("OSCFMSwitch_{}", index).as_str(), false),
-                fm_amount: param_tree.remove_or(format!("OSCFM_{}", index).as_str(), 0.0),
-                rm_enabled: param_tree
-                    .remove_bool_or(format!("OSCRMSwitch_{}", index).as_str(), false),
-                rm_amount: param_tree.remove_or(format!("OSCRM_{}", index).as_str(), 0.0),
+                volume: param_tree.remove_f64_or(format!("OSCVol_{}", index).as_str(), 0.5),
+                ..Oscillator::default()
             };
+
+            // The third oscillator doesn't have these settings.
+            if Oscillator::supports_modulation(index - 1) {
+                oscillator.sync_all =
+                    param_tree.remove_bool_or(format!("OSCSyncAll_{}", index).as_str(), false);
+                oscillator.unison = Unison {
+                    voices: param_tree.remove_u32_or(format!("OSCNumVoice_{}", index).as_str(), 1),
+                    detune: param_tree.remove_f64_or(format!("OSCDetune_{}", index).as_str(), 0.2),
+                    spread: param_tree.remove_f64_or(format!("OSCSpread_{}", index).as_str(), 0.5),
+                    mix: param_tree.remove_f64_or(format!("OSCUniMix_{}", index).as_str(), 1.0),
+                };
+                oscillator.am_enabled =
+                    param_tree.remove_bool_or(format!("OSCAMSwitch_{}", index).as_str(), false);
+                oscillator.am_amount =
+                    param_tree.remove_f64_or(format!("OSCAM_{}", index).as_str(), 0.0);
+                oscillator.fm_enabled =
+                    param_tree.remove_bool_or(format!("OSCFMSwitch_{}", index).as_str(), false);
+                oscillator.fm_amount =
+                    param_tree.remove_f64_or(format!("OSCFM_{}", index).as_str(), 0.0);
+                oscillator.rm_enabled =
+                    param_tree.remove_bool_or(format!("OSCRMSwitch_{}", index).as_str(), false);
+                oscillator.rm_amount =
+                    param_tree.remove_f64_or(format!("OSCRM_{}", index).as_str(), 0.0);
+            }
             oscillators.push(oscillator);
         }
 
         let noise = Noise {
             enabled: param_tree.remove_bool_or("OSCSwitch_N", false),
-            width: param_tree.remove_or("OSCWidth_N", 1.0),
-            pan: param_tree.remove_or("OSCPan_N", 0.5),
-            volume: param_tree.remove_or("OSCVol_N", 0.32),
+            color: param_tree.remove_choice_or(
+                NOISE_COLOR_PARAM_ID,
+                NoiseColor::White,
+                |color| color.file_id(),
+                warnings,
+            ),
+            width: param_tree.remove_f64_or("OSCWidth_N", 1.0),
+            pan: param_tree.remove_f64_or("OSCPan_N", 0.5),
+            volume: param_tree.remove_f64_or("OSCVol_N", 0.32),
         };
 
         //
@@ -1149,83 +3164,102 @@ impl Preset {
 
         let lfo1 = Lfo {
             enabled: param_tree.remove_bool_or("LFOSwitch_1", false),
-            waveform: Waveform::from_or(
-                param_tree.remove_u32_or("LFOWaveType_1", Waveform::Sine as u32),
+            waveform: param_tree.remove_choice_or(
+                "LFOWaveType_1",
                 Waveform::Sine,
+                |item| item.file_id(),
+                warnings,
             ),
             sync: param_tree.remove_bool_or("LFOSync_1", true),
             invert: param_tree.remove_bool_or("LFOInvert_1", false),
             reverse: param_tree.remove_bool_or("LFOReverse_1", false),
             mono: param_tree.remove_bool_or("LFOMono_1", false),
             free_run: param_tree.remove_bool_or("LFOFreeRun_1", false),
-            frequency: param_tree.remove_or("LFOFreq_1", 0.35),
-            phase: param_tree.remove_or("LFOPhase_1", 0.0),
+            frequency: param_tree.remove_f64_or("LFOFreq_1", 0.35),
+            phase: param_tree.remove_f64_or("LFOPhase_1", 0.0),
         };
 
         let lfo2 = Lfo {
             enabled: param_tree.remove_bool_or("LFOSwitch_2", false),
-            waveform: Waveform::from_or(
-                param_tree.remove_u32_or("LFOWaveType_2", Waveform::Sine as u32),
+            waveform: param_tree.remove_choice_or(
+                "LFOWaveType_2",
                 Waveform::Sine,
+                |item| item.file_id(),
+                warnings,
             ),
             sync: param_tree.remove_bool_or("LFOSync_2", true),
             invert: param_tree.remove_bool_or("LFOInvert_2", false),
             reverse: param_tree.remove_bool_or("LFOReverse_2", false),
             mono: param_tree.remove_bool_or("LFOMono_2", false),
             free_run: param_tree.remove_bool_or("LFOFreeRun_2", false),
-            frequency: param_tree.remove_or("LFOFreq_2", 0.35),
-            phase: param_tree.remove_or("LFOPhase_2", 0.0),
+            frequency: param_tree.remove_f64_or("LFOFreq_2", 0.35),
+            phase: param_tree.remove_f64_or("LFOPhase_2", 0.0),
         };
 
         let lfos = vec![lfo1, lfo2];
 
         let mod_envelope1 = ModulatorEnvelope {
             enabled: param_tree.remove_bool_or("ModEnvSwitch_1", false),
-            curve: param_tree.remove_or("ModEnvCurveType_1", 0.14),
+            curve: param_tree.remove_f64_or("ModEnvCurveType_1", 0.14),
             envelope: Envelope {
                 attack: param_tree.remove_milliseconds_or("ModEnvAttack_1", 1.0),
-                attack_curve: param_tree.remove_or("ModAttCurveType_1", 0.07),
+                attack_curve: param_tree
+                    .remove_envelope_curve_or("ModAttCurveType_1", EnvelopeCurve::Exponential1),
                 decay: param_tree.remove_milliseconds_or("ModEnvDecay_1", 150.0),
-                decay_falloff: param_tree.remove_or("ModDecCurveType_1", 0.07),
-                sustain: param_tree.remove_percent_or("ModEnvSustain_1", 1.9),
+                decay_falloff: param_tree
+                    .remove_envelope_curve_or("ModDecCurveType_1", EnvelopeCurve::Exponential1),
+                sustain: param_tree.remove_percent_or("ModEnvSustain_1", 0.9),
                 release: param_tree.remove_milliseconds_or("ModEnvRelease_1", 1.0),
-                release_falloff: param_tree.remove_or("ModRelCurveType_1", 0.07),
+                release_falloff: param_tree
+                    .remove_envelope_curve_or("ModRelCurveType_1", EnvelopeCurve::Exponential1),
             },
         };
         let mod_envelope2 = ModulatorEnvelope {
             enabled: param_tree.remove_bool_or("ModEnvSwitch_2", false),
-            curve: param_tree.remove_or("ModEnvCurveType_2", 0.14),
+            curve: param_tree.remove_f64_or("ModEnvCurveType_2", 0.14),
             envelope: Envelope {
                 attack: param_tree.remove_milliseconds_or("ModEnvAttack_2", 1.0),
-                attack_curve: param_tree.remove_or("ModAttCurveType_2", 0.07),
+                attack_curve: param_tree
+                    .remove_envelope_curve_or("ModAttCurveType_2", EnvelopeCurve::Exponential1),
                 decay: param_tree.remove_milliseconds_or("ModEnvDecay_2", 150.0),
-                decay_falloff: param_tree.remove_or("ModDecCurveType_2", 0.07),
+                decay_falloff: param_tree
+                    .remove_envelope_curve_or("ModDecCurveType_2", EnvelopeCurve::Exponential1),
                 sustain: param_tree.remove_percent_or("ModEnvSustain_2", 0.9),
                 release: param_tree.remove_milliseconds_or("ModEnvRelease_2", 1.0),
-                release_falloff: param_tree.remove_or("ModRelCurveType_2", 0.07),
+                release_falloff: param_tree
+                    .remove_envelope_curve_or("ModRelCurveType_2", EnvelopeCurve::Exponential1),
             },
         };
         let mod_envelopes = vec![mod_envelope1, mod_envelope2];
 
         let vibrato = Vibrato {
             enabled: param_tree.remove_bool_or("VibSwitch", false),
-            attack: param_tree.remove_or("VibAttack", 232.0),
-            frequency: param_tree.remove_or("VibFrequency", 6.1),
-            delay: param_tree.remove_or("VibDelay", 232.0),
+            attack: param_tree.remove_milliseconds_or("VibAttack", 232.0),
+            frequency: param_tree.remove_hertz_or("VibFrequency", 6.1),
+            delay: param_tree.remove_milliseconds_or("VibDelay", 232.0),
         };
 
         let mut matrix = Vec::new();
         for index in 1..=MODULATION_MATRIX_SIZE {
+            let (default_source, default_target) = if index == 1 {
+                (ModSource::Lfo1, ModTarget::FilterCutoff)
+            } else {
+                (ModSource::Off, ModTarget::Off)
+            };
             matrix.push(MatrixItem {
-                source: param_tree.remove_or(
+                source: param_tree.remove_choice_or(
                     format!("MatrixSource_{}", index).as_str(),
-                    if index == 1 { 7 } else { 0 },
+                    default_source,
+                    |item| item.file_id(),
+                    warnings,
                 ),
-                target: param_tree.remove_or(
+                target: param_tree.remove_choice_or(
                     format!("MatrixTarget_{}", index).as_str(),
-                    if index == 1 { 2 } else { 0 },
+                    default_target,
+                    |item| item.file_id(),
+                    warnings,
                 ),
-                amount: param_tree.remove_or(
+                amount: param_tree.remove_f64_or(
                     format!("MatrixAmount_{}", index).as_str(),
                     if index == 1 { 1.0 } else { 0.0 },
                 ),
@@ -1236,75 +3270,86 @@ impl Preset {
         // Effects
         //
 
-        let effect_type_ids = [
-            param_tree.fx_order0.unwrap_or(0),
-            param_tree.fx_order1.unwrap_or(1),
-            param_tree.fx_order2.unwrap_or(2),
-            param_tree.fx_order3.unwrap_or(3),
-            param_tree.fx_order4.unwrap_or(4),
-            param_tree.fx_order5.unwrap_or(5),
-            param_tree.fx_order6.unwrap_or(6),
-        ];
-        let mut effect_order = Vec::with_capacity(effect_type_ids.len());
-        for effect_type_id in effect_type_ids.iter() {
-            match EffectType::try_from(*effect_type_id) {
+        let mut effect_slots = param_tree.remove_effect_slots();
+        let mut effect_order = Vec::with_capacity(EFFECT_SLOT_COUNT);
+        for slot in 0..EFFECT_SLOT_COUNT {
+            let effect_type_id = effect_slots.remove(&slot).unwrap_or(slot as u32);
+            match EffectType::try_from(effect_type_id) {
                 Ok(effect) => effect_order.push(effect),
-                Err(msg) => return Err(Error::new(ErrorKind::InvalidData, msg)),
+                Err(_) => {
+                    return Err(BabylonError::UnknownEffectType {
+                        preset: source.to_string(),
+                        attribute: format!("{}{}", EFFECT_SLOT_PREFIX, slot),
+                        effect_type_id,
+                    })
+                }
             }
         }
 
+        // Slots beyond the ones Babylon is known to have may be from a newer
+        // version. They are kept so they are not lost.
+        let extra_effect_order = effect_slots.into_values().collect();
+
         let chorus = Chorus {
             enabled: param_tree.remove_bool_or("ChorusSwitch", false),
-            depth: param_tree.remove_or("ChorusDepth", 0.5),
-            mix: param_tree.remove_or("ChorusMix", 0.5),
-            pre_delay: param_tree.remove_or("ChorusPdelay", 0.5),
-            ratio: param_tree.remove_or("ChorusRatio", 0.5),
+            depth: param_tree.remove_f64_or("ChorusDepth", 0.5),
+            mix: param_tree.remove_f64_or("ChorusMix", 0.5),
+            pre_delay: param_tree.remove_f64_or("ChorusPdelay", 0.5),
+            ratio: param_tree.remove_f64_or("ChorusRatio", 0.5),
         };
 
-        let delay_filter_mode_float: f64 = param_tree.remove_or("DelayLP", 0.0);
-        let delay_filter_mode = DelayFilterMode::from_or(
-            (delay_filter_mode_float * 1000.0) as u32,
-            DelayFilterMode::Off,
-        );
+        // The filter mode is stored as the position of the mode in the list.
+        // Positions that are not in the list turn the filter off.
+        let delay_filter = param_tree.remove_f64_or("DelayLP", 0.0);
+        let delay_filter_mode = conversions::control_to_delay_filter_mode(delay_filter);
+        let delay_filter_id = (delay_filter * 1000.0).round() as u32;
+        if delay_filter_id != delay_filter_mode as u32 {
+            warnings.push(Warning::UnknownChoice {
+                id: "DelayLP".to_string(),
+                value: delay_filter_id,
+            });
+        }
         let delay = Delay {
             enabled: param_tree.remove_bool_or("DelaySwitch", false),
             ping_pong: param_tree.remove_bool_or("DelayMode", false),
-            feedback: param_tree.remove_or("DelayFeed", 0.3),
+            feedback: param_tree.remove_f64_or("DelayFeed", 0.3),
             filter_mode: delay_filter_mode,
             sync: param_tree.remove_bool_or("DelaySync", true),
-            time: param_tree.remove_or("DelayTime", 0.17),
-            mix: param_tree.remove_or("DelayMix", 0.2),
+            time: param_tree.remove_f64_or("DelayTime", 0.17),
+            mix: param_tree.remove_f64_or("DelayMix", 0.2),
         };
 
         let distortion = Distortion {
             enabled: param_tree.remove_bool_or("DistSwitch", false),
-            gain: param_tree.remove_or("DistGain", 0.2),
+            gain: param_tree.remove_f64_or("DistGain", 0.2),
         };
 
         let equalizer = Equalizer {
             enabled: param_tree.remove_bool_or("EQSwitch", false),
-            high_gain: param_tree.remove_or("EQHigh", Ratio::new::<percent>(0.5)),
-            low_gain: param_tree.remove_or("EQLow", Ratio::new::<percent>(0.5)),
-            mid_gain: param_tree.remove_or("EQMid", Ratio::new::<percent>(0.5)),
+            high_gain: param_tree.remove_percent_or("EQHigh", 0.5),
+            low_gain: param_tree.remove_percent_or("EQLow", 0.5),
+            mid_gain: param_tree.remove_percent_or("EQMid", 0.5),
         };
 
         let effect_filter = Filter {
             enabled: param_tree.remove_bool_or("FXFilterSwitch", false),
-            mode: FilterMode::from_or(
-                param_tree.remove_u32_or("FXFilterType", FilterMode::LowPass as u32),
+            mode: param_tree.remove_choice_or(
+                "FXFilterType",
                 FilterMode::LowPass,
+                |item| item.file_id(),
+                warnings,
             ),
-            resonance: param_tree.remove_or("FXFilterRes", 0.0),
-            cutoff_frequency: param_tree.remove_or("FXFilterCut", 1.0),
+            resonance: param_tree.remove_f64_or("FXFilterRes", 0.1),
+            cutoff_frequency: param_tree.remove_f64_or("FXFilterCut", 0.5) * 100.0,
             key_tracking: 0.0,
             envelope: Envelope {
                 attack: Time::new::<second>(-1.01),
-                attack_curve: -1.0,
+                attack_curve: EnvelopeCurve::Linear,
                 decay: Time::new::<second>(-1.1),
-                decay_falloff: -1.0,
+                decay_falloff: EnvelopeCurve::Linear,
                 sustain: Ratio::zero(),
                 release: Time::new::<second>(-1.1),
-                release_falloff: -1.0,
+                release_falloff: EnvelopeCurve::Linear,
             },
             envelope_amount: 1.0,
             effect_enabled: false,
@@ -1314,43 +3359,49 @@ impl Preset {
 
         let lofi = LoFi {
             enabled: param_tree.remove_bool_or("LoFiSwitch", false),
-            bitrate: param_tree.remove_or("LoFiBitRate", 1.0),
-            sample_rate: param_tree.remove_or("LoFiSampleRate", 1.0),
-            mix: param_tree.remove_or("LoFiMix", 1.0),
+            bitrate: param_tree.remove_f64_or("LoFiBitRate", 1.0),
+            sample_rate: param_tree.remove_f64_or("LoFiSampleRate", 1.0),
+            mix: param_tree.remove_f64_or("LoFiMix", 1.0),
         };
 
         let reverb = Reverb {
             enabled: param_tree.remove_bool_or("ReverbSwitch", false),
-            dampen: param_tree.remove_or("ReverbDamp", 0.3),
-            room: param_tree.remove_or("ReverbRoom", 0.3),
-            filter: param_tree.remove_or("ReverbLP", 0.0),
-            width: param_tree.remove_or("ReverbWidth", 0.8),
-            mix: param_tree.remove_or("ReverbMix", 0.2),
+            dampen: param_tree.remove_f64_or("ReverbDamp", 0.3),
+            room: param_tree.remove_f64_or("ReverbRoom", 0.3),
+            filter: param_tree.remove_f64_or("ReverbLP", 0.0),
+            width: param_tree.remove_f64_or("ReverbWidth", 0.8),
+            mix: param_tree.remove_f64_or("ReverbMix", 0.2),
         };
 
         let preset = Preset {
             name,
             description,
-            master_volume_normalized: param_tree.remove_or("MainVol", 0.0),
-            polyphony: param_tree.remove_or("MaxVoices", 8),
-            portamento_mode: PortamentoMode::from_or(
-                param_tree.remove_u32_or("PortaMode", PortamentoMode::Poly as u32),
+            metadata,
+            master_volume_normalized: param_tree.remove_f64_or("MainVol", 0.5),
+            polyphony: param_tree.remove_u32_or("MaxVoices", 8),
+            portamento_mode: param_tree.remove_choice_or(
+                "PortaMode",
                 PortamentoMode::Poly,
+                |item| item.file_id(),
+                warnings,
             ),
-            midi_play_mode: MidiPlayMode::from_or(
-                param_tree.remove_u32_or("MidiPlayMode", MidiPlayMode::Normal as u32),
+            midi_play_mode: param_tree.remove_choice_or(
+                "MidiPlayMode",
                 MidiPlayMode::Normal,
+                |item| item.file_id(),
+                warnings,
             ),
-            glide: param_tree.remove_or("Glide", 30.0),
-            velocity_curve: param_tree.remove_or("VeloCurve", 0.5),
-            key_track_curve: param_tree.remove_or("KeyTrackCurve", 0.0),
-            pitch_bend_range: param_tree.remove_or("PBRange", 2.0),
+            glide: param_tree.remove_f64_or("Glide", 30.0),
+            velocity_curve: param_tree.remove_f64_or("VeloCurve", 0.5),
+            key_track_curve: param_tree.remove_f64_or("KeyTrackCurve", 0.0),
+            pitch_bend_range: param_tree.remove_f64_or("PBRange", 2.0),
             limit_enabled: param_tree.remove_bool_or("LimitSwitch", false),
             tuning,
+            pch,
             envelope,
-            envelope_curve: param_tree.remove_or("EnvCurveType", 0.14),
+            envelope_curve: param_tree.remove_f64_or("EnvCurveType", 0.14),
             filter,
-            filter_envelope_curve: param_tree.remove_or("FilterEnvCurveType", 0.14),
+            filter_envelope_curve: param_tree.remove_f64_or("FilterEnvCurveType", 0.14),
 
             // Oscillators
             oscillators,
@@ -1365,6 +3416,7 @@ impl Preset {
 
             // Effects
             effect_order,
+            extra_effect_order,
             chorus,
             delay,
             distortion,
@@ -1372,24 +3424,95 @@ impl Preset {
             effect_filter,
             lofi,
             reverb,
+            unknown_params: Vec::new(),
+            unknown_attributes: BTreeMap::new(),
         };
 
-        for param in &param_tree.params {
-            warn!(
-                "Unrecognized parameter while reading {}, parameter {} is {:?}",
-                path.as_ref().to_string_lossy(),
-                param.id,
-                param.value
-            );
+        Ok(preset)
+    }
+}
+
+impl Default for Preset {
+    /// A preset that sounds the same as the init preset of Babylon. Every
+    /// value is the default used when reading a preset that is missing it, so
+    /// the two can't drift apart.
+    fn default() -> Self {
+        let mut param_tree = PluginParamTree {
+            preset_name: "Init".to_string(),
+            ..PluginParamTree::default()
+        };
+        Preset::from_param_tree(&mut param_tree, "default preset", &mut Vec::new())
+            .expect("the defaults are a valid preset")
+    }
+}
+
+impl FromStr for Preset {
+    type Err = BabylonError;
+
+    /// Parse the XML of a preset. Whitespace and a byte order mark before the
+    /// XML declaration are ignored.
+    fn from_str(xml: &str) -> Result<Self, Self::Err> {
+        let xml = xml.trim_start_matches('\u{feff}').trim_start();
+        Preset::read(xml.as_bytes())
+    }
+}
+
+/// Group the indices of presets that sound the same, according to
+/// [`Preset::fingerprint`]. Only groups with more than one preset are
+/// returned, ordered by their first preset.
+pub fn find_duplicates(presets: &[Preset]) -> Vec<Vec<usize>> {
+    // Groups are kept in the order their first preset was seen, and the map
+    // finds the group of a fingerprint.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_indices: HashMap<u64, usize> = HashMap::new();
+    for (index, preset) in presets.iter().enumerate() {
+        match group_indices.entry(preset.fingerprint()) {
+            Entry::Occupied(entry) => groups[*entry.get()].push(index),
+            Entry::Vacant(entry) => {
+                entry.insert(groups.len());
+                groups.push(vec![index]);
+            }
         }
+    }
+    groups
+        .into_iter()
+        .filter(|indices| indices.len() > 1)
+        .collect()
+}
 
-        Ok(preset)
+/// Iterator over the effects of a [`Preset`] in the order they are processed,
+/// created by [`Preset::effects`].
+pub struct Effects<'a> {
+    preset: &'a Preset,
+    effect_types: std::slice::Iter<'a, EffectType>,
+}
+
+impl<'a> Iterator for Effects<'a> {
+    type Item = (EffectType, &'a dyn Effect);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.effect_types
+            .next()
+            .map(|effect_type| (*effect_type, self.preset.effect(*effect_type)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.effect_types.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a Preset {
+    type Item = (EffectType, &'a dyn Effect);
+    type IntoIter = Effects<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.effects()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::io::Result;
+    use std::io::ErrorKind;
     use std::path::Path;
 
     use approx::assert_relative_eq;
@@ -1398,8 +3521,8 @@ mod test {
     use super::effect::{EffectType, FilterEffectMode, FilterMode};
     use super::*;
 
-    fn read_preset(filename: &str) -> Result<Preset> {
-        let path = &Path::new("tests").join(&filename);
+    fn read_preset(filename: &str) -> Result<Preset, BabylonError> {
+        let path = &Path::new("tests").join(filename);
         Preset::read_file(path)
     }
 
@@ -1423,17 +3546,17 @@ mod test {
 
             let envelope = &preset.envelope;
             assert_relative_eq!(envelope.attack.get::<millisecond>(), 2.0, epsilon = 0.0001);
-            assert_relative_eq!(envelope.attack_curve, 0.07, epsilon = 0.0001);
+            assert_eq!(envelope.attack_curve, EnvelopeCurve::Exponential1);
             assert_relative_eq!(envelope.decay.get::<millisecond>(), 150.0, epsilon = 0.0001);
-            assert_relative_eq!(envelope.decay_falloff, 0.07, epsilon = 0.0001);
+            assert_eq!(envelope.decay_falloff, EnvelopeCurve::Exponential1);
             assert_relative_eq!(envelope.sustain.get::<percent>(), 0.9, epsilon = 0.0001);
             assert_relative_eq!(envelope.release.get::<millisecond>(), 4.0, epsilon = 0.0001);
-            assert_relative_eq!(envelope.release_falloff, 0.07, epsilon = 0.0001);
+            assert_eq!(envelope.release_falloff, EnvelopeCurve::Exponential1);
             assert_relative_eq!(preset.envelope_curve, 0.14, epsilon = 0.0001);
 
             let tuning = &preset.tuning;
             assert_eq!(tuning.transpose, 0.0);
-            assert_eq!(tuning.scale, 0);
+            assert_eq!(tuning.scale, Scale::Chromatic);
             assert_eq!(tuning.root_key, 0);
             let tunings = tuning.tunings;
             assert_eq!(tunings, [0.0_f64; 12]);
@@ -1455,20 +3578,20 @@ mod test {
                 2.0,
                 epsilon = 0.0001
             );
-            assert_relative_eq!(filter_env.attack_curve, 0.07, epsilon = 0.0001);
+            assert_eq!(filter_env.attack_curve, EnvelopeCurve::Exponential1);
             assert_relative_eq!(
                 filter_env.decay.get::<millisecond>(),
                 150.0,
                 epsilon = 0.0001
             );
-            assert_relative_eq!(filter_env.decay_falloff, 0.07, epsilon = 0.0001);
+            assert_eq!(filter_env.decay_falloff, EnvelopeCurve::Exponential1);
             assert_relative_eq!(filter_env.sustain.get::<percent>(), 0.02, epsilon = 0.0001);
             assert_relative_eq!(
                 filter_env.release.get::<millisecond>(),
                 4.0,
                 epsilon = 0.0001
             );
-            assert_relative_eq!(filter_env.release_falloff, 0.07, epsilon = 0.0001);
+            assert_eq!(filter_env.release_falloff, EnvelopeCurve::Exponential1);
             assert_relative_eq!(preset.filter_envelope_curve, 0.14, epsilon = 0.0001);
 
             //
@@ -1515,6 +3638,7 @@ mod test {
             let noise = &preset.noise;
             assert!(!noise.enabled);
             assert_relative_eq!(noise.width, 1.0, epsilon = 0.0001);
+            assert_relative_eq!(noise.width_percent(), 100.0, epsilon = 0.0001);
             assert_relative_eq!(noise.pan, 0.5, epsilon = 0.0001);
             assert_relative_eq!(noise.volume, 0.32, epsilon = 0.0001);
 
@@ -1541,26 +3665,26 @@ mod test {
                 assert_relative_eq!(mod_envelope.curve, 0.14, epsilon = 0.0001);
                 let env = &mod_envelope.envelope;
                 assert_relative_eq!(env.attack.get::<millisecond>(), 1.0, epsilon = 0.0001);
-                assert_relative_eq!(env.attack_curve, 0.07, epsilon = 0.0001);
+                assert_eq!(env.attack_curve, EnvelopeCurve::Exponential1);
                 assert_relative_eq!(env.decay.get::<millisecond>(), 150.0, epsilon = 0.0001);
-                assert_relative_eq!(env.decay_falloff, 0.07, epsilon = 0.0001);
+                assert_eq!(env.decay_falloff, EnvelopeCurve::Exponential1);
                 assert_relative_eq!(env.sustain.get::<percent>(), 0.9, epsilon = 0.0001);
                 assert_relative_eq!(env.release.get::<millisecond>(), 1.0, epsilon = 0.0001);
-                assert_relative_eq!(env.release_falloff, 0.07, epsilon = 0.0001);
+                assert_eq!(env.release_falloff, EnvelopeCurve::Exponential1);
             }
 
             let vibrato = &preset.vibrato;
             assert!(!vibrato.enabled);
-            assert_relative_eq!(vibrato.attack, 232.0, epsilon = 0.0001);
-            assert_relative_eq!(vibrato.delay, 232.0, epsilon = 0.0001);
-            assert_relative_eq!(vibrato.frequency, 6.1, epsilon = 0.0001);
+            assert_relative_eq!(vibrato.attack.get::<millisecond>(), 232.0, epsilon = 0.0001);
+            assert_relative_eq!(vibrato.delay.get::<millisecond>(), 232.0, epsilon = 0.0001);
+            assert_relative_eq!(vibrato.frequency.get::<hertz>(), 6.1, epsilon = 0.0001);
 
-            assert_eq!(preset.matrix[0].source, 7);
-            assert_eq!(preset.matrix[0].target, 2);
+            assert_eq!(preset.matrix[0].source, ModSource::Lfo1);
+            assert_eq!(preset.matrix[0].target, ModTarget::FilterCutoff);
             assert_eq!(preset.matrix[0].amount, 1.0);
             for index in 1..MODULATION_MATRIX_SIZE {
-                assert_eq!(preset.matrix[index].source, 0);
-                assert_eq!(preset.matrix[index].target, 0);
+                assert_eq!(preset.matrix[index].source, ModSource::Off);
+                assert_eq!(preset.matrix[index].target, ModTarget::Off);
                 assert_eq!(preset.matrix[index].amount, 0.0);
             }
 
@@ -1595,7 +3719,7 @@ mod test {
             assert!(!effect_filter.enabled);
             assert_eq!(effect_filter.mode, FilterMode::LowPass);
             assert_eq!(effect_filter.effect_mode, FilterEffectMode::Off);
-            assert_relative_eq!(effect_filter.cutoff_frequency, 0.5, epsilon = 0.0001);
+            assert_relative_eq!(effect_filter.cutoff_frequency, 50.0, epsilon = 0.0001);
             assert_relative_eq!(effect_filter.resonance, 0.1, epsilon = 0.0001);
             assert_relative_eq!(effect_filter.resonance, 0.1, epsilon = 0.0001);
             assert_relative_eq!(effect_filter.key_tracking, 0.0, epsilon = 0.0001);
@@ -1620,6 +3744,7 @@ mod test {
             assert_relative_eq!(reverb.filter, 0.0, epsilon = 0.0001);
             assert_relative_eq!(reverb.room, 0.3, epsilon = 0.0001);
             assert_relative_eq!(reverb.width, 0.8, epsilon = 0.0001);
+            assert_relative_eq!(reverb.width_percent(), 80.0, epsilon = 0.0001);
             assert_relative_eq!(reverb.mix, 0.2, epsilon = 0.0001);
         }
     }
@@ -1631,35 +3756,23 @@ mod test {
         // ADSR
         let envelope = &preset.envelope;
         assert_relative_eq!(envelope.attack.get::<millisecond>(), 1.0, epsilon = 0.00001);
-        assert_relative_eq!(
-            envelope.attack_curve,
-            EnvelopeCurve::Linear.value(),
-            epsilon = 0.00001
-        );
+        assert_eq!(envelope.attack_curve, EnvelopeCurve::Linear);
         assert_relative_eq!(
             envelope.decay.get::<millisecond>(),
             15000.0,
             epsilon = 0.00001
         );
-        assert_relative_eq!(
-            envelope.decay_falloff,
-            EnvelopeCurve::Exponential1.value(),
-            epsilon = 0.00001
-        );
+        assert_eq!(envelope.decay_falloff, EnvelopeCurve::Exponential1);
         assert_relative_eq!(envelope.sustain.get::<percent>(), 0.42, epsilon = 0.00001);
         assert_relative_eq!(
             envelope.release.get::<millisecond>(),
             76.0,
             epsilon = 0.00001
         );
-        assert_relative_eq!(
-            envelope.release_falloff,
-            EnvelopeCurve::Exponential2.value(),
-            epsilon = 0.00001
-        );
+        assert_eq!(envelope.release_falloff, EnvelopeCurve::Exponential2);
 
         // Modulator envelope 1
-        let mod_envelope = &preset.mod_envelopes.get(0).unwrap();
+        let mod_envelope = &preset.mod_envelopes.first().unwrap();
         assert!(mod_envelope.enabled);
         let envelope = &mod_envelope.envelope;
         assert_relative_eq!(
@@ -1667,32 +3780,20 @@ mod test {
             748.0,
             epsilon = 0.00001
         );
+        assert_eq!(envelope.attack_curve, EnvelopeCurve::Pluck1);
         assert_relative_eq!(
-            envelope.attack_curve,
-            EnvelopeCurve::Pluck1.value(),
-            epsilon = 0.00001
-        );
-        assert_relative_eq!(
-            envelope.decay.get::<millisecond>(),
-            150.0,
-            epsilon = 0.00001
-        );
-        assert_relative_eq!(
-            envelope.decay_falloff,
-            EnvelopeCurve::Pluck2.value(),
+            envelope.decay.get::<millisecond>(),
+            150.0,
             epsilon = 0.00001
         );
+        assert_eq!(envelope.decay_falloff, EnvelopeCurve::Pluck2);
         assert_relative_eq!(envelope.sustain.get::<percent>(), 0.90, epsilon = 0.00001);
         assert_relative_eq!(
             envelope.release.get::<millisecond>(),
             1.0,
             epsilon = 0.00001
         );
-        assert_relative_eq!(
-            envelope.release_falloff,
-            EnvelopeCurve::Pluck3.value(),
-            epsilon = 0.00001
-        );
+        assert_eq!(envelope.release_falloff, EnvelopeCurve::Pluck3);
 
         // Modulator envelope 2
         // NOTE: Bug report send to W. A. Productions on 2021-10-21 showing the curve types for
@@ -1703,99 +3804,1101 @@ mod test {
         assert!(!mod_envelope.enabled);
         let envelope = &mod_envelope.envelope;
         assert_relative_eq!(envelope.attack.get::<millisecond>(), 1.0, epsilon = 0.00001);
-        assert_relative_eq!(
-            envelope.attack_curve,
-            EnvelopeCurve::Logarithmic2.value(),
-            epsilon = 0.00001
-        );
+        assert_eq!(envelope.attack_curve, EnvelopeCurve::Logarithmic2);
         assert_relative_eq!(envelope.decay.get::<millisecond>(), 2.0, epsilon = 0.00001);
-        // assert_relative_eq!(envelope.decay_falloff, EnvelopeCurve::DoubleCurve1.value(), epsilon = 0.00001);
+        assert_eq!(envelope.decay_falloff, EnvelopeCurve::Exponential1);
+        // assert_eq!(envelope.decay_falloff, EnvelopeCurve::DoubleCurve1);
         assert_relative_eq!(envelope.sustain.get::<percent>(), 0.0, epsilon = 0.00001);
         assert_relative_eq!(
             envelope.release.get::<millisecond>(),
             1.0,
             epsilon = 0.00001
         );
-        // assert_relative_eq!(envelope.release_falloff, EnvelopeCurve::DoubleCurve2.value(), epsilon = 0.00001);
+        assert_eq!(envelope.release_falloff, EnvelopeCurve::Exponential1);
+        // assert_eq!(envelope.release_falloff, EnvelopeCurve::DoubleCurve2);
 
         // Filter envelope
+        // The curves were set to L1, Linear and E4 in Babylon but the preset
+        // stores E1 for all three, the same as the curves of every other
+        // preset, so they appear to be affected by the bug above. The IDs
+        // are the only filter envelope curves in the file.
         let envelope = &preset.filter.envelope;
         assert_relative_eq!(envelope.attack.get::<millisecond>(), 2.0, epsilon = 0.00001);
-        // assert_relative_eq!(envelope.attack_curve, EnvelopeCurve::Logarithmic1.value(), epsilon = 0.00001);
+        assert_eq!(envelope.attack_curve, EnvelopeCurve::Exponential1);
+        // assert_eq!(envelope.attack_curve, EnvelopeCurve::Logarithmic1);
         assert_relative_eq!(
             envelope.decay.get::<millisecond>(),
             150.0,
             epsilon = 0.00001
         );
-        // assert_relative_eq!(envelope.decay_falloff, EnvelopeCurve::Linear.value(), epsilon = 0.00001);
+        assert_eq!(envelope.decay_falloff, EnvelopeCurve::Exponential1);
+        // assert_eq!(envelope.decay_falloff, EnvelopeCurve::Linear);
         assert_relative_eq!(envelope.sustain.get::<percent>(), 0.02, epsilon = 0.00001);
         assert_relative_eq!(
             envelope.release.get::<millisecond>(),
             4.0,
             epsilon = 0.00001
         );
-        // assert_relative_eq!(envelope.release_falloff, EnvelopeCurve::Exponential4.value(), epsilon = 0.00001);
+        assert_eq!(envelope.release_falloff, EnvelopeCurve::Exponential1);
+        // assert_eq!(envelope.release_falloff, EnvelopeCurve::Exponential4);
+    }
+
+    /// The curves of the second modulator envelope as Babylon 1.0.2 saved
+    /// them. The decay and release were set to D1 and D2 but saved as E1. A
+    /// failure here with a preset from a newer version of Babylon means the
+    /// bug has been fixed.
+    #[test]
+    fn mod_envelope2_saved_curves() {
+        let input = File::open(Path::new("tests").join("envelopes-1.0.2.bab")).unwrap();
+        let param_tree: PluginParamTree = from_reader(BufReader::new(input)).unwrap();
+        let stored = |id: &str| {
+            let param = param_tree.params.iter().find(|param| param.id == id);
+            param.and_then(|param| param.value.clone()).unwrap()
+        };
+        assert_eq!(stored("ModAttCurveType_2"), "0.4000000059604645");
+        assert_eq!(stored("ModDecCurveType_2"), "0.07000000029802322388");
+        assert_eq!(stored("ModRelCurveType_2"), "0.07000000029802322388");
+
+        let preset = read_preset("envelopes-1.0.2.bab").unwrap();
+        let envelope = &preset.mod_envelopes[1].envelope;
+        assert_eq!(envelope.attack_curve, EnvelopeCurve::Logarithmic2);
+        assert_eq!(envelope.decay_falloff, EnvelopeCurve::Exponential1);
+        assert_eq!(envelope.release_falloff, EnvelopeCurve::Exponential1);
+    }
+
+    #[test]
+    fn envelope_at_velocity() {
+        let preset = read_preset("envelopes-1.0.2.bab").unwrap();
+        let soft = preset.envelope_at_velocity(0.5);
+        let hard = preset.envelope_at_velocity(1.0);
+        assert_eq!(hard, preset.envelope);
+        assert!(soft.sustain < hard.sustain);
+        assert_eq!(soft.attack, hard.attack);
+        assert_eq!(soft.release, hard.release);
+
+        assert_relative_eq!(preset.velocity_curve, 0.5);
+        assert_relative_eq!(preset.velocity_response(0.5), 0.5);
     }
 
     #[test]
     fn envelope_curves() {
         let preset = read_preset("envelope_curve-ae3-de4-rl1-1.0.3.bab").unwrap();
-        assert_relative_eq!(
-            preset.envelope.attack_curve,
-            EnvelopeCurve::Exponential3.value(),
-            epsilon = 0.0001
+        assert_eq!(preset.envelope.attack_curve, EnvelopeCurve::Exponential3);
+        assert_eq!(preset.envelope.decay_falloff, EnvelopeCurve::Exponential4);
+        assert_eq!(preset.envelope.release_falloff, EnvelopeCurve::Logarithmic1);
+
+        let preset = read_preset("envelope_curve-ap3-dd1-rd2-1.0.3.bab").unwrap();
+        assert_eq!(preset.envelope.attack_curve, EnvelopeCurve::Pluck3);
+        assert_eq!(preset.envelope.decay_falloff, EnvelopeCurve::DoubleCurve1);
+        assert_eq!(preset.envelope.release_falloff, EnvelopeCurve::DoubleCurve2);
+
+        // The filter envelope uses the same values as the amplifier envelope.
+        let xml = std::fs::read_to_string(Path::new("tests").join("init-1.0.4.bab"))
+            .unwrap()
+            .replace(
+                r#"id="FilterAttCurveType" value="0.07000000029802322""#,
+                r#"id="FilterAttCurveType" value="0.3330000042915344""#,
+            )
+            .replace(
+                r#"id="FilterDecCurveType" value="0.07000000029802322""#,
+                r#"id="FilterDecCurveType" value="0.0""#,
+            )
+            .replace(
+                r#"id="FilterRelCurveType" value="0.07000000029802322""#,
+                r#"id="FilterRelCurveType" value="0.2669999897480011""#,
+            );
+        let preset: Preset = xml.parse().unwrap();
+        let envelope = &preset.filter.envelope;
+        assert_eq!(envelope.attack_curve, EnvelopeCurve::Logarithmic1);
+        assert_eq!(envelope.decay_falloff, EnvelopeCurve::Linear);
+        assert_eq!(envelope.release_falloff, EnvelopeCurve::Exponential4);
+    }
+
+    #[test]
+    fn envelope_curve_from_value() {
+        for curve in EnvelopeCurve::iter() {
+            assert_eq!(EnvelopeCurve::from_value(curve.value()), Some(curve));
+            assert_eq!(EnvelopeCurve::from_value(curve.value() + 0.02), Some(curve));
+        }
+        assert_eq!(
+            EnvelopeCurve::from_value(0.4670000076293945),
+            Some(EnvelopeCurve::Pluck1)
         );
-        assert_relative_eq!(
-            preset.envelope.decay_falloff,
-            EnvelopeCurve::Exponential4.value(),
-            epsilon = 0.0001
+        assert_eq!(EnvelopeCurve::from_value(-1.0), None);
+        assert_eq!(EnvelopeCurve::from_value(0.9), None);
+        assert_eq!(EnvelopeCurve::nearest(0.9), EnvelopeCurve::DoubleCurve2);
+    }
+
+    #[test]
+    fn default() {
+        let preset = Preset::default();
+        assert_eq!(preset.name, "Init");
+        assert_eq!(preset.master_volume_normalized, 0.5);
+        assert_eq!(preset.polyphony, 8);
+        assert_eq!(preset.portamento_mode, PortamentoMode::Poly);
+        assert!(preset.oscillators[0].enabled);
+        assert!(!preset.oscillators[1].enabled);
+        assert!(!preset.oscillators[2].enabled);
+        assert_eq!(preset.matrix[0].source, ModSource::Lfo1);
+        assert_eq!(preset.matrix[0].target, ModTarget::FilterCutoff);
+        assert_eq!(preset.matrix[0].amount, 1.0);
+        assert_eq!(preset.effect_order, EffectType::iter().collect::<Vec<_>>());
+        assert!(preset.unknown_params.is_empty());
+
+        // Babylon stores single precision values so they are only close.
+        let params = preset.to_param_tree().params;
+        for file in &["init-1.0.2.bab", "init-1.0.4.bab"] {
+            let init_params = read_preset(file).unwrap().to_param_tree().params;
+            assert_eq!(params.len(), init_params.len());
+            for (param, init_param) in params.iter().zip(&init_params) {
+                assert_eq!(param.id, init_param.id);
+                // PCH is kept as-is and differs between versions.
+                if param.id == "PCH" {
+                    continue;
+                }
+                let value = param.value_finite_f64().unwrap();
+                let init_value = init_param.value_finite_f64().unwrap();
+                assert_relative_eq!(value, init_value, epsilon = 0.0001);
+            }
+        }
+    }
+
+    /// The defaults of the components match the defaults used when reading.
+    #[test]
+    fn default_components() {
+        let preset = Preset::default();
+        assert_eq!(Envelope::default(), preset.envelope);
+        assert_eq!(Filter::default(), preset.filter);
+        assert_eq!(Oscillator::default(), preset.oscillators[1]);
+        assert_eq!(Oscillator::default(), preset.oscillators[2]);
+        assert_eq!(Unison::default(), preset.oscillators[0].unison);
+        assert_eq!(Noise::default(), preset.noise);
+        assert_eq!(Lfo::default(), preset.lfos[0]);
+        assert_eq!(Lfo::default(), preset.lfos[1]);
+        assert_eq!(ModulatorEnvelope::default(), preset.mod_envelopes[0]);
+        assert_eq!(ModulatorEnvelope::default(), preset.mod_envelopes[1]);
+        assert_eq!(Vibrato::default(), preset.vibrato);
+        assert_eq!(Tuning::default(), preset.tuning);
+        assert_eq!(MatrixItem::default(), preset.matrix[1]);
+        assert_eq!(Chorus::default(), preset.chorus);
+        assert_eq!(Delay::default(), preset.delay);
+        assert_eq!(Distortion::default(), preset.distortion);
+        assert_eq!(Equalizer::default(), preset.equalizer);
+        assert_eq!(LoFi::default(), preset.lofi);
+        assert_eq!(Reverb::default(), preset.reverb);
+
+        assert_eq!(Envelope::default().sustain, Ratio::new::<percent>(0.9));
+    }
+
+    #[test]
+    fn effect_chain_string() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        assert_eq!(preset.effect_chain_string(), "");
+
+        preset.set_effect_enabled(EffectType::Reverb, true);
+        preset.set_effect_enabled(EffectType::Distortion, true);
+        assert_eq!(preset.effect_chain_string(), "Dist -> Reverb");
+    }
+
+    #[test]
+    fn estimated_output_gain_db() {
+        let init = read_preset("init-1.0.2.bab").unwrap();
+        assert_relative_eq!(init.estimated_output_gain_db(), 0.0, epsilon = 0.0001);
+
+        let mut hot = read_preset("init-1.0.2.bab").unwrap();
+        for oscillator in &mut hot.oscillators {
+            oscillator.enabled = true;
+            oscillator.volume = 1.0;
+        }
+        hot.distortion.enabled = true;
+        hot.distortion.set_gain_ui(5.0);
+        hot.master_volume_normalized = 1.0;
+        assert!(hot.estimated_output_gain_db() > init.estimated_output_gain_db());
+
+        for oscillator in &mut hot.oscillators {
+            oscillator.enabled = false;
+        }
+        assert_eq!(hot.estimated_output_gain_db(), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn find_duplicates() {
+        let original = read_preset("init-1.0.2.bab").unwrap();
+        let mut renamed = read_preset("init-1.0.2.bab").unwrap();
+        renamed.name = "Renamed".to_string();
+        renamed.description = Some("Same sound".to_string());
+        let louder = read_preset("master-volume-10-1.0.3.bab").unwrap();
+        assert_eq!(original.fingerprint(), renamed.fingerprint());
+        assert_ne!(original.fingerprint(), louder.fingerprint());
+
+        let presets = [original, louder, renamed];
+        assert_eq!(super::find_duplicates(&presets), vec![vec![0, 2]]);
+
+        // Groups are ordered by their first preset.
+        let [original, louder, renamed] = presets;
+        let presets = [louder.clone(), original, louder.clone(), renamed, louder];
+        assert_eq!(
+            super::find_duplicates(&presets),
+            vec![vec![0, 2, 4], vec![1, 3]]
         );
-        assert_relative_eq!(
-            preset.envelope.release_falloff,
-            EnvelopeCurve::Logarithmic1.value(),
-            epsilon = 0.0001
+    }
+
+    /// Line endings and indentation must not affect parsing.
+    #[test]
+    fn formatting() {
+        let expected = read_preset("init-1.0.2.bab").unwrap();
+        for file in &["init-indented-1.0.2.bab", "init-lf-1.0.2.bab"] {
+            let path = Path::new("tests").join("formatting").join(file);
+            let preset = Preset::read_file(path).unwrap();
+            assert_eq!(preset.name, expected.name);
+            assert_eq!(preset.description, expected.description);
+            assert_eq!(preset.fingerprint(), expected.fingerprint());
+        }
+    }
+
+    #[test]
+    fn harmonic_profile() {
+        assert_eq!(
+            Waveform::Sine.harmonic_profile(),
+            HarmonicProfile::Fundamental
+        );
+        assert_eq!(
+            Waveform::Saw.harmonic_profile(),
+            HarmonicProfile::AllInverse
+        );
+        assert_eq!(
+            Waveform::Square.harmonic_profile(),
+            HarmonicProfile::OddInverse
+        );
+        assert_eq!(
+            Waveform::Triangle.harmonic_profile(),
+            HarmonicProfile::OddInverseSquare
+        );
+        assert_eq!(
+            Waveform::SineFmA1.harmonic_profile(),
+            HarmonicProfile::Complex
+        );
+        assert_eq!(
+            Waveform::Organ1.harmonic_profile(),
+            HarmonicProfile::Sampled
+        );
+    }
+
+    #[test]
+    fn hard_sync_active() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        assert!(!preset.hard_sync_active());
+
+        preset.hard_sync = true;
+        assert!(!preset.oscillators[1].enabled);
+        assert!(!preset.hard_sync_active());
+
+        preset.oscillators[1].enabled = true;
+        assert!(preset.hard_sync_active());
+
+        preset.oscillators.truncate(1);
+        assert!(preset.oscillators[0].enabled);
+        assert!(!preset.hard_sync_active());
+        assert!(!preset.sync_config().hard_sync);
+
+        preset.oscillators.clear();
+        assert!(!preset.hard_sync_active());
+    }
+
+    #[test]
+    fn sync_config() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        assert_eq!(preset.sync_config(), SyncConfig::default());
+
+        preset.hard_sync = true;
+        for oscillator in &mut preset.oscillators {
+            oscillator.sync_all = true;
+        }
+        assert_eq!(
+            preset.sync_config(),
+            SyncConfig {
+                hard_sync: false,
+                sync_all: vec![0],
+            }
         );
 
-        let preset = read_preset("envelope_curve-ap3-dd1-rd2-1.0.3.bab").unwrap();
-        assert_relative_eq!(
-            preset.envelope.attack_curve,
-            EnvelopeCurve::Pluck3.value(),
-            epsilon = 0.0001
+        for oscillator in &mut preset.oscillators {
+            oscillator.enabled = true;
+        }
+        assert_eq!(
+            preset.sync_config(),
+            SyncConfig {
+                hard_sync: true,
+                sync_all: vec![0, 1],
+            }
         );
-        assert_relative_eq!(
-            preset.envelope.decay_falloff,
-            EnvelopeCurve::DoubleCurve1.value(),
-            epsilon = 0.0001
+    }
+
+    #[test]
+    fn invalid_effect_order() {
+        let error = read_preset("invalid-effect-order-1.0.4.bab").unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                BabylonError::UnknownEffectType { attribute, effect_type_id: 9, .. }
+                    if attribute == "FX_Order_2"
+            ),
+            "{:?}",
+            error
         );
-        assert_relative_eq!(
-            preset.envelope.release_falloff,
-            EnvelopeCurve::DoubleCurve2.value(),
-            epsilon = 0.0001
+        let message = error.to_string();
+        assert!(
+            message.contains("invalid-effect-order-1.0.4.bab"),
+            "{}",
+            message
         );
+        assert!(message.contains("FX_Order_2"), "{}", message);
+        assert!(message.contains("Unknown effect type ID 9"), "{}", message);
+    }
+
+    /// Parameters that are not read are left in the tree in their original
+    /// order, including repeats of parameters that are read.
+    #[test]
+    fn leftover_params() {
+        let param = |id: String, value: &str| Param {
+            id,
+            value: Some(value.to_string()),
+        };
+        let mut params: Vec<Param> = (0..1000)
+            .map(|index| param(format!("Unknown_{}", index), "1"))
+            .collect();
+        params.insert(500, param("MainVol".to_string(), "0.25"));
+        params.push(param("MainVol".to_string(), "0.75"));
+        let mut param_tree = PluginParamTree {
+            params,
+            ..PluginParamTree::default()
+        };
+        let preset = Preset::from_param_tree(&mut param_tree, "leftover", &mut Vec::new()).unwrap();
+        assert_eq!(preset.master_volume_normalized, 0.25);
+        assert_eq!(param_tree.params.len(), 1001);
+        assert_eq!(param_tree.params[0].id, "Unknown_0");
+        assert_eq!(param_tree.params[999].id, "Unknown_999");
+        assert_eq!(param_tree.params[1000].value.as_deref(), Some("0.75"));
+    }
+
+    /// Every parameter that is read must be listed as known, and none that
+    /// are left over may be.
+    #[test]
+    fn known_param_ids() {
+        for file in &["init-1.0.2.bab", "init-1.0.4.bab"] {
+            let input = File::open(Path::new("tests").join(file)).unwrap();
+            let mut param_tree: PluginParamTree = from_reader(BufReader::new(input)).unwrap();
+            let all_ids: Vec<String> = param_tree.params.iter().map(|p| p.id.clone()).collect();
+            Preset::from_param_tree(&mut param_tree, file, &mut Vec::new()).unwrap();
+            for id in &all_ids {
+                let consumed = !param_tree.params.iter().any(|p| &p.id == id);
+                assert_eq!(
+                    KNOWN_PARAM_IDS.contains(&id.as_str()),
+                    consumed,
+                    "Parameter {} in {}",
+                    id,
+                    file
+                );
+            }
+        }
+    }
+
+    /// Presets from before Babylon 1.0.2 may be missing attributes and
+    /// parameters, which fall back to their defaults.
+    #[test]
+    fn legacy() {
+        let preset = read_preset("legacy-synthesized.bab").unwrap();
+        assert_eq!(preset.name, "legacy");
+        assert_eq!(preset.description, None);
+        assert_eq!(preset.tuning.scale, Scale::Chromatic);
+        assert_eq!(preset.tuning.root_key, 0);
+        assert_eq!(preset.effect_order, EffectType::iter().collect::<Vec<_>>());
+        assert!(preset.oscillators[0].enabled);
+        assert_eq!(preset.oscillators[0].unison.voices, 1);
+        assert_eq!(preset.lfos.len(), 2);
+        assert!(!preset.limit_enabled);
+    }
+
+    #[test]
+    fn lfo_effective_mode() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        let lfo = &mut preset.lfos[0];
+        lfo.sync = false;
+        lfo.free_run = false;
+        lfo.mono = false;
+        assert_eq!(lfo.effective_mode(), LfoMode::Retriggered);
+
+        lfo.mono = true;
+        assert_eq!(lfo.effective_mode(), LfoMode::FreeRunning);
+
+        lfo.mono = false;
+        lfo.free_run = true;
+        assert_eq!(lfo.effective_mode(), LfoMode::FreeRunning);
+
+        lfo.sync = true;
+        assert_eq!(lfo.effective_mode(), LfoMode::Synced);
+    }
+
+    #[test]
+    fn lfo_rate() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        let lfo = &mut preset.lfos[0];
+        assert!(lfo.sync);
+        assert_eq!(lfo.rate(), LfoRate::Division(NoteDivision::Eighth));
+
+        lfo.sync = false;
+        lfo.frequency = 1.0;
+        assert_eq!(lfo.rate(), LfoRate::Hz(20.0));
     }
 
     #[test]
     fn master_volume() {
         let preset = read_preset("master-volume-10-1.0.3.bab").unwrap();
         assert_eq!(preset.master_volume_normalized, 1.0);
+        assert_relative_eq!(preset.master_volume_db(), 10.0, epsilon = 0.0001);
+
+        let preset = read_preset("master-volume--97-1.0.3.bab").unwrap();
+        assert_relative_eq!(preset.master_volume_db(), -9.7, epsilon = 0.1);
 
-        let preset = read_preset("master-volume--398-1.0.3.bab").unwrap();
+        let mut preset = read_preset("master-volume--398-1.0.3.bab").unwrap();
         assert_relative_eq!(preset.master_volume_normalized, 0.007, epsilon = 0.001);
+        assert_relative_eq!(preset.master_volume_db(), -39.8, epsilon = 0.1);
+        preset.set_master_volume_db(0.0);
+        assert_relative_eq!(preset.master_volume_normalized, 0.5);
+        preset.set_master_volume_db(20.0);
+        assert_eq!(preset.master_volume_normalized, 1.0);
+        preset.set_master_volume_db(f64::NEG_INFINITY);
+        assert_eq!(preset.master_volume_normalized, 0.0);
 
         let preset = read_preset("master-volume--inf-1.0.3.bab").unwrap();
         assert_eq!(preset.master_volume_normalized, 0.0);
+        assert_eq!(preset.master_volume_db(), f64::NEG_INFINITY);
     }
 
     #[test]
     fn midi_play_mode() {
         let preset = read_preset("playmode-cheat1-1.0.2.bab").unwrap();
         assert_eq!(preset.midi_play_mode, MidiPlayMode::Cheat1);
+        assert_eq!(preset.midi_play_mode.to_string(), "Mute Off-Key");
+        assert_eq!(MidiPlayMode::Cheat2.to_string(), "Replace Off-Key");
+    }
+
+    /// The volume is stored without conversion and defaults to the volume of
+    /// a new preset.
+    #[test]
+    fn oscillator_volume() {
+        let preset = read_preset("oscillator-volume-missing-1.0.4.bab").unwrap();
+        for oscillator in &preset.oscillators {
+            assert_eq!(oscillator.volume, 0.5);
+        }
+    }
+
+    #[test]
+    fn metadata() {
+        let preset = read_preset("init-1.0.4.bab").unwrap();
+        assert_eq!(preset.metadata, None);
+
+        let preset = read_preset("metadata-1.0.4.bab").unwrap();
+        let metadata = preset.metadata.unwrap();
+        assert_eq!(metadata.author.as_deref(), Some("Sheldon Young"));
+        assert_eq!(metadata.created, None);
+        assert_eq!(metadata.category.as_deref(), Some("Bass"));
+    }
+
+    /// Descriptions that are blank or the placeholder are not descriptions.
+    #[test]
+    fn default_description() {
+        for file in [
+            "description-padded-1.0.4.bab",
+            "description-blank-1.0.4.bab",
+        ] {
+            let preset = read_preset(file).unwrap();
+            assert_eq!(preset.description, None, "{}", file);
+        }
+    }
+
+    #[test]
+    fn move_effect() {
+        use EffectType::*;
+        let mut preset = read_preset("init-1.0.4.bab").unwrap();
+        assert!(preset.move_effect(Reverb, 0));
+        assert_eq!(
+            preset.effect_order,
+            vec![Reverb, Distortion, LoFi, Filter, Chorus, Equalizer, Delay]
+        );
+        assert!(preset.move_effect(Distortion, 6));
+        assert_eq!(
+            preset.effect_order,
+            vec![Reverb, LoFi, Filter, Chorus, Equalizer, Delay, Distortion]
+        );
+        assert!(!preset.move_effect(Filter, 7));
+        assert_eq!(preset.effect_position(Filter), Some(2));
+    }
+
+    /// Values that are not finite fall back to the defaults.
+    #[test]
+    fn non_finite() {
+        let preset = read_preset("non-finite-1.0.4.bab").unwrap();
+        let oscillator = &preset.oscillators[0];
+        assert_eq!(oscillator.pan, 0.5);
+        assert_eq!(oscillator.phase, 0.0);
+        assert_eq!(oscillator.fine_tuning, 0);
+    }
+
+    #[test]
+    fn oscillator_beat_frequency() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        assert_eq!(preset.oscillator_beat_frequency(0, 1), None);
+
+        preset.oscillators[1].enabled = true;
+        assert_eq!(preset.oscillator_beat_frequency(0, 1), Some(0.0));
+
+        preset.oscillators[1].fine_tuning = 3;
+        let beat = preset.oscillator_beat_frequency(0, 1).unwrap();
+        assert_relative_eq!(beat, 0.763, epsilon = 0.001);
+        assert_eq!(preset.oscillator_beat_frequency(1, 0), Some(beat));
+
+        preset.oscillators[1].octave_tuning = 1;
+        preset.oscillators[1].fine_tuning = 0;
+        assert_relative_eq!(preset.oscillators[1].frequency_ratio(), 2.0);
+        assert_eq!(preset.oscillator_beat_frequency(0, 3), None);
+    }
+
+    #[test]
+    fn signal_flow_description() {
+        let path = Path::new("effects").join("effect-order-reversed-1.0.2.bab");
+        let preset = read_preset(path.to_str().unwrap()).unwrap();
+        let description = preset.signal_flow_description();
+        assert!(description.starts_with("Oscillator 1 -> Filter"));
+
+        // The effects are listed in the effect order.
+        let mut position = description.find("Amplifier").unwrap();
+        for effect_type in &preset.effect_order {
+            let name = effect_type.to_string();
+            position += description[position..].find(&name).unwrap();
+        }
+        assert!(description.ends_with("Master volume"));
+    }
+
+    #[test]
+    fn oscillator_tuning() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        let oscillator = &mut preset.oscillators[0];
+        oscillator.pitch = 0.5;
+        oscillator.octave_tuning = -1;
+        oscillator.semitone_tuning = 7;
+        oscillator.fine_tuning = 25;
+        let tuning = oscillator.tuning();
+        assert_relative_eq!(tuning.total_semitones(), 0.5 - 12.0 + 7.0 + 0.25);
+        assert_relative_eq!(tuning.total_cents(), -425.0);
+        assert_relative_eq!(tuning.frequency_ratio(), oscillator.frequency_ratio());
+    }
+
+    #[test]
+    fn scale() {
+        assert_eq!(Scale::from_attributes(0, 0), Scale::Chromatic);
+        assert_eq!(Scale::from_attributes(5, 0), Scale::Other(5));
+        let custom = Scale::Custom { scale: 5, index: 2 };
+        assert_eq!(Scale::from_attributes(5, 2), custom);
+        assert_eq!(Scale::Chromatic.to_string(), "Chromatic");
+        assert_eq!(Scale::Other(5).to_string(), "Scale 5");
+        assert_eq!(custom.to_string(), "Custom 2");
+
+        let mut preset = read_preset("init-1.0.4.bab").unwrap();
+        preset.tuning.scale = custom;
+        let mut written = Vec::new();
+        preset.write(&mut written).unwrap();
+        let reread = Preset::read_named(written.as_slice(), "written").unwrap();
+        assert_eq!(reread.tuning.scale, custom);
+    }
+
+    #[test]
+    fn oscillator_total_offset() {
+        let preset = read_preset("oscillator-tuning-1.0.4.bab").unwrap();
+        let oscillator = &preset.oscillators[0];
+        assert_relative_eq!(oscillator.pitch, 0.5);
+        assert_eq!(oscillator.octave_tuning, -1);
+        assert_eq!(oscillator.semitone_tuning, 7);
+        assert_eq!(oscillator.fine_tuning, 25);
+        assert_relative_eq!(oscillator.total_cents(), -425.0);
+        assert_relative_eq!(oscillator.total_semitones(), -4.25);
+        assert_relative_eq!(preset.oscillators[1].total_cents(), 0.0);
+    }
+
+    #[test]
+    fn param_scale() {
+        let pan = ParamScale::Bipolar { center: 0.5 };
+        assert_eq!(ParamScale::of("OSCPan_1"), Some(pan));
+        assert_eq!(ParamScale::of("OSCPan_N"), Some(pan));
+        assert_eq!(ParamScale::of("EQLow"), Some(pan));
+        assert_eq!(
+            ParamScale::of("OSCFine_3"),
+            Some(ParamScale::Bipolar { center: 0.0 })
+        );
+        assert_eq!(
+            ParamScale::of("TuneCSharp"),
+            Some(ParamScale::Bipolar { center: 0.0 })
+        );
+        assert_eq!(ParamScale::of("DelayFeed"), Some(ParamScale::Unipolar));
+        assert_eq!(ParamScale::of("OSCPan_4"), None);
+        for id in KNOWN_PARAM_IDS {
+            assert!(ParamScale::of(id).is_some());
+        }
+    }
+
+    #[test]
+    fn portamento_mode() {
+        for mode in PortamentoMode::iter() {
+            assert_eq!(mode.to_string().parse::<PortamentoMode>().unwrap(), mode);
+        }
+        assert_eq!(
+            PortamentoMode::LegatoNoRetrigger.to_string(),
+            "Legato (No Retrigger)"
+        );
+        assert_eq!(
+            "porta poly".parse::<PortamentoMode>().unwrap(),
+            PortamentoMode::PortaPoly
+        );
+        assert!(matches!(
+            "Mono".parse::<PortamentoMode>(),
+            Err(BabylonError::UnknownPortamentoMode(name)) if name == "Mono"
+        ));
+    }
+
+    #[test]
+    fn preset_clone_eq() {
+        let preset = read_preset("init-1.0.4.bab").unwrap();
+        let mut copy = preset.clone();
+        assert_eq!(copy, preset);
+        copy.oscillators[0].volume = 0.25;
+        assert_ne!(copy, preset);
+    }
+
+    #[test]
+    fn primary_waveform() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        assert_eq!(preset.primary_waveform(), Some(Waveform::Sine));
+
+        preset.oscillators[2].enabled = true;
+        preset.oscillators[2].waveform = Waveform::Saw;
+        assert_eq!(preset.primary_waveform(), Some(Waveform::Sine));
+        preset.oscillators[2].volume = 0.75;
+        assert_eq!(preset.primary_waveform(), Some(Waveform::Saw));
+
+        for oscillator in &mut preset.oscillators {
+            oscillator.enabled = false;
+        }
+        assert_eq!(preset.primary_waveform(), None);
+    }
+
+    #[test]
+    fn from_str() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let xml = std::fs::read_to_string(&path).unwrap();
+        let expected = Preset::read_file(&path).unwrap();
+        let preset: Preset = xml.parse().unwrap();
+        assert_eq!(format!("{:?}", preset), format!("{:?}", expected));
+
+        let preset: Preset = format!("\u{feff}\r\n  {}", xml).parse().unwrap();
+        assert_eq!(format!("{:?}", preset), format!("{:?}", expected));
+
+        let error = "<NotAPreset".parse::<Preset>().unwrap_err();
+        assert!(matches!(error, BabylonError::Xml { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn read_malformed() {
+        let path = Path::new("tests").join("malformed").join("empty.bab");
+        let error = Preset::read_file(&path).unwrap_err();
+        assert!(matches!(error, BabylonError::Empty { .. }), "{:?}", error);
+        assert!(error
+            .to_string()
+            .ends_with("empty.bab: The preset is empty"));
+        let error = " \r\n".parse::<Preset>().unwrap_err();
+        assert!(matches!(error, BabylonError::Empty { .. }), "{:?}", error);
+
+        let path = Path::new("tests")
+            .join("malformed")
+            .join("truncated-1.0.4.bab");
+        let error = Preset::read_file(&path).unwrap_err();
+        assert!(
+            matches!(error, BabylonError::Truncated { .. }),
+            "{:?}",
+            error
+        );
+        assert!(
+            error.to_string().contains("The preset is truncated"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn read_dir() {
+        let results = Preset::read_dir("tests");
+        let names: Vec<String> = results
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert!(names.contains(&"init-1.0.2.bab".to_string()));
+        assert!(!names.contains(&"effects".to_string()));
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+        for (path, result) in &results {
+            let invalid = path.ends_with("invalid-effect-order-1.0.4.bab");
+            assert_eq!(result.is_err(), invalid, "{:?}", path);
+        }
+
+        let results = Preset::read_dir(Path::new("tests").join("missing"));
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].1, Err(BabylonError::Io(_))));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn read_dir_parallel() {
+        let expected = Preset::read_dir("tests");
+        let results = Preset::read_dir_parallel("tests");
+        assert_eq!(results.len(), expected.len());
+        for ((path, result), (expected_path, expected_result)) in results.iter().zip(&expected) {
+            assert_eq!(path, expected_path);
+            assert_eq!(result.as_ref().ok(), expected_result.as_ref().ok());
+        }
+
+        let results = Preset::read_dir_parallel(Path::new("tests").join("missing"));
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].1, Err(BabylonError::Io(_))));
+    }
+
+    #[test]
+    fn raw_params() {
+        let mut preset = read_preset("unknown-1.0.4.bab").unwrap();
+        let param = preset.unknown_params[0].clone();
+        assert_eq!(preset.get_raw_param(&param.id), param.value.as_deref());
+        assert_eq!(preset.get_raw_param("MainVol"), None);
+
+        preset.set_raw_param(&param.id, "0.5".to_string()).unwrap();
+        assert_eq!(preset.get_raw_param(&param.id), Some("0.5"));
+        let count = preset.unknown_params.len();
+        preset
+            .set_raw_param("FutureParam", "1".to_string())
+            .unwrap();
+        assert_eq!(preset.unknown_params.len(), count + 1);
+
+        let mut written = Vec::new();
+        preset.write(&mut written).unwrap();
+        let reread = Preset::read(written.as_slice()).unwrap();
+        assert_eq!(reread.get_raw_param(&param.id), Some("0.5"));
+        assert_eq!(reread.get_raw_param("FutureParam"), Some("1"));
+
+        let error = preset
+            .set_raw_param("MainVol", "1".to_string())
+            .unwrap_err();
+        assert!(matches!(error, BabylonError::KnownParam(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn read_file_with_raw() {
+        let path = Path::new("tests").join("unknown-1.0.4.bab");
+        let (preset, raw_params) = Preset::read_file_with_raw(&path).unwrap();
+        assert_eq!(preset, Preset::read_file(&path).unwrap());
+        assert!(raw_params.len() > preset.unknown_params.len());
+        assert!(raw_params.iter().any(|param| param.id == "MainVol"));
+        for param in &preset.unknown_params {
+            assert!(raw_params.contains(param));
+        }
+    }
+
+    #[test]
+    fn read_file_verbose() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let (_, warnings) = Preset::read_file_verbose(path).unwrap();
+        assert!(warnings.is_empty());
+
+        let path = Path::new("tests").join("unknown-choice-1.0.4.bab");
+        let (preset, warnings) = Preset::read_file_verbose(path).unwrap();
+        assert_eq!(preset.filter.mode, FilterMode::LowPass);
+        assert_eq!(preset.oscillators[0].waveform, Waveform::Sine);
+        assert_eq!(preset.delay.filter_mode, DelayFilterMode::Off);
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::UnknownChoice {
+                    id: "FilterType".to_string(),
+                    value: 42,
+                },
+                Warning::UnknownChoice {
+                    id: "OSCWaveType_1".to_string(),
+                    value: 999,
+                },
+                Warning::UnknownChoice {
+                    id: "DelayLP".to_string(),
+                    value: 900,
+                },
+            ]
+        );
+
+        let path = Path::new("tests").join("unknown-1.0.4.bab");
+        let (_, warnings) = Preset::read_file_verbose(path).unwrap();
+        assert_eq!(
+            warnings[0],
+            Warning::UnknownParam {
+                id: "AFutureParam".to_string(),
+                value: Some("0.25".to_string()),
+            }
+        );
+        assert!(warnings.contains(&Warning::UnknownAttribute {
+            name: "FutureAttribute".to_string(),
+            value: "future".to_string(),
+        }));
+        assert_eq!(
+            warnings[0].to_string(),
+            "Unrecognized parameter AFutureParam is Some(\"0.25\")"
+        );
+    }
+
+    #[test]
+    fn read() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let bytes = std::fs::read(&path).unwrap();
+        let preset = Preset::read(bytes.as_slice()).unwrap();
+        let expected = Preset::read_file(&path).unwrap();
+        assert_eq!(format!("{:?}", preset), format!("{:?}", expected));
+
+        let error = Preset::read("not a preset".as_bytes()).unwrap_err();
+        assert!(matches!(error, BabylonError::Xml { .. }), "{:?}", error);
+
+        let error = Preset::read_file("missing.bab").unwrap_err();
+        assert!(
+            matches!(&error, BabylonError::Io(io) if io.kind() == ErrorKind::NotFound),
+            "{:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn sampled_waveforms_used() {
+        let path = Path::new("tests")
+            .join("waveforms")
+            .join("waveforms-formanta1-svoice1-organ1-1.0.3.bab");
+        let mut preset = Preset::read_file(path).unwrap();
+        preset.oscillators[0].waveform = Waveform::EPiano2;
+        preset.oscillators[1].waveform = Waveform::Saw;
+        assert_eq!(
+            preset.sampled_waveforms_used(),
+            vec![Waveform::EPiano2, Waveform::Organ1]
+        );
+
+        preset.oscillators[0].waveform = Waveform::Organ1;
+        assert_eq!(preset.sampled_waveforms_used(), vec![Waveform::Organ1]);
+
+        let preset = read_preset("init-1.0.2.bab").unwrap();
+        assert!(preset.sampled_waveforms_used().is_empty());
+        assert!(!Waveform::Gate1.is_sample_based());
+        assert!(Waveform::Voice1.is_sample_based());
+    }
+
+    #[test]
+    fn shift_oscillator_octave() {
+        let mut preset = read_preset("init-1.0.2.bab").unwrap();
+        preset.shift_oscillator_octave(1, 1).unwrap();
+        assert_eq!(preset.oscillators[0].octave_tuning, 0);
+        assert_eq!(preset.oscillators[1].octave_tuning, 1);
+        assert_eq!(preset.oscillators[2].octave_tuning, 0);
+
+        preset.shift_oscillator_octave(1, -100).unwrap();
+        assert_eq!(preset.oscillators[1].octave_tuning, OSCILLATOR_OCTAVE_MIN);
+
+        assert!(matches!(
+            preset.shift_oscillator_octave(3, 1),
+            Err(BabylonError::NoOscillator(3))
+        ));
+    }
+
+    #[test]
+    fn noise_is_enabled() {
+        let mut noise = Noise::default();
+        assert!(!noise.is_enabled());
+        noise.enabled = true;
+        assert!(noise.is_enabled());
+    }
+
+    #[test]
+    fn noise_color() {
+        let preset = read_preset("init-1.0.4.bab").unwrap();
+        assert_eq!(preset.noise.color, NoiseColor::White);
+        assert_eq!(preset.noise.color.to_string(), "White");
+        let tree = preset.to_param_tree();
+        assert!(!tree.params.iter().any(|p| p.id == NOISE_COLOR_PARAM_ID));
+
+        // The color is read if a preset has it.
+        let xml = std::fs::read_to_string(Path::new("tests").join("init-1.0.4.bab")).unwrap();
+        let with_color = |value: &str| {
+            xml.replace(
+                r#"<PARAM id="OSCVol_N" value="0.320000022649765"/>"#,
+                &format!(
+                    r#"<PARAM id="OSCVol_N" value="0.320000022649765"/><PARAM id="OSCColor_N" value="{}"/>"#,
+                    value
+                ),
+            )
+        };
+        let (preset, warnings) =
+            Preset::read_named_verbose(with_color("0").as_bytes(), "preset").unwrap();
+        assert_eq!(preset.noise.color, NoiseColor::White);
+        assert!(preset.unknown_params.is_empty());
+        assert!(warnings.is_empty());
+
+        let (preset, warnings) =
+            Preset::read_named_verbose(with_color("3").as_bytes(), "preset").unwrap();
+        assert_eq!(preset.noise.color, NoiseColor::White);
+        assert_eq!(
+            warnings,
+            vec![Warning::UnknownChoice {
+                id: NOISE_COLOR_PARAM_ID.to_string(),
+                value: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn noise_is_audible() {
+        let mut noise = Noise::default();
+        assert!(!noise.is_audible());
+        noise.enabled = true;
+        assert!(noise.is_audible());
+        noise.volume = 0.0;
+        assert!(noise.is_enabled());
+        assert!(!noise.is_audible());
+    }
+
+    #[test]
+    fn with() {
+        let preset = read_preset("init-1.0.2.bab")
+            .unwrap()
+            .with_effect_enabled(EffectType::Reverb, true)
+            .with_master_volume_db(-3.0)
+            .with_name("Edited")
+            .with_polyphony(4);
+        assert!(preset.reverb.enabled);
+        assert!(!preset.delay.enabled);
+        assert_relative_eq!(
+            conversions::normalized_to_db(preset.master_volume_normalized),
+            -3.0,
+            epsilon = 0.0001
+        );
+        assert_eq!(preset.name, "Edited");
+        assert_eq!(preset.polyphony, 4);
+    }
+
+    #[test]
+    fn swap_effects() {
+        use EffectType::*;
+        let mut preset = read_preset("init-1.0.4.bab").unwrap();
+        assert!(preset.swap_effects(Distortion, Reverb));
+        assert_eq!(
+            preset.effect_order,
+            vec![Reverb, LoFi, Filter, Chorus, Equalizer, Delay, Distortion]
+        );
+
+        preset
+            .effect_order
+            .retain(|effect_type| *effect_type != Chorus);
+        assert!(!preset.swap_effects(Chorus, Delay));
+        assert_eq!(preset.effect_position(Delay), Some(4));
+    }
+
+    /// Compare the parsed form of every fixture with a previously saved
+    /// snapshot, to catch unintended changes. Run the tests with the
+    /// `UPDATE_SNAPSHOTS` environment variable set to regenerate the
+    /// snapshots after an intended change.
+    #[test]
+    fn snapshots() {
+        fn find_presets(dir: &Path, presets: &mut Vec<std::path::PathBuf>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    find_presets(&path, presets);
+                } else if path.extension().is_some_and(|ext| ext == "bab") {
+                    presets.push(path);
+                }
+            }
+        }
+
+        let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+        let mut presets = Vec::new();
+        find_presets(Path::new("tests"), &mut presets);
+        presets.sort();
+        assert!(!presets.is_empty());
+
+        let mut mismatches = Vec::new();
+        for path in presets {
+            let actual = match Preset::read_file(&path) {
+                Ok(preset) => format!("{:#?}\n", preset),
+                Err(error) => format!("Error: {}\n", error),
+            };
+            let relative = path.strip_prefix("tests").unwrap();
+            let snapshot_path = Path::new("tests")
+                .join("snapshots")
+                .join(relative)
+                .with_extension("snap");
+            let expected = std::fs::read_to_string(&snapshot_path).ok();
+            if expected.as_deref() != Some(actual.as_str()) {
+                if update {
+                    std::fs::create_dir_all(snapshot_path.parent().unwrap()).unwrap();
+                    std::fs::write(&snapshot_path, actual).unwrap();
+                } else {
+                    mismatches.push(snapshot_path);
+                }
+            }
+        }
+        assert!(
+            mismatches.is_empty(),
+            "Snapshots do not match, set UPDATE_SNAPSHOTS to regenerate: {:?}",
+            mismatches
+        );
+    }
+
+    #[test]
+    fn tuning() {
+        let preset = read_preset("init-1.0.2.bab").unwrap();
+        assert!(preset.tuning.is_equal_temperament());
+
+        let mut preset = read_preset("tuning-microtuned-1.0.4.bab").unwrap();
+        assert!(!preset.tuning.is_equal_temperament());
+        assert!(preset.tuning.snap_to_equal_temperament());
+        assert!(preset.tuning.is_equal_temperament());
+        assert_eq!(preset.tuning.tunings, [0.0; 12]);
+        assert_eq!(preset.tuning.transpose, 2.0);
+        assert!(!preset.tuning.snap_to_equal_temperament());
+    }
+
+    #[test]
+    fn waveform_from_str() {
+        for waveform in Waveform::iter() {
+            assert_eq!(waveform.to_string().parse::<Waveform>().unwrap(), waveform);
+        }
+        assert_eq!(
+            "sine fm kick 12".parse::<Waveform>().unwrap(),
+            Waveform::SineFmKick12
+        );
+        assert_eq!(" Saw ".parse::<Waveform>().unwrap(), Waveform::Saw);
+        assert!(matches!(
+            "Wobble".parse::<Waveform>(),
+            Err(BabylonError::UnknownWaveform(name)) if name == "Wobble"
+        ));
+    }
+
+    #[test]
+    fn waveform_names() {
+        let names: Vec<String> = Waveform::iter().map(|w| w.to_string()).collect();
+        let unique: std::collections::HashSet<&String> = names.iter().collect();
+        assert_eq!(unique.len(), names.len());
+        assert_eq!(Waveform::SyntheticVoice29.to_string(), "Synthetic Voice 29");
     }
 
     #[test]
     fn waveforms() {
-        fn read_waveform_preset(filename: &str) -> Result<Preset> {
-            let path = &Path::new("tests").join("waveforms").join(&filename);
+        fn read_waveform_preset(filename: &str) -> Result<Preset, BabylonError> {
+            let path = &Path::new("tests").join("waveforms").join(filename);
             Preset::read_file(path)
         }
 
@@ -1820,4 +4923,62 @@ mod test {
         assert_eq!(preset.oscillators[1].waveform, Waveform::Pulse1);
         assert_eq!(preset.oscillators[2].waveform, Waveform::Voice1);
     }
+
+    #[test]
+    fn note_division() {
+        assert_eq!(NoteDivision::from_normalized(0.3), NoteDivision::Quarter);
+        assert_eq!(NoteDivision::Quarter.to_seconds(120.0), 0.5);
+        assert_eq!(NoteDivision::Whole.to_seconds(60.0), 4.0);
+        assert_eq!(NoteDivision::EighthDotted.beats(), 0.75);
+        assert_relative_eq!(NoteDivision::QuarterTriplet.to_seconds(60.0), 2.0 / 3.0);
+        for division in NoteDivision::iter() {
+            let value = division.to_normalized();
+            assert_eq!(NoteDivision::from_normalized(value), division);
+        }
+    }
+
+    #[test]
+    fn waveform_family() {
+        assert!(Waveform::Voice30.is_voice());
+        assert!(!Waveform::SyntheticVoice1.is_voice());
+        assert!(Waveform::Organ23.is_organ());
+        assert!(Waveform::FormantB8.is_formant());
+        assert!(Waveform::Dirty4C.is_dirty());
+        assert!(!Waveform::Gritty1.is_dirty());
+        assert!(Waveform::Chip1.is_chip());
+        assert_eq!(Waveform::SineFmKick1.family(), WaveformFamily::Kick);
+        assert_eq!(Waveform::SineFmD15.family(), WaveformFamily::SineFm);
+        assert_eq!(Waveform::Rhode.family(), WaveformFamily::Keys);
+        assert_eq!(
+            WaveformFamily::SyntheticVoice.to_string(),
+            "Synthetic Voice"
+        );
+
+        // Every family has waveforms.
+        for family in WaveformFamily::iter() {
+            assert!(Waveform::iter().any(|waveform| waveform.family() == family));
+        }
+    }
+
+    #[test]
+    fn file_ids() {
+        assert_eq!(Waveform::Sine.file_id(), 0);
+        assert_eq!(Waveform::from_file_id(0), Some(Waveform::Sine));
+        assert_eq!(Waveform::from_file_id(9999), None);
+        for waveform in Waveform::iter() {
+            assert_eq!(Waveform::from_file_id(waveform.file_id()), Some(waveform));
+        }
+        for source in ModSource::iter() {
+            assert_eq!(ModSource::from_file_id(source.file_id()), Some(source));
+        }
+        for target in ModTarget::iter() {
+            assert_eq!(ModTarget::from_file_id(target.file_id()), Some(target));
+        }
+        for effect_type in EffectType::iter() {
+            assert_eq!(
+                EffectType::from_file_id(effect_type.file_id()),
+                Some(effect_type)
+            );
+        }
+    }
 }
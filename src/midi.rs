@@ -0,0 +1,116 @@
+//! Suggestions for controlling a preset with MIDI.
+
+use uom::si::time::millisecond;
+
+use crate::{ModSource, Preset};
+
+/// Modulation wheel.
+const CC_MODULATION: u8 = 1;
+
+/// Portamento time.
+const CC_PORTAMENTO_TIME: u8 = 5;
+
+/// Channel volume.
+const CC_VOLUME: u8 = 7;
+
+/// Sound controller 2, timbre or harmonic intensity, usually resonance.
+const CC_RESONANCE: u8 = 71;
+
+/// Sound controller 3, release time.
+const CC_RELEASE: u8 = 72;
+
+/// Sound controller 4, attack time.
+const CC_ATTACK: u8 = 73;
+
+/// Sound controller 5, brightness, usually the filter cutoff.
+const CC_CUTOFF: u8 = 74;
+
+/// Sound controller 6, decay time.
+const CC_DECAY: u8 = 75;
+
+impl Preset {
+    /// A starting point for automating the preset from a DAW, as MIDI CC
+    /// numbers with the identifier of the parameter they suggest
+    /// controlling and its current value. Ordered by CC number.
+    ///
+    /// The mapping follows the General MIDI 2 conventions for sound
+    /// controllers. The modulation wheel is only included if the
+    /// modulation matrix has a route from it, in which case it controls
+    /// the amount of the first such route. Times are in milliseconds and
+    /// other values are as they appear in the preset model.
+    pub fn suggested_cc_map(&self) -> Vec<(u8, String, f64)> {
+        let mut map = Vec::new();
+
+        let mod_wheel_route = self
+            .matrix
+            .iter()
+            .position(|item| item.source == ModSource::ModWheel);
+        if let Some(index) = mod_wheel_route {
+            map.push((
+                CC_MODULATION,
+                format!("MatrixAmount_{}", index + 1),
+                self.matrix[index].amount,
+            ));
+        }
+
+        let envelope = &self.envelope;
+        map.extend([
+            (CC_PORTAMENTO_TIME, "Glide".to_string(), self.glide),
+            (
+                CC_VOLUME,
+                "MainVol".to_string(),
+                self.master_volume_normalized,
+            ),
+            (CC_RESONANCE, "FilterRes".to_string(), self.filter.resonance),
+            (
+                CC_RELEASE,
+                "EnvRelease".to_string(),
+                envelope.release.get::<millisecond>(),
+            ),
+            (
+                CC_ATTACK,
+                "EnvAttack".to_string(),
+                envelope.attack.get::<millisecond>(),
+            ),
+            (
+                CC_CUTOFF,
+                "FilterCut".to_string(),
+                self.filter.cutoff_frequency,
+            ),
+            (
+                CC_DECAY,
+                "EnvDecay".to_string(),
+                envelope.decay.get::<millisecond>(),
+            ),
+        ]);
+        map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{ModSource, Preset, KNOWN_PARAM_IDS};
+
+    #[test]
+    fn suggested_cc_map() {
+        let path = Path::new("tests")
+            .join("effects")
+            .join("filter-bandpass-1.0.2.bab");
+        let mut preset = Preset::read_file(path).unwrap();
+        let map = preset.suggested_cc_map();
+        let (_, param_id, value) = map.iter().find(|(cc, _, _)| *cc == 74).unwrap();
+        assert_eq!(param_id, "FilterCut");
+        assert_eq!(*value, preset.filter.cutoff_frequency);
+        assert!(map.iter().all(|(cc, _, _)| *cc != 1));
+        for (_, param_id, _) in &map {
+            assert!(KNOWN_PARAM_IDS.contains(&param_id.as_str()));
+        }
+
+        preset.matrix[2].source = ModSource::ModWheel;
+        preset.matrix[2].amount = 0.25;
+        let map = preset.suggested_cc_map();
+        assert_eq!(map[0], (1, "MatrixAmount_3".to_string(), 0.25));
+    }
+}
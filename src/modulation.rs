@@ -0,0 +1,286 @@
+use std::fmt::{Display, Formatter};
+
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{MatrixItem, Preset, MODULATION_MATRIX_SIZE};
+
+/// Source of a route in the modulation matrix.
+///
+/// The discriminants of the items match the file format. Listed in the order
+/// they appear in the Babylon user interface.
+#[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum ModSource {
+    Off,
+    Velocity,
+    ModWheel,
+    Aftertouch,
+    KeyTrack,
+    ModEnvelope1,
+    ModEnvelope2,
+    Lfo1,
+    Lfo2,
+}
+
+impl Display for ModSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ModSource::*;
+        let msg = match self {
+            Off => "Off",
+            Velocity => "Velocity",
+            ModWheel => "Mod Wheel",
+            Aftertouch => "Aftertouch",
+            KeyTrack => "Key Track",
+            ModEnvelope1 => "Mod Env 1",
+            ModEnvelope2 => "Mod Env 2",
+            Lfo1 => "LFO 1",
+            Lfo2 => "LFO 2",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Destination of a route in the modulation matrix.
+///
+/// The discriminants of the items match the file format. Listed in the order
+/// they appear in the Babylon user interface.
+#[derive(Copy, Clone, Debug, EnumIter, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum ModTarget {
+    Off,
+    Volume,
+    FilterCutoff,
+    FilterResonance,
+    Pitch,
+    Pan,
+    Oscillator1Volume,
+    Oscillator2Volume,
+    Oscillator3Volume,
+    Oscillator1Pitch,
+    Oscillator2Pitch,
+    Oscillator3Pitch,
+    Oscillator1Pan,
+    Oscillator2Pan,
+    Oscillator3Pan,
+    NoiseVolume,
+    Lfo1Rate,
+    Lfo2Rate,
+}
+
+impl Display for ModTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ModTarget::*;
+        let msg = match self {
+            Off => "Off",
+            Volume => "Volume",
+            FilterCutoff => "Filter Cutoff",
+            FilterResonance => "Filter Resonance",
+            Pitch => "Pitch",
+            Pan => "Pan",
+            Oscillator1Volume => "Osc 1 Volume",
+            Oscillator2Volume => "Osc 2 Volume",
+            Oscillator3Volume => "Osc 3 Volume",
+            Oscillator1Pitch => "Osc 1 Pitch",
+            Oscillator2Pitch => "Osc 2 Pitch",
+            Oscillator3Pitch => "Osc 3 Pitch",
+            Oscillator1Pan => "Osc 1 Pan",
+            Oscillator2Pan => "Osc 2 Pan",
+            Oscillator3Pan => "Osc 3 Pan",
+            NoiseVolume => "Noise Volume",
+            Lfo1Rate => "LFO 1 Rate",
+            Lfo2Rate => "LFO 2 Rate",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Route from a modulation source to a target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModEdge {
+    pub source: ModSource,
+    pub target: ModTarget,
+    pub amount: f64,
+}
+
+/// The modulation matrix as a graph, with the sources and targets as nodes
+/// and the routes between them as edges. Only routes that have an effect are
+/// included.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModGraph {
+    /// Sources used by at least one route, in the order first used.
+    pub sources: Vec<ModSource>,
+
+    /// Targets used by at least one route, in the order first used.
+    pub targets: Vec<ModTarget>,
+
+    pub edges: Vec<ModEdge>,
+}
+
+impl Preset {
+    /// The modulation sources that can have an effect in this preset, for
+    /// choosing the source of a route. The LFOs and modulation envelopes are
+    /// only included when they are enabled. [`ModSource::Off`] is always
+    /// included so a route can be cleared.
+    pub fn available_mod_sources(&self) -> Vec<ModSource> {
+        let mod_envelope_enabled =
+            |index: usize| self.mod_envelopes.get(index).is_some_and(|env| env.enabled);
+        let lfo_enabled = |index: usize| self.lfos.get(index).is_some_and(|lfo| lfo.enabled);
+        ModSource::iter()
+            .filter(|source| match source {
+                ModSource::ModEnvelope1 => mod_envelope_enabled(0),
+                ModSource::ModEnvelope2 => mod_envelope_enabled(1),
+                ModSource::Lfo1 => lfo_enabled(0),
+                ModSource::Lfo2 => lfo_enabled(1),
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Route a modulation source to a target in the first unused slot of the
+    /// modulation matrix, returning the index of the slot. `None` if every
+    /// slot is in use. A matrix with missing slots is first filled out with
+    /// unused ones.
+    pub fn add_matrix_route(
+        &mut self,
+        source: ModSource,
+        target: ModTarget,
+        amount: f64,
+    ) -> Option<usize> {
+        if self.matrix.len() < MODULATION_MATRIX_SIZE {
+            self.matrix
+                .resize(MODULATION_MATRIX_SIZE, MatrixItem::default());
+        }
+        let index = self.matrix.iter().position(MatrixItem::is_unused)?;
+        self.matrix[index] = MatrixItem {
+            source,
+            target,
+            amount,
+        };
+        Some(index)
+    }
+
+    /// Clear the route in a slot of the modulation matrix, returning it. The
+    /// slot is left unused rather than removed so the matrix keeps its size.
+    /// `None` if there is no slot at the index.
+    pub fn remove_matrix_route(&mut self, index: usize) -> Option<MatrixItem> {
+        let item = self.matrix.get_mut(index)?;
+        Some(std::mem::take(item))
+    }
+
+    /// The routes in the modulation matrix that are in use, with the index
+    /// of their slot.
+    pub fn active_matrix_routes(&self) -> impl Iterator<Item = (usize, &MatrixItem)> {
+        self.matrix
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !item.is_unused())
+    }
+
+    /// The routes in the modulation matrix that have an effect, as a graph.
+    pub fn modulation_graph(&self) -> ModGraph {
+        let mut graph = ModGraph::default();
+        for item in &self.matrix {
+            let (source, target) = (item.source, item.target);
+            if source == ModSource::Off || target == ModTarget::Off || item.amount == 0.0 {
+                continue;
+            }
+            if !graph.sources.contains(&source) {
+                graph.sources.push(source);
+            }
+            if !graph.targets.contains(&target) {
+                graph.targets.push(target);
+            }
+            graph.edges.push(ModEdge {
+                source,
+                target,
+                amount: item.amount,
+            });
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{ModSource, ModTarget, Preset, MODULATION_MATRIX_SIZE};
+
+    #[test]
+    fn available_mod_sources() {
+        let path = Path::new("tests").join("lfo1-enabled-1.0.4.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let sources = preset.available_mod_sources();
+        assert!(sources.contains(&ModSource::Off));
+        assert!(sources.contains(&ModSource::ModWheel));
+        assert!(sources.contains(&ModSource::Lfo1));
+        assert!(!sources.contains(&ModSource::Lfo2));
+        assert!(!sources.contains(&ModSource::ModEnvelope1));
+    }
+
+    #[test]
+    fn modulation_graph() {
+        let path = Path::new("tests").join("init-1.0.2.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let graph = preset.modulation_graph();
+        assert_eq!(graph.sources, vec![ModSource::Lfo1]);
+        assert_eq!(graph.targets, vec![ModTarget::FilterCutoff]);
+        assert_eq!(graph.edges.len(), 1);
+        let edge = &graph.edges[0];
+        assert_eq!(edge.source, ModSource::Lfo1);
+        assert_eq!(edge.target, ModTarget::FilterCutoff);
+        assert_eq!(edge.amount, 1.0);
+    }
+
+    #[test]
+    fn matrix_routes() {
+        let mut preset = Preset::default();
+        let active: Vec<usize> = preset.active_matrix_routes().map(|(i, _)| i).collect();
+        assert_eq!(active, vec![0]);
+
+        assert_eq!(
+            preset.add_matrix_route(ModSource::Velocity, ModTarget::Volume, 0.5),
+            Some(1)
+        );
+        let removed = preset.remove_matrix_route(0).unwrap();
+        assert_eq!(removed.source, ModSource::Lfo1);
+        assert!(preset.matrix[0].is_unused());
+        assert_eq!(preset.matrix.len(), MODULATION_MATRIX_SIZE);
+        assert!(preset.remove_matrix_route(MODULATION_MATRIX_SIZE).is_none());
+        assert_eq!(
+            preset.add_matrix_route(ModSource::ModWheel, ModTarget::Volume, 1.0),
+            Some(0)
+        );
+        let active: Vec<usize> = preset.active_matrix_routes().map(|(i, _)| i).collect();
+        assert_eq!(active, vec![0, 1]);
+
+        for _ in 2..MODULATION_MATRIX_SIZE {
+            assert!(preset
+                .add_matrix_route(ModSource::Aftertouch, ModTarget::Volume, 1.0)
+                .is_some());
+        }
+        assert!(preset
+            .add_matrix_route(ModSource::Aftertouch, ModTarget::Volume, 1.0)
+            .is_none());
+        assert_eq!(preset.matrix.len(), MODULATION_MATRIX_SIZE);
+
+        // Missing slots are filled out.
+        preset.matrix.clear();
+        assert_eq!(
+            preset.add_matrix_route(ModSource::Velocity, ModTarget::Volume, 1.0),
+            Some(0)
+        );
+        assert_eq!(preset.matrix.len(), MODULATION_MATRIX_SIZE);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(ModSource::Lfo1.to_string(), "LFO 1");
+        assert_eq!(ModSource::ModEnvelope2.to_string(), "Mod Env 2");
+        assert_eq!(ModTarget::FilterCutoff.to_string(), "Filter Cutoff");
+        assert_eq!(ModTarget::Oscillator3Pan.to_string(), "Osc 3 Pan");
+    }
+}
@@ -0,0 +1,141 @@
+//! MIDI Tuning Standard messages for sending the tuning of a preset to other
+//! instruments.
+
+use crate::Tuning;
+
+/// Sends the message to every device.
+const ALL_DEVICES: u8 = 0x7f;
+
+/// Non-real-time universal system exclusive message.
+const NON_REAL_TIME: u8 = 0x7e;
+
+/// MIDI Tuning Standard.
+const MIDI_TUNING: u8 = 0x08;
+
+/// Key-based tuning dump, which is a bulk dump with a tuning bank.
+const KEY_BASED_TUNING_DUMP: u8 = 0x04;
+
+/// Length of the name of a tuning program.
+const NAME_LENGTH: usize = 16;
+
+/// Steps in a semitone of the 14-bit fractions of a semitone.
+const FRACTION_STEPS: f64 = 16384.0;
+
+/// Highest pitch that can be sent. The largest fraction above note 127 is
+/// reserved, `7F 7F 7F` means the tuning of the key is not changed, so the
+/// highest pitch is one step below it.
+const PITCH_MAX: f64 = 127.0 + (FRACTION_STEPS - 2.0) / FRACTION_STEPS;
+
+impl Tuning {
+    /// A key-based MIDI Tuning Standard bulk dump of all 128 notes as a
+    /// system exclusive message, framed by `F0` and `F7`, for every device.
+    ///
+    /// Each note is tuned by the transpose and the offset in cents for its
+    /// note from [`Tuning::tuning_for_note`]. The root key does not change
+    /// the pitch of any note because the offsets are for fixed notes. Only
+    /// the first 16 ASCII characters of the name are used. Values for the
+    /// bank and program above 127 are truncated to 7 bits.
+    pub fn to_mts_bulk_dump(&self, bank: u8, program: u8, name: &str) -> Vec<u8> {
+        let mut data = vec![
+            NON_REAL_TIME,
+            ALL_DEVICES,
+            MIDI_TUNING,
+            KEY_BASED_TUNING_DUMP,
+            bank & 0x7f,
+            program & 0x7f,
+        ];
+
+        let mut name: Vec<u8> = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii() && !c.is_ascii_control() {
+                    c as u8
+                } else {
+                    b' '
+                }
+            })
+            .take(NAME_LENGTH)
+            .collect();
+        name.resize(NAME_LENGTH, b' ');
+        data.extend(name);
+
+        for note in 0..128 {
+            let pitch = note as f64 + self.transpose + self.tuning_for_note(note) / 100.0;
+            let pitch = pitch.clamp(0.0, PITCH_MAX);
+            let steps = (pitch * FRACTION_STEPS)
+                .round()
+                .min(PITCH_MAX * FRACTION_STEPS) as u32;
+            let semitone = steps / FRACTION_STEPS as u32;
+            let fraction = steps % FRACTION_STEPS as u32;
+            data.extend([
+                semitone as u8,
+                (fraction >> 7) as u8,
+                (fraction & 0x7f) as u8,
+            ]);
+        }
+
+        let checksum = data.iter().fold(0, |checksum, byte| checksum ^ byte) & 0x7f;
+        let mut message = Vec::with_capacity(data.len() + 3);
+        message.push(0xf0);
+        message.extend(data);
+        message.extend([checksum, 0xf7]);
+        message
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{Preset, Tuning};
+
+    /// The three bytes for a note in a bulk dump.
+    fn note_bytes(message: &[u8], note: usize) -> &[u8] {
+        let start = 23 + note * 3;
+        &message[start..start + 3]
+    }
+
+    #[test]
+    fn mts_bulk_dump() {
+        let message = Tuning::default().to_mts_bulk_dump(1, 2, "Equal");
+        assert_eq!(message.len(), 409);
+        assert_eq!(&message[..7], &[0xf0, 0x7e, 0x7f, 0x08, 0x04, 1, 2]);
+        assert_eq!(&message[7..23], b"Equal           ");
+        for note in 0..128 {
+            assert_eq!(note_bytes(&message, note), &[note as u8, 0, 0]);
+        }
+        let checksum = message[1..message.len() - 2]
+            .iter()
+            .fold(0, |checksum, byte| checksum ^ byte);
+        assert_eq!(message[message.len() - 2], checksum & 0x7f);
+        assert_eq!(message[message.len() - 1], 0xf7);
+
+        let path = Path::new("tests").join("tuning-microtuned-1.0.4.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let message = preset.tuning.to_mts_bulk_dump(0, 0, &preset.name);
+        assert_eq!(&message[7..23], b"tuning-microtune");
+
+        // Every note is transposed up by 2.25 semitones.
+        assert_eq!(note_bytes(&message, 60), &[62, 0x20, 0]);
+
+        let mut tuning = preset.tuning.clone();
+        tuning.transpose = 0.0;
+        let message = tuning.to_mts_bulk_dump(0, 0, &preset.name);
+        assert_eq!(note_bytes(&message, 60), &[60, 0, 0]);
+
+        // A is 12.5 cents sharp, an eighth of a semitone.
+        assert_eq!(note_bytes(&message, 69), &[69, 0x10, 0]);
+
+        // E is 7 cents flat, so it is 93% of the way from D#.
+        assert_eq!(note_bytes(&message, 64), &[63, 0x77, 0x05]);
+
+        // F# is half a cent sharp.
+        assert_eq!(note_bytes(&message, 66), &[66, 0, 82]);
+
+        // Notes can't be tuned beyond the range of MIDI notes, or to the
+        // value reserved for not changing the tuning.
+        tuning.transpose = 24.0;
+        let message = tuning.to_mts_bulk_dump(0, 0, "");
+        assert_eq!(note_bytes(&message, 127), &[127, 0x7f, 0x7e]);
+    }
+}
@@ -0,0 +1,136 @@
+//! Names of MIDI notes, such as "C4" for middle C.
+
+use crate::{BabylonError, Tuning};
+
+/// Octave of middle C, MIDI note 60, in scientific pitch notation. Some
+/// software, such as Ableton Live and FL Studio, call middle C "C3" or "C5"
+/// instead.
+pub const MIDDLE_C_OCTAVE: i32 = 4;
+
+/// MIDI note number of middle C.
+const MIDDLE_C: i32 = 60;
+
+/// Highest MIDI note number, G9 when middle C is C4.
+const MIDI_NOTE_MAX: u32 = 127;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Name of a MIDI note with sharps, such as "A4" or "C#3", where middle C is
+/// in `middle_c_octave`.
+pub fn note_name(note: u32, middle_c_octave: i32) -> String {
+    let octave = (note / 12) as i32 - MIDDLE_C / 12 + middle_c_octave;
+    format!("{}{}", NOTE_NAMES[note as usize % 12], octave)
+}
+
+/// MIDI note number of a note name, the inverse of [`note_name`]. The letter
+/// is case insensitive and may be followed by `#` or `b` for sharps and
+/// flats, so "Db4" and "c#4" are the same note. Names of notes outside the
+/// MIDI range of 0 to 127 are unknown.
+pub fn note_from_name(name: &str, middle_c_octave: i32) -> Result<u32, BabylonError> {
+    let unknown = || BabylonError::UnknownNoteName(name.to_string());
+    let trimmed = name.trim();
+    let mut chars = trimmed.chars();
+    let letter = chars.next().ok_or_else(unknown)?.to_ascii_uppercase();
+    let mut pitch_class = NOTE_NAMES
+        .iter()
+        .position(|n| n.starts_with(letter) && n.len() == 1)
+        .ok_or_else(unknown)? as i32;
+    let mut octave = chars.as_str();
+    if let Some(rest) = octave.strip_prefix('#') {
+        pitch_class += 1;
+        octave = rest;
+    } else if let Some(rest) = octave.strip_prefix('b') {
+        pitch_class -= 1;
+        octave = rest;
+    }
+    let octave: i32 = octave.parse().map_err(|_| unknown())?;
+    octave
+        .checked_sub(middle_c_octave)
+        .and_then(|octaves| octaves.checked_mul(12))
+        .and_then(|semitones| semitones.checked_add(MIDDLE_C + pitch_class))
+        .and_then(|note| u32::try_from(note).ok())
+        .filter(|note| *note <= MIDI_NOTE_MAX)
+        .ok_or_else(unknown)
+}
+
+impl Tuning {
+    /// Name of the root key, such as "C4" for middle C. See
+    /// [`Tuning::root_key_name_with_middle_c`] for other octave numbering.
+    pub fn root_key_name(&self) -> String {
+        self.root_key_name_with_middle_c(MIDDLE_C_OCTAVE)
+    }
+
+    /// Name of the root key where middle C is in `middle_c_octave`.
+    pub fn root_key_name_with_middle_c(&self, middle_c_octave: i32) -> String {
+        note_name(self.root_key, middle_c_octave)
+    }
+
+    /// Set the root key from its name, such as "C4" for middle C. The root
+    /// key is unchanged if the name is not a note.
+    pub fn set_root_key_name(&mut self, name: &str) -> Result<(), BabylonError> {
+        self.root_key = note_from_name(name, MIDDLE_C_OCTAVE)?;
+        Ok(())
+    }
+
+    /// The microtuning offset of a MIDI note, from the octave of
+    /// [`Tuning::tunings`] that starts at A natural.
+    pub fn tuning_for_note(&self, note: u32) -> f64 {
+        // A is the tenth note of an octave starting at C.
+        self.tunings[(note as usize + 3) % 12]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{note_from_name, note_name, Preset, MIDDLE_C_OCTAVE};
+
+    #[test]
+    fn names() {
+        assert_eq!(note_name(60, MIDDLE_C_OCTAVE), "C4");
+        assert_eq!(note_name(69, MIDDLE_C_OCTAVE), "A4");
+        assert_eq!(note_name(0, MIDDLE_C_OCTAVE), "C-1");
+        assert_eq!(note_name(61, 3), "C#3");
+        for note in 0..128 {
+            for octave in [3, 4, 5] {
+                let name = note_name(note, octave);
+                assert_eq!(note_from_name(&name, octave).unwrap(), note);
+            }
+        }
+        assert_eq!(note_from_name("db4", MIDDLE_C_OCTAVE).unwrap(), 61);
+        assert_eq!(note_from_name(" B#3 ", MIDDLE_C_OCTAVE).unwrap(), 60);
+        assert!(note_from_name("H4", MIDDLE_C_OCTAVE).is_err());
+        assert!(note_from_name("C", MIDDLE_C_OCTAVE).is_err());
+        assert!(note_from_name("Cb-1", MIDDLE_C_OCTAVE).is_err());
+        assert_eq!(note_from_name("G9", MIDDLE_C_OCTAVE).unwrap(), 127);
+        assert!(note_from_name("G#9", MIDDLE_C_OCTAVE).is_err());
+        assert!(note_from_name("C10", MIDDLE_C_OCTAVE).is_err());
+        assert!(note_from_name("C20", MIDDLE_C_OCTAVE).is_err());
+        assert!(note_from_name("C2000000000", MIDDLE_C_OCTAVE).is_err());
+        assert!(note_from_name("C-2000000000", MIDDLE_C_OCTAVE).is_err());
+        assert!(note_from_name("C4", i32::MIN).is_err());
+        assert!(note_from_name("C4", i32::MAX).is_err());
+    }
+
+    #[test]
+    fn tuning_for_note() {
+        let path = Path::new("tests").join("tuning-microtuned-1.0.4.bab");
+        let mut preset = Preset::read_file(path).unwrap();
+        let tuning = &mut preset.tuning;
+        assert_eq!(tuning.root_key_name(), "C-1");
+        tuning.set_root_key_name("A4").unwrap();
+        assert_eq!(tuning.root_key, 69);
+        assert_eq!(tuning.root_key_name_with_middle_c(3), "A3");
+        assert!(tuning.set_root_key_name("X").is_err());
+        assert_eq!(tuning.root_key, 69);
+
+        assert_eq!(tuning.tuning_for_note(69), 12.5);
+        assert_eq!(tuning.tuning_for_note(21), 12.5);
+        assert_eq!(tuning.tuning_for_note(64), -7.0);
+        assert_eq!(tuning.tuning_for_note(66), 0.5);
+        assert_eq!(tuning.tuning_for_note(60), 0.0);
+    }
+}
@@ -0,0 +1,293 @@
+//! Randomizing presets for generative sound design.
+
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::Rng;
+use strum::IntoEnumIterator;
+use uom::si::f64::{Frequency, Ratio, Time};
+use uom::si::frequency::hertz;
+use uom::si::ratio::percent;
+use uom::si::time::millisecond;
+
+use crate::visit::{
+    DELAY_TIME_MAX, ENVELOPE_TIME_MAX_MS, OSCILLATOR_FINE_MAX, OSCILLATOR_PITCH_MAX,
+    OSCILLATOR_SEMITONE_MAX, TRANSPOSE_MAX, TUNING_OFFSET_MAX, UNISON_VOICES_MAX,
+    VIBRATO_FREQUENCY_MAX, VIBRATO_TIME_MAX_MS,
+};
+use crate::{
+    conversions, DelayFilterMode, EffectType, Envelope, FilterMode, ModSource, ModTarget,
+    Oscillator, Preset, OSCILLATOR_OCTAVE_MAX, OSCILLATOR_OCTAVE_MIN,
+};
+
+/// Limits on what [`Preset::randomize`] changes. The default randomizes
+/// everything it can.
+#[derive(Clone, Debug, Default)]
+pub struct RandomizeConstraints {
+    /// Keep the tuning of the preset and of each oscillator.
+    pub keep_tuning: bool,
+
+    /// Keep the oscillators and the noise, apart from their tuning.
+    pub keep_oscillators: bool,
+
+    /// Keep the filter and its envelope.
+    pub keep_filter: bool,
+
+    /// Keep the main envelope.
+    pub keep_envelope: bool,
+
+    /// Keep the LFOs, modulation envelopes, vibrato and modulation matrix.
+    pub keep_modulation: bool,
+
+    /// Keep the effects and their order.
+    pub keep_effects: bool,
+
+    /// Longest time for each envelope stage in milliseconds, such as to only
+    /// make short envelopes. Defaults to the longest Babylon allows, which is
+    /// also used when the limit is not a finite number.
+    pub max_envelope_ms: Option<f64>,
+}
+
+fn random_choice<R: Rng + ?Sized, E: IntoEnumIterator>(rng: &mut R, current: E) -> E {
+    E::iter().choose(rng).unwrap_or(current)
+}
+
+fn random_envelope<R: Rng + ?Sized>(rng: &mut R, envelope: &mut Envelope, max_ms: f64) {
+    let time = |rng: &mut R| Time::new::<millisecond>(rng.gen_range(0.0..=max_ms));
+    envelope.attack = time(rng);
+    envelope.decay = time(rng);
+    envelope.release = time(rng);
+    envelope.sustain = Ratio::new::<percent>(rng.gen());
+    envelope.attack_curve = random_choice(rng, envelope.attack_curve);
+    envelope.decay_falloff = random_choice(rng, envelope.decay_falloff);
+    envelope.release_falloff = random_choice(rng, envelope.release_falloff);
+}
+
+impl Preset {
+    /// Set the parameters of the preset to random values within the ranges
+    /// Babylon allows, except for the sections kept by the constraints.
+    ///
+    /// The name, description, master volume, polyphony and other settings
+    /// that affect how the preset is played rather than how it sounds are
+    /// never changed. Requires the `rand` feature.
+    pub fn randomize<R: Rng + ?Sized>(&mut self, rng: &mut R, constraints: RandomizeConstraints) {
+        let max_envelope_ms = constraints
+            .max_envelope_ms
+            .filter(|ms| ms.is_finite())
+            .unwrap_or(ENVELOPE_TIME_MAX_MS)
+            .clamp(0.0, ENVELOPE_TIME_MAX_MS);
+
+        if !constraints.keep_tuning {
+            for offset in &mut self.tuning.tunings {
+                *offset = rng.gen_range(-TUNING_OFFSET_MAX..=TUNING_OFFSET_MAX);
+            }
+            self.tuning.transpose = rng.gen_range(-TRANSPOSE_MAX..=TRANSPOSE_MAX).round();
+            for oscillator in &mut self.oscillators {
+                oscillator.pitch = rng.gen_range(-OSCILLATOR_PITCH_MAX..=OSCILLATOR_PITCH_MAX);
+                oscillator.fine_tuning =
+                    rng.gen_range(-OSCILLATOR_FINE_MAX..=OSCILLATOR_FINE_MAX) as i32;
+                oscillator.semitone_tuning =
+                    rng.gen_range(-OSCILLATOR_SEMITONE_MAX..=OSCILLATOR_SEMITONE_MAX) as i32;
+                oscillator.octave_tuning =
+                    rng.gen_range(OSCILLATOR_OCTAVE_MIN..=OSCILLATOR_OCTAVE_MAX);
+            }
+        }
+
+        if !constraints.keep_oscillators {
+            for (index, oscillator) in self.oscillators.iter_mut().enumerate() {
+                oscillator.enabled = rng.gen();
+                oscillator.waveform = random_choice(rng, oscillator.waveform);
+                oscillator.invert = rng.gen();
+                oscillator.pan = rng.gen();
+                oscillator.phase = rng.gen();
+                oscillator.reverse = rng.gen();
+                oscillator.free_run = rng.gen();
+                oscillator.volume = rng.gen();
+                if !Oscillator::supports_modulation(index) {
+                    continue;
+                }
+                oscillator.sync_all = rng.gen();
+                oscillator.unison.voices = rng.gen_range(1..=UNISON_VOICES_MAX) as u32;
+                oscillator.unison.detune = rng.gen();
+                oscillator.unison.spread = rng.gen();
+                oscillator.unison.mix = rng.gen();
+                oscillator.am_enabled = rng.gen();
+                oscillator.am_amount = rng.gen();
+                oscillator.fm_enabled = rng.gen();
+                oscillator.fm_amount = rng.gen();
+                oscillator.rm_enabled = rng.gen();
+                oscillator.rm_amount = rng.gen();
+            }
+
+            // Make sure there is something to hear.
+            if let Some(oscillator) = self.oscillators.first_mut() {
+                oscillator.enabled = true;
+            }
+
+            self.hard_sync = rng.gen();
+            self.noise.enabled = rng.gen();
+            self.noise.width = rng.gen();
+            self.noise.pan = rng.gen();
+            self.noise.volume = rng.gen();
+        }
+
+        if !constraints.keep_envelope {
+            random_envelope(rng, &mut self.envelope, max_envelope_ms);
+            self.envelope_curve = rng.gen();
+        }
+
+        if !constraints.keep_filter {
+            let filter = &mut self.filter;
+            filter.enabled = rng.gen();
+            filter.mode = random_choice(rng, filter.mode);
+            filter.resonance = rng.gen();
+            filter.cutoff_frequency = rng.gen_range(0.0..=100.0);
+            filter.key_tracking = rng.gen();
+            filter.envelope_amount = rng.gen();
+            filter.effect_enabled = rng.gen();
+            filter.effect_mode = random_choice(rng, filter.effect_mode);
+            filter.effect_amount = rng.gen();
+            random_envelope(rng, &mut filter.envelope, max_envelope_ms);
+            self.filter_envelope_curve = rng.gen();
+        }
+
+        if !constraints.keep_modulation {
+            for lfo in &mut self.lfos {
+                lfo.enabled = rng.gen();
+                lfo.waveform = random_choice(rng, lfo.waveform);
+                lfo.sync = rng.gen();
+                lfo.invert = rng.gen();
+                lfo.reverse = rng.gen();
+                lfo.mono = rng.gen();
+                lfo.free_run = rng.gen();
+                lfo.frequency = rng.gen();
+                lfo.phase = rng.gen();
+            }
+            for mod_envelope in &mut self.mod_envelopes {
+                mod_envelope.enabled = rng.gen();
+                mod_envelope.curve = rng.gen();
+                random_envelope(rng, &mut mod_envelope.envelope, max_envelope_ms);
+            }
+            self.vibrato.enabled = rng.gen();
+            let time =
+                |rng: &mut R| Time::new::<millisecond>(rng.gen_range(0.0..=VIBRATO_TIME_MAX_MS));
+            self.vibrato.attack = time(rng);
+            self.vibrato.delay = time(rng);
+            self.vibrato.frequency =
+                Frequency::new::<hertz>(rng.gen_range(0.0..=VIBRATO_FREQUENCY_MAX));
+            for item in &mut self.matrix {
+                item.source = random_choice(rng, ModSource::Off);
+                item.target = random_choice(rng, ModTarget::Off);
+                item.amount = rng.gen_range(-1.0..=1.0);
+            }
+        }
+
+        if !constraints.keep_effects {
+            let mut effect_order: Vec<EffectType> = EffectType::iter().collect();
+            effect_order.shuffle(rng);
+            self.effect_order = effect_order;
+
+            let chorus = &mut self.chorus;
+            chorus.enabled = rng.gen();
+            chorus.depth = rng.gen();
+            chorus.mix = rng.gen();
+            chorus.pre_delay = rng.gen();
+            chorus.ratio = rng.gen();
+
+            let delay = &mut self.delay;
+            delay.enabled = rng.gen();
+            delay.ping_pong = rng.gen();
+            delay.feedback = rng.gen();
+            delay.filter_mode = random_choice(rng, DelayFilterMode::Off);
+            delay.sync = rng.gen();
+            delay.time = if delay.sync {
+                rng.gen()
+            } else {
+                rng.gen_range(0.0..=DELAY_TIME_MAX)
+            };
+            delay.mix = rng.gen();
+
+            self.distortion.enabled = rng.gen();
+            self.distortion.gain = rng.gen();
+
+            let equalizer = &mut self.equalizer;
+            equalizer.enabled = rng.gen();
+            equalizer.low_gain = Ratio::new::<percent>(rng.gen());
+            equalizer.mid_gain = Ratio::new::<percent>(rng.gen());
+            equalizer.high_gain = Ratio::new::<percent>(rng.gen());
+
+            let effect_filter = &mut self.effect_filter;
+            effect_filter.enabled = rng.gen();
+            effect_filter.mode = random_choice(rng, FilterMode::LowPass);
+            effect_filter.resonance = rng.gen();
+            effect_filter.cutoff_frequency = rng.gen_range(0.0..=100.0);
+
+            self.lofi.enabled = rng.gen();
+            self.lofi.bitrate = rng.gen();
+            self.lofi.sample_rate = rng.gen();
+            self.lofi.mix = rng.gen();
+
+            let reverb = &mut self.reverb;
+            reverb.enabled = rng.gen();
+            reverb.dampen = rng.gen();
+            reverb.room = rng.gen();
+            let filter_mode = random_choice(rng, DelayFilterMode::Off);
+            reverb.filter = conversions::delay_filter_mode_to_control(filter_mode);
+            reverb.width = rng.gen();
+            reverb.mix = rng.gen();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use uom::si::time::millisecond;
+
+    use crate::{Preset, RandomizeConstraints};
+
+    #[test]
+    fn randomize() {
+        let path = Path::new("tests").join("init-1.0.2.bab");
+        let original = Preset::read_file(&path).unwrap();
+        let mut preset = Preset::read_file(&path).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let constraints = RandomizeConstraints {
+            keep_tuning: true,
+            max_envelope_ms: Some(100.0),
+            ..Default::default()
+        };
+        preset.randomize(&mut rng, constraints);
+
+        assert_eq!(preset.tuning.tunings, original.tuning.tunings);
+        assert_eq!(preset.tuning.transpose, original.tuning.transpose);
+        for (oscillator, original) in preset.oscillators.iter().zip(&original.oscillators) {
+            assert_eq!(oscillator.tuning(), original.tuning());
+        }
+        assert_eq!(preset.name, original.name);
+
+        assert_ne!(preset.fingerprint(), original.fingerprint());
+        assert_ne!(preset.reverb.room, original.reverb.room);
+        assert_ne!(preset.oscillators[0].volume, original.oscillators[0].volume);
+        assert!(preset.envelope.attack.get::<millisecond>() <= 100.0);
+        assert!(preset.oscillators[0].enabled);
+        assert_eq!(preset.validate(), Vec::new());
+    }
+
+    #[test]
+    fn randomize_non_finite_max_envelope() {
+        let path = Path::new("tests").join("init-1.0.2.bab");
+        let mut preset = Preset::read_file(path).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for max_envelope_ms in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let constraints = RandomizeConstraints {
+                max_envelope_ms: Some(max_envelope_ms),
+                ..Default::default()
+            };
+            preset.randomize(&mut rng, constraints);
+            assert!(preset.envelope.attack.get::<millisecond>().is_finite());
+            assert_eq!(preset.validate(), Vec::new());
+        }
+    }
+}
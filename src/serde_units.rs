@@ -0,0 +1,94 @@
+//! Serialization of quantities as plain numbers in the units they are stored
+//! in presets, for use with `#[serde(with = "...")]`.
+
+/// A [`Time`](uom::si::f64::Time) as milliseconds.
+pub(crate) mod milliseconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use uom::si::f64::Time;
+    use uom::si::time::millisecond;
+
+    pub(crate) fn serialize<S: Serializer>(time: &Time, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(time.get::<millisecond>())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Time, D::Error> {
+        f64::deserialize(deserializer).map(Time::new::<millisecond>)
+    }
+}
+
+/// A [`Frequency`](uom::si::f64::Frequency) as hertz.
+pub(crate) mod hertz {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use uom::si::f64::Frequency;
+    use uom::si::frequency::hertz;
+
+    pub(crate) fn serialize<S: Serializer>(
+        frequency: &Frequency,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(frequency.get::<hertz>())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Frequency, D::Error> {
+        f64::deserialize(deserializer).map(Frequency::new::<hertz>)
+    }
+}
+
+/// A [`Ratio`](uom::si::f64::Ratio) as the value stored in the preset, such
+/// as 0.9 for a sustain of 90%.
+pub(crate) mod percent {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use uom::si::f64::Ratio;
+    use uom::si::ratio::percent;
+
+    pub(crate) fn serialize<S: Serializer>(
+        ratio: &Ratio,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(ratio.get::<percent>())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Ratio, D::Error> {
+        f64::deserialize(deserializer).map(Ratio::new::<percent>)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use uom::si::ratio::percent;
+    use uom::si::time::millisecond;
+
+    use crate::Preset;
+
+    #[test]
+    fn json_round_trip() {
+        let path = Path::new("tests").join("envelopes-1.0.2.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let json = serde_json::to_string(&preset).unwrap();
+        assert!(json.contains(r#""attack":"#));
+        let reread: Preset = serde_json::from_str(&json).unwrap();
+        assert!(preset.diff(&reread).is_empty());
+        assert_eq!(reread.unknown_params, preset.unknown_params);
+        assert_eq!(reread.unknown_attributes, preset.unknown_attributes);
+
+        // Times are in milliseconds and ratios are as stored in the preset.
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let envelope = &value["envelope"];
+        assert_eq!(
+            envelope["attack"].as_f64().unwrap(),
+            preset.envelope.attack.get::<millisecond>()
+        );
+        assert_eq!(
+            envelope["sustain"].as_f64().unwrap(),
+            preset.envelope.sustain.get::<percent>()
+        );
+    }
+}
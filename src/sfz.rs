@@ -0,0 +1,102 @@
+//! Approximating a preset in the SFZ format used by samplers.
+
+use std::fmt::Write;
+
+use uom::si::ratio::percent;
+use uom::si::time::second;
+
+use crate::{FilterMode, Preset};
+
+/// Loudest resonance allowed by SFZ, in decibels.
+const SFZ_RESONANCE_MAX_DB: f64 = 40.0;
+
+/// Round to a precision that is meaningful for a sampler so the output is
+/// readable.
+fn round(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+impl Preset {
+    /// SFZ opcodes for the parts of the preset that samplers have in common
+    /// with Babylon, the amplifier envelope and the filter. Load a sample
+    /// of the oscillators to hear the result.
+    ///
+    /// This is a best effort approximation, not an export. Oscillators,
+    /// modulation and effects are not included and the filter slopes and
+    /// resonance only loosely match.
+    pub fn to_sfz_hint(&self) -> String {
+        let mut sfz = String::new();
+        let _ = writeln!(
+            sfz,
+            "// Approximation of the Babylon preset \"{}\"",
+            self.name
+        );
+        sfz.push_str("<region>\n");
+
+        let envelope = &self.envelope;
+        let _ = writeln!(
+            sfz,
+            "ampeg_attack={}",
+            round(envelope.attack.get::<second>())
+        );
+        let _ = writeln!(sfz, "ampeg_decay={}", round(envelope.decay.get::<second>()));
+
+        // Babylon stores the sustain from 0.0 to 1.0 and SFZ from 0 to 100.
+        let sustain = envelope.sustain.get::<percent>() * 100.0;
+        let _ = writeln!(sfz, "ampeg_sustain={}", round(sustain));
+        let _ = writeln!(
+            sfz,
+            "ampeg_release={}",
+            round(envelope.release.get::<second>())
+        );
+
+        if self.filter.enabled {
+            let fil_type = match self.filter.mode {
+                FilterMode::LowPass => "lpf_2p",
+                FilterMode::BandPass => "bpf_2p",
+                FilterMode::HighPass => "hpf_2p",
+                FilterMode::Notch => "brf_2p",
+                FilterMode::Peak => "pkf_2p",
+            };
+            let cutoff = self.filter.cutoff_hz();
+            let resonance = self.filter.resonance.clamp(0.0, 1.0) * SFZ_RESONANCE_MAX_DB;
+            let _ = writeln!(sfz, "fil_type={}", fil_type);
+            let _ = writeln!(sfz, "cutoff={}", round(cutoff));
+            let _ = writeln!(sfz, "resonance={}", round(resonance));
+        }
+
+        sfz
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::Preset;
+
+    #[test]
+    fn to_sfz_hint() {
+        let path = Path::new("tests").join("envelopes-1.0.2.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let sfz = preset.to_sfz_hint();
+        let opcodes: Vec<&str> = sfz.lines().collect();
+        assert!(opcodes.contains(&"<region>"));
+        assert!(opcodes.contains(&"ampeg_attack=0.001"));
+        assert!(opcodes.contains(&"ampeg_decay=15"));
+        assert!(opcodes.contains(&"ampeg_sustain=42"));
+        assert!(opcodes.contains(&"ampeg_release=0.076"));
+        assert!(!sfz.contains("fil_type"));
+
+        let path = Path::new("tests")
+            .join("effects")
+            .join("filter-bandpass-1.0.2.bab");
+        let mut preset = Preset::read_file(path).unwrap();
+        preset.filter.enabled = true;
+        let sfz = preset.to_sfz_hint();
+        let opcodes: Vec<&str> = sfz.lines().collect();
+        assert!(opcodes.contains(&"fil_type=bpf_2p"));
+        assert!(opcodes.contains(&"cutoff=20000"));
+        assert!(opcodes.contains(&"resonance=0"));
+    }
+}
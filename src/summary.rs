@@ -0,0 +1,159 @@
+use uom::si::ratio::percent;
+use uom::si::time::millisecond;
+
+use crate::{Effect, ModSource, ModTarget, PortamentoMode, Preset, Waveform};
+
+/// Attack and release longer than this suggest a pad, in milliseconds.
+const PAD_MIN_MS: f64 = 500.0;
+
+/// Decay shorter than this without sustain suggests a pluck, in milliseconds.
+const PLUCK_MAX_MS: f64 = 500.0;
+
+/// High level information about a preset for a row in a preset browser,
+/// gathered in one call. Each value is also available on its own from the
+/// method of the same name on [`Preset`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PresetSummary {
+    pub primary_waveform: Option<Waveform>,
+    pub enabled_effect_count: usize,
+
+    /// Routes in the modulation matrix that have an effect.
+    pub modulation_count: usize,
+    pub peak_voice_count: u32,
+    pub is_stereo: bool,
+    pub estimated_output_gain_db: f64,
+    pub inferred_tags: Vec<&'static str>,
+}
+
+impl Preset {
+    /// The high level information about the preset.
+    pub fn summary(&self) -> PresetSummary {
+        PresetSummary {
+            primary_waveform: self.primary_waveform(),
+            enabled_effect_count: self.enabled_effect_count(),
+            modulation_count: self.modulation_graph().edges.len(),
+            peak_voice_count: self.peak_voice_count(),
+            is_stereo: self.is_stereo(),
+            estimated_output_gain_db: self.estimated_output_gain_db(),
+            inferred_tags: self.inferred_tags(),
+        }
+    }
+
+    /// Number of effects that are enabled.
+    pub fn enabled_effect_count(&self) -> usize {
+        self.effects()
+            .filter(|(_, effect)| effect.is_enabled())
+            .count()
+    }
+
+    /// Most oscillator voices that can sound at once. Each note plays every
+    /// unison voice of every enabled oscillator, and the legato and
+    /// portamento modes only play one note at a time.
+    pub fn peak_voice_count(&self) -> u32 {
+        let notes = match self.portamento_mode {
+            PortamentoMode::Poly | PortamentoMode::PortaPoly => self.polyphony,
+            PortamentoMode::Legato | PortamentoMode::LegatoNoRetrigger | PortamentoMode::Porta => 1,
+        };
+        let voices_per_note: u32 = self
+            .oscillators
+            .iter()
+            .filter(|oscillator| oscillator.enabled)
+            .map(|oscillator| oscillator.unison.voices.max(1))
+            .sum();
+        notes.saturating_mul(voices_per_note)
+    }
+
+    /// Whether the left and right channels differ. That happens when a
+    /// source is panned or spread, or an audible effect widens the sound.
+    pub fn is_stereo(&self) -> bool {
+        let oscillators = self.oscillators.iter().any(|oscillator| {
+            let unison = &oscillator.unison;
+            oscillator.enabled
+                && (oscillator.pan != 0.5 || (unison.voices > 1 && unison.spread > 0.0))
+        });
+        let noise = self.noise.enabled && (self.noise.pan != 0.5 || self.noise.width > 0.0);
+        let effects = self.chorus.is_audible()
+            || (self.delay.is_audible() && self.delay.ping_pong)
+            || (self.reverb.is_audible() && self.reverb.width > 0.0);
+        oscillators || noise || effects
+    }
+
+    /// Descriptive tags guessed from the settings, such as `"pad"` or
+    /// `"mono"`, in alphabetical order.
+    ///
+    /// | Tag       | Reason                                               |
+    /// |-----------|------------------------------------------------------|
+    /// | `mono`    | Only one note can play at a time                     |
+    /// | `pad`     | Slow attack and release                              |
+    /// | `pluck`   | Short decay without any sustain                      |
+    /// | `sampled` | An enabled oscillator uses a sample based waveform   |
+    /// | `wobble`  | An LFO modulates the filter cutoff                   |
+    pub fn inferred_tags(&self) -> Vec<&'static str> {
+        let mut tags = Vec::new();
+
+        let single_note = match self.portamento_mode {
+            PortamentoMode::Poly | PortamentoMode::PortaPoly => self.polyphony <= 1,
+            _ => true,
+        };
+        if single_note {
+            tags.push("mono");
+        }
+
+        let envelope = &self.envelope;
+        if envelope.attack.get::<millisecond>() > PAD_MIN_MS
+            && envelope.release.get::<millisecond>() > PAD_MIN_MS
+        {
+            tags.push("pad");
+        }
+        if envelope.sustain.get::<percent>() == 0.0
+            && envelope.decay.get::<millisecond>() < PLUCK_MAX_MS
+        {
+            tags.push("pluck");
+        }
+
+        let sampled = self
+            .oscillators
+            .iter()
+            .any(|oscillator| oscillator.enabled && oscillator.waveform.is_sample_based());
+        if sampled {
+            tags.push("sampled");
+        }
+
+        let wobble = self.modulation_graph().edges.iter().any(|edge| {
+            matches!(edge.source, ModSource::Lfo1 | ModSource::Lfo2)
+                && edge.target == ModTarget::FilterCutoff
+                && self.filter.enabled
+        });
+        if wobble {
+            tags.push("wobble");
+        }
+
+        tags
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::Preset;
+
+    #[test]
+    fn summary() {
+        let path = Path::new("tests").join("init-1.0.2.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let summary = preset.summary();
+        assert_eq!(summary.primary_waveform, preset.primary_waveform());
+        assert_eq!(summary.enabled_effect_count, preset.enabled_effect_count());
+        assert_eq!(summary.enabled_effect_count, 0);
+        assert_eq!(summary.modulation_count, 1);
+        assert_eq!(summary.peak_voice_count, preset.peak_voice_count());
+        assert_eq!(summary.is_stereo, preset.is_stereo());
+        assert!(!summary.is_stereo);
+        assert_eq!(
+            summary.estimated_output_gain_db,
+            preset.estimated_output_gain_db()
+        );
+        assert_eq!(summary.inferred_tags, preset.inferred_tags());
+    }
+}
@@ -0,0 +1,342 @@
+use std::fmt::{Display, Formatter};
+
+use uom::si::f64::{Frequency, Ratio, Time};
+use uom::si::frequency::hertz;
+use uom::si::ratio::percent;
+use uom::si::time::millisecond;
+
+use crate::visit::{
+    DELAY_TIME_MAX, ENVELOPE_TIME_MAX_MS, GLIDE_MAX_MS, OSCILLATOR_FINE_MAX, OSCILLATOR_PITCH_MAX,
+    OSCILLATOR_SEMITONE_MAX, PITCH_BEND_RANGE_MAX, POLYPHONY_MAX, TRANSPOSE_MAX, TUNING_OFFSET_MAX,
+    UNISON_VOICES_MAX, VIBRATO_FREQUENCY_MAX, VIBRATO_TIME_MAX_MS,
+};
+use crate::{
+    Envelope, MatrixItem, Oscillator, ParamVisitor, Preset, MODULATION_MATRIX_SIZE,
+    OSCILLATOR_OCTAVE_MAX, OSCILLATOR_OCTAVE_MIN,
+};
+
+/// A parameter with a value outside of the range Babylon accepts. See
+/// [`Preset::validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    /// ID of the parameter in the preset file, such as `OSCPan_1` for the
+    /// pan of the first oscillator.
+    pub name: String,
+
+    /// Value in the preset, as it is in the [`Preset`] model.
+    pub value: f64,
+
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is {}, outside of {} to {}",
+            self.name, self.value, self.min, self.max
+        )
+    }
+}
+
+/// Collects the parameters that are out of range.
+#[derive(Default)]
+struct IssueCollector {
+    issues: Vec<ValidationIssue>,
+}
+
+impl IssueCollector {
+    fn check(&mut self, id: &str, value: f64, min: f64, max: f64) {
+        if !(min..=max).contains(&value) {
+            self.issues.push(ValidationIssue {
+                name: id.to_string(),
+                value,
+                min,
+                max,
+            });
+        }
+    }
+}
+
+impl ParamVisitor for IssueCollector {
+    fn visit_float(&mut self, id: &str, value: f64, min: f64, max: f64) {
+        self.check(id, value, min, max);
+    }
+
+    fn visit_int(&mut self, id: &str, value: i64, min: i64, max: i64) {
+        self.check(id, value as f64, min as f64, max as f64);
+    }
+}
+
+/// Clamp a value to a range, using the bottom of the range for values that
+/// are not a number.
+fn clamp(value: &mut f64, min: f64, max: f64) {
+    *value = if value.is_nan() {
+        min
+    } else {
+        value.clamp(min, max)
+    };
+}
+
+fn clamp_unit(value: &mut f64) {
+    clamp(value, 0.0, 1.0);
+}
+
+fn clamp_ratio(value: &mut Ratio) {
+    let mut ratio = value.get::<percent>();
+    clamp_unit(&mut ratio);
+    *value = Ratio::new::<percent>(ratio);
+}
+
+fn clamp_time(value: &mut Time, max_ms: f64) {
+    let mut ms = value.get::<millisecond>();
+    clamp(&mut ms, 0.0, max_ms);
+    *value = Time::new::<millisecond>(ms);
+}
+
+/// The settings that only some oscillators have, by the name of their
+/// parameter without the oscillator number, as numbers.
+fn modulation_values(oscillator: &Oscillator) -> [(&'static str, f64); 11] {
+    let switch = |enabled: bool| if enabled { 1.0 } else { 0.0 };
+    let unison = &oscillator.unison;
+    [
+        ("OSCSyncAll", switch(oscillator.sync_all)),
+        ("OSCNumVoice", unison.voices as f64),
+        ("OSCDetune", unison.detune),
+        ("OSCSpread", unison.spread),
+        ("OSCUniMix", unison.mix),
+        ("OSCAMSwitch", switch(oscillator.am_enabled)),
+        ("OSCAM", oscillator.am_amount),
+        ("OSCFMSwitch", switch(oscillator.fm_enabled)),
+        ("OSCFM", oscillator.fm_amount),
+        ("OSCRMSwitch", switch(oscillator.rm_enabled)),
+        ("OSCRM", oscillator.rm_amount),
+    ]
+}
+
+fn normalize_envelope(envelope: &mut Envelope) {
+    clamp_time(&mut envelope.attack, ENVELOPE_TIME_MAX_MS);
+    clamp_time(&mut envelope.decay, ENVELOPE_TIME_MAX_MS);
+    clamp_ratio(&mut envelope.sustain);
+    clamp_time(&mut envelope.release, ENVELOPE_TIME_MAX_MS);
+}
+
+impl Preset {
+    /// The parameters with values outside of the range Babylon accepts, in
+    /// the order they are read. The ranges are the ones reported by
+    /// [`Preset::visit_params`]. Values that are not a number are always
+    /// out of range. Settings of oscillators that don't have them, see
+    /// [`Oscillator::supports_modulation`], are out of range unless they are
+    /// the default. A modulation matrix without exactly
+    /// [`MODULATION_MATRIX_SIZE`] routes is reported last, as `Matrix`.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut collector = IssueCollector::default();
+        self.visit_params(&mut collector);
+        for (index, oscillator) in self.oscillators.iter().enumerate() {
+            if Oscillator::supports_modulation(index) {
+                continue;
+            }
+            let default = Oscillator::default();
+            let values = modulation_values(oscillator);
+            for ((name, value), (_, default)) in values.iter().zip(modulation_values(&default)) {
+                let id = format!("{}_{}", name, index + 1);
+                collector.check(&id, *value, default, default);
+            }
+        }
+        let size = MODULATION_MATRIX_SIZE as f64;
+        collector.check("Matrix", self.matrix.len() as f64, size, size);
+        collector.issues
+    }
+
+    /// Clamp every parameter to the range Babylon accepts, like Babylon does
+    /// when it loads a preset. Values that are not a number are set to the
+    /// bottom of their range. Afterwards [`Preset::validate`] finds no
+    /// issues.
+    pub fn normalize(&mut self) {
+        normalize_envelope(&mut self.envelope);
+
+        let tuning = &mut self.tuning;
+        for offset in &mut tuning.tunings {
+            clamp(offset, -TUNING_OFFSET_MAX, TUNING_OFFSET_MAX);
+        }
+        clamp(&mut tuning.transpose, -TRANSPOSE_MAX, TRANSPOSE_MAX);
+
+        let filter = &mut self.filter;
+        normalize_envelope(&mut filter.envelope);
+        clamp_unit(&mut filter.resonance);
+        clamp(&mut filter.cutoff_frequency, 0.0, 100.0);
+        clamp_unit(&mut filter.key_tracking);
+        clamp_unit(&mut filter.envelope_amount);
+        clamp_unit(&mut filter.effect_amount);
+
+        for (index, oscillator) in self.oscillators.iter_mut().enumerate() {
+            if !Oscillator::supports_modulation(index) {
+                oscillator.reset_modulation();
+            }
+            clamp_unit(&mut oscillator.pan);
+            clamp_unit(&mut oscillator.phase);
+            clamp(
+                &mut oscillator.pitch,
+                -OSCILLATOR_PITCH_MAX,
+                OSCILLATOR_PITCH_MAX,
+            );
+            let fine_max = OSCILLATOR_FINE_MAX as i32;
+            oscillator.fine_tuning = oscillator.fine_tuning.clamp(-fine_max, fine_max);
+            let semitone_max = OSCILLATOR_SEMITONE_MAX as i32;
+            oscillator.semitone_tuning = oscillator
+                .semitone_tuning
+                .clamp(-semitone_max, semitone_max);
+            oscillator.octave_tuning = oscillator
+                .octave_tuning
+                .clamp(OSCILLATOR_OCTAVE_MIN, OSCILLATOR_OCTAVE_MAX);
+            clamp_unit(&mut oscillator.volume);
+            let unison = &mut oscillator.unison;
+            unison.voices = unison.voices.clamp(1, UNISON_VOICES_MAX as u32);
+            clamp_unit(&mut unison.detune);
+            clamp_unit(&mut unison.spread);
+            clamp_unit(&mut unison.mix);
+            clamp_unit(&mut oscillator.am_amount);
+            clamp_unit(&mut oscillator.fm_amount);
+            clamp_unit(&mut oscillator.rm_amount);
+        }
+
+        let noise = &mut self.noise;
+        clamp_unit(&mut noise.width);
+        clamp_unit(&mut noise.pan);
+        clamp_unit(&mut noise.volume);
+
+        for lfo in &mut self.lfos {
+            clamp_unit(&mut lfo.frequency);
+            clamp_unit(&mut lfo.phase);
+        }
+
+        for mod_envelope in &mut self.mod_envelopes {
+            clamp_unit(&mut mod_envelope.curve);
+            normalize_envelope(&mut mod_envelope.envelope);
+        }
+
+        let vibrato = &mut self.vibrato;
+        clamp_time(&mut vibrato.attack, VIBRATO_TIME_MAX_MS);
+        let mut frequency = vibrato.frequency.get::<hertz>();
+        clamp(&mut frequency, 0.0, VIBRATO_FREQUENCY_MAX);
+        vibrato.frequency = Frequency::new::<hertz>(frequency);
+        clamp_time(&mut vibrato.delay, VIBRATO_TIME_MAX_MS);
+
+        self.matrix
+            .resize(MODULATION_MATRIX_SIZE, MatrixItem::default());
+        for item in &mut self.matrix {
+            clamp(&mut item.amount, -1.0, 1.0);
+        }
+
+        let chorus = &mut self.chorus;
+        clamp_unit(&mut chorus.depth);
+        clamp_unit(&mut chorus.mix);
+        clamp_unit(&mut chorus.pre_delay);
+        clamp_unit(&mut chorus.ratio);
+
+        let delay = &mut self.delay;
+        clamp_unit(&mut delay.feedback);
+        clamp(&mut delay.time, 0.0, DELAY_TIME_MAX);
+        clamp_unit(&mut delay.mix);
+
+        clamp_unit(&mut self.distortion.gain);
+
+        let equalizer = &mut self.equalizer;
+        clamp_ratio(&mut equalizer.high_gain);
+        clamp_ratio(&mut equalizer.low_gain);
+        clamp_ratio(&mut equalizer.mid_gain);
+
+        // The filter in the effect chain only has a resonance and cutoff.
+        let effect_filter = &mut self.effect_filter;
+        clamp_unit(&mut effect_filter.resonance);
+        clamp(&mut effect_filter.cutoff_frequency, 0.0, 100.0);
+
+        let lofi = &mut self.lofi;
+        clamp_unit(&mut lofi.bitrate);
+        clamp_unit(&mut lofi.sample_rate);
+        clamp_unit(&mut lofi.mix);
+
+        let reverb = &mut self.reverb;
+        clamp_unit(&mut reverb.dampen);
+        clamp_unit(&mut reverb.room);
+        clamp_unit(&mut reverb.width);
+        clamp_unit(&mut reverb.mix);
+
+        clamp_unit(&mut self.master_volume_normalized);
+        self.polyphony = self.polyphony.clamp(1, POLYPHONY_MAX as u32);
+        clamp(&mut self.glide, 0.0, GLIDE_MAX_MS);
+        clamp_unit(&mut self.velocity_curve);
+        clamp_unit(&mut self.key_track_curve);
+        clamp(&mut self.pitch_bend_range, 0.0, PITCH_BEND_RANGE_MAX);
+        clamp_unit(&mut self.envelope_curve);
+        clamp_unit(&mut self.filter_envelope_curve);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use uom::si::f64::Ratio;
+    use uom::si::ratio::percent;
+
+    use crate::{Preset, MODULATION_MATRIX_SIZE};
+
+    #[test]
+    fn normalize() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let init = Preset::read_file(path).unwrap();
+        assert!(init.validate().is_empty());
+
+        let mut preset = init.clone();
+        preset.envelope.sustain = Ratio::new::<percent>(1.5);
+        preset.oscillators[0].pan = 1.5;
+        preset.oscillators[1].unison.voices = 20;
+        preset.matrix[0].amount = -2.0;
+        preset.delay.mix = -0.5;
+        preset.master_volume_normalized = f64::NAN;
+        preset.oscillators[2].fm_enabled = true;
+        preset.oscillators[2].unison.voices = 3;
+        preset.matrix.pop();
+        let names: Vec<String> = preset
+            .validate()
+            .into_iter()
+            .map(|issue| issue.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "EnvSustain",
+                "OSCPan_1",
+                "OSCNumVoice_2",
+                "MatrixAmount_1",
+                "DelayMix",
+                "MainVol",
+                "OSCNumVoice_3",
+                "OSCFMSwitch_3",
+                "Matrix"
+            ]
+        );
+        assert_eq!(
+            preset.validate()[1].to_string(),
+            "OSCPan_1 is 1.5, outside of 0 to 1"
+        );
+
+        preset.normalize();
+        assert!(preset.validate().is_empty());
+        assert_eq!(preset.envelope.sustain.get::<percent>(), 1.0);
+        assert_eq!(preset.oscillators[0].pan, 1.0);
+        assert_eq!(preset.oscillators[1].unison.voices, 9);
+        assert_eq!(preset.matrix[0].amount, -1.0);
+        assert_eq!(preset.delay.mix, 0.0);
+        assert_eq!(preset.master_volume_normalized, 0.0);
+        assert!(!preset.oscillators[2].fm_enabled);
+        assert_eq!(preset.oscillators[2].unison.voices, 1);
+        assert_eq!(preset.matrix.len(), MODULATION_MATRIX_SIZE);
+
+        let mut normalized = init.clone();
+        normalized.normalize();
+        assert_eq!(normalized, init);
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt::Debug;
+
+use strum::IntoEnumIterator;
+use uom::si::f64::{Ratio, Time};
+use uom::si::frequency::hertz;
+use uom::si::ratio::percent;
+use uom::si::time::millisecond;
+
+use crate::{
+    Envelope, Oscillator, Preset, NOISE_COLOR_PARAM_ID, OSCILLATOR_OCTAVE_MAX,
+    OSCILLATOR_OCTAVE_MIN,
+};
+
+/// Names of the notes in the IDs of the per note tuning parameters, in the
+/// order of [`Tuning::tunings`](crate::Tuning::tunings).
+pub(crate) const TUNING_NOTES: [&str; 12] = [
+    "A", "ASharp", "B", "C", "CSharp", "D", "DSharp", "E", "F", "FSharp", "G", "GSharp",
+];
+
+/// Longest envelope stage, in milliseconds.
+pub(crate) const ENVELOPE_TIME_MAX_MS: f64 = 20_000.0;
+
+/// Largest per note tuning offset, in cents.
+pub(crate) const TUNING_OFFSET_MAX: f64 = 100.0;
+
+/// Largest transpose, in semitones.
+pub(crate) const TRANSPOSE_MAX: f64 = 24.0;
+
+/// Largest oscillator pitch offset, in semitones.
+pub(crate) const OSCILLATOR_PITCH_MAX: f64 = 12.0;
+
+/// Largest oscillator fine tuning, in cents.
+pub(crate) const OSCILLATOR_FINE_MAX: i64 = 100;
+
+/// Largest oscillator semitone tuning.
+pub(crate) const OSCILLATOR_SEMITONE_MAX: i64 = 12;
+
+/// Most unison voices for each oscillator.
+pub(crate) const UNISON_VOICES_MAX: i64 = 9;
+
+/// Longest vibrato attack and delay, in milliseconds.
+pub(crate) const VIBRATO_TIME_MAX_MS: f64 = 5_000.0;
+
+/// Fastest vibrato, in hertz.
+pub(crate) const VIBRATO_FREQUENCY_MAX: f64 = 20.0;
+
+/// Longest unsynced delay time, in seconds.
+pub(crate) const DELAY_TIME_MAX: f64 = 2.0;
+
+/// Most notes that can play at once.
+pub(crate) const POLYPHONY_MAX: i64 = 32;
+
+/// Longest glide, in milliseconds.
+pub(crate) const GLIDE_MAX_MS: f64 = 5_000.0;
+
+/// Largest pitch bend range, in semitones.
+pub(crate) const PITCH_BEND_RANGE_MAX: f64 = 24.0;
+
+/// Callbacks for each parameter of a preset, for tools that treat every
+/// parameter the same way. See [`Preset::visit_params`].
+///
+/// Parameters are identified by the IDs used in the preset file. Values are
+/// as they are in the [`Preset`] model, with times in milliseconds and
+/// ratios from 0.0 to 1.0. Every callback does nothing by default.
+pub trait ParamVisitor {
+    /// A continuous parameter and its range.
+    fn visit_float(&mut self, _id: &str, _value: f64, _min: f64, _max: f64) {}
+
+    /// A whole number parameter and its range.
+    fn visit_int(&mut self, _id: &str, _value: i64, _min: i64, _max: i64) {}
+
+    /// A parameter that is one of a list of choices, with the names of all
+    /// the choices.
+    fn visit_enum(&mut self, _id: &str, _variant: &str, _all_variants: &[&str]) {}
+
+    /// A switch.
+    fn visit_bool(&mut self, _id: &str, _value: bool) {}
+}
+
+fn visit_choice<V, E>(visitor: &mut V, id: &str, value: E)
+where
+    V: ParamVisitor,
+    E: Debug + IntoEnumIterator,
+{
+    let names: Vec<String> = E::iter().map(|variant| format!("{:?}", variant)).collect();
+    let all_variants: Vec<&str> = names.iter().map(String::as_str).collect();
+    visitor.visit_enum(id, &format!("{:?}", value), &all_variants);
+}
+
+fn visit_ratio<V: ParamVisitor>(visitor: &mut V, id: &str, value: Ratio) {
+    visitor.visit_float(id, value.get::<percent>(), 0.0, 1.0);
+}
+
+fn visit_time<V: ParamVisitor>(visitor: &mut V, id: &str, value: Time) {
+    visitor.visit_float(id, value.get::<millisecond>(), 0.0, ENVELOPE_TIME_MAX_MS);
+}
+
+fn visit_unit<V: ParamVisitor>(visitor: &mut V, id: &str, value: f64) {
+    visitor.visit_float(id, value, 0.0, 1.0);
+}
+
+/// Visit an envelope whose parameters are named with a prefix for the times
+/// and curves and a suffix, such as `ModEnvAttack_1` and `ModAttCurveType_1`.
+fn visit_envelope<V: ParamVisitor>(
+    visitor: &mut V,
+    envelope: &Envelope,
+    time_prefix: &str,
+    curve_prefix: &str,
+    suffix: &str,
+) {
+    let id = |name: &str, prefix: &str| format!("{}{}{}", prefix, name, suffix);
+    visit_time(visitor, &id("Attack", time_prefix), envelope.attack);
+    visit_choice(
+        visitor,
+        &id("AttCurveType", curve_prefix),
+        envelope.attack_curve,
+    );
+    visit_time(visitor, &id("Decay", time_prefix), envelope.decay);
+    visit_choice(
+        visitor,
+        &id("DecCurveType", curve_prefix),
+        envelope.decay_falloff,
+    );
+    visit_ratio(visitor, &id("Sustain", time_prefix), envelope.sustain);
+    visit_time(visitor, &id("Release", time_prefix), envelope.release);
+    visit_choice(
+        visitor,
+        &id("RelCurveType", curve_prefix),
+        envelope.release_falloff,
+    );
+}
+
+impl Preset {
+    /// Call the visitor for every parameter of the preset, in the order they
+    /// are read. The attributes of the preset, such as the name and the
+    /// effect order, are not parameters and are not visited.
+    pub fn visit_params<V: ParamVisitor>(&self, visitor: &mut V) {
+        visit_envelope(visitor, &self.envelope, "Env", "", "");
+
+        let tuning = &self.tuning;
+        for (note, offset) in TUNING_NOTES.iter().zip(tuning.tunings) {
+            let id = format!("Tune{}", note);
+            visitor.visit_float(&id, offset, -TUNING_OFFSET_MAX, TUNING_OFFSET_MAX);
+        }
+        visitor.visit_float("Transpose", tuning.transpose, -TRANSPOSE_MAX, TRANSPOSE_MAX);
+
+        let filter = &self.filter;
+        visit_envelope(visitor, &filter.envelope, "FilterEnv", "Filter", "");
+        visitor.visit_bool("FilterSwitch", filter.enabled);
+        visit_choice(visitor, "FilterType", filter.mode);
+        visit_unit(visitor, "FilterRes", filter.resonance);
+        visitor.visit_float("FilterCut", filter.cutoff_frequency, 0.0, 100.0);
+        visit_unit(visitor, "FilterKey", filter.key_tracking);
+        visit_unit(visitor, "FilterEnv", filter.envelope_amount);
+        visitor.visit_bool("FilterDriveSwitch", filter.effect_enabled);
+        visit_choice(visitor, "FilterDriveType", filter.effect_mode);
+        visit_unit(visitor, "FilterDrive", filter.effect_amount);
+
+        for (index, oscillator) in self.oscillators.iter().enumerate() {
+            let id = |name: &str| format!("OSC{}_{}", name, index + 1);
+            visitor.visit_bool(&id("Switch"), oscillator.enabled);
+            visit_choice(visitor, &id("WaveType"), oscillator.waveform);
+            visitor.visit_bool(&id("Invert"), oscillator.invert);
+            visit_unit(visitor, &id("Pan"), oscillator.pan);
+            visit_unit(visitor, &id("Phase"), oscillator.phase);
+            visitor.visit_float(
+                &id("Pitch"),
+                oscillator.pitch,
+                -OSCILLATOR_PITCH_MAX,
+                OSCILLATOR_PITCH_MAX,
+            );
+            visitor.visit_int(
+                &id("Fine"),
+                oscillator.fine_tuning.into(),
+                -OSCILLATOR_FINE_MAX,
+                OSCILLATOR_FINE_MAX,
+            );
+            visitor.visit_int(
+                &id("Semi"),
+                oscillator.semitone_tuning.into(),
+                -OSCILLATOR_SEMITONE_MAX,
+                OSCILLATOR_SEMITONE_MAX,
+            );
+            visitor.visit_int(
+                &id("Octave"),
+                oscillator.octave_tuning.into(),
+                OSCILLATOR_OCTAVE_MIN.into(),
+                OSCILLATOR_OCTAVE_MAX.into(),
+            );
+            visitor.visit_bool(&id("Reverse"), oscillator.reverse);
+            visitor.visit_bool(&id("FreeRun"), oscillator.free_run);
+            visit_unit(visitor, &id("Vol"), oscillator.volume);
+            if !Oscillator::supports_modulation(index) {
+                continue;
+            }
+            visitor.visit_bool(&id("SyncAll"), oscillator.sync_all);
+            let unison = &oscillator.unison;
+            visitor.visit_int(&id("NumVoice"), unison.voices.into(), 1, UNISON_VOICES_MAX);
+            visit_unit(visitor, &id("Detune"), unison.detune);
+            visit_unit(visitor, &id("Spread"), unison.spread);
+            visit_unit(visitor, &id("UniMix"), unison.mix);
+            visitor.visit_bool(&id("AMSwitch"), oscillator.am_enabled);
+            visit_unit(visitor, &id("AM"), oscillator.am_amount);
+            visitor.visit_bool(&id("FMSwitch"), oscillator.fm_enabled);
+            visit_unit(visitor, &id("FM"), oscillator.fm_amount);
+            visitor.visit_bool(&id("RMSwitch"), oscillator.rm_enabled);
+            visit_unit(visitor, &id("RM"), oscillator.rm_amount);
+        }
+
+        let noise = &self.noise;
+        visitor.visit_bool("OSCSwitch_N", noise.enabled);
+        visit_unit(visitor, "OSCWidth_N", noise.width);
+        visit_unit(visitor, "OSCPan_N", noise.pan);
+        visit_unit(visitor, "OSCVol_N", noise.volume);
+        visit_choice(visitor, NOISE_COLOR_PARAM_ID, noise.color);
+
+        for (index, lfo) in self.lfos.iter().enumerate() {
+            let id = |name: &str| format!("LFO{}_{}", name, index + 1);
+            visitor.visit_bool(&id("Switch"), lfo.enabled);
+            visit_choice(visitor, &id("WaveType"), lfo.waveform);
+            visitor.visit_bool(&id("Sync"), lfo.sync);
+            visitor.visit_bool(&id("Invert"), lfo.invert);
+            visitor.visit_bool(&id("Reverse"), lfo.reverse);
+            visitor.visit_bool(&id("Mono"), lfo.mono);
+            visitor.visit_bool(&id("FreeRun"), lfo.free_run);
+            visit_unit(visitor, &id("Freq"), lfo.frequency);
+            visit_unit(visitor, &id("Phase"), lfo.phase);
+        }
+
+        for (index, mod_envelope) in self.mod_envelopes.iter().enumerate() {
+            let suffix = format!("_{}", index + 1);
+            visitor.visit_bool(&format!("ModEnvSwitch{}", suffix), mod_envelope.enabled);
+            visit_unit(
+                visitor,
+                &format!("ModEnvCurveType{}", suffix),
+                mod_envelope.curve,
+            );
+            visit_envelope(visitor, &mod_envelope.envelope, "ModEnv", "Mod", &suffix);
+        }
+
+        let vibrato = &self.vibrato;
+        visitor.visit_bool("VibSwitch", vibrato.enabled);
+        visitor.visit_float(
+            "VibAttack",
+            vibrato.attack.get::<millisecond>(),
+            0.0,
+            VIBRATO_TIME_MAX_MS,
+        );
+        visitor.visit_float(
+            "VibFrequency",
+            vibrato.frequency.get::<hertz>(),
+            0.0,
+            VIBRATO_FREQUENCY_MAX,
+        );
+        visitor.visit_float(
+            "VibDelay",
+            vibrato.delay.get::<millisecond>(),
+            0.0,
+            VIBRATO_TIME_MAX_MS,
+        );
+
+        for (index, item) in self.matrix.iter().enumerate() {
+            let id = |name: &str| format!("Matrix{}_{}", name, index + 1);
+            visit_choice(visitor, &id("Source"), item.source);
+            visit_choice(visitor, &id("Target"), item.target);
+            visitor.visit_float(&id("Amount"), item.amount, -1.0, 1.0);
+        }
+
+        let chorus = &self.chorus;
+        visitor.visit_bool("ChorusSwitch", chorus.enabled);
+        visit_unit(visitor, "ChorusDepth", chorus.depth);
+        visit_unit(visitor, "ChorusMix", chorus.mix);
+        visit_unit(visitor, "ChorusPdelay", chorus.pre_delay);
+        visit_unit(visitor, "ChorusRatio", chorus.ratio);
+
+        let delay = &self.delay;
+        visit_choice(visitor, "DelayLP", delay.filter_mode);
+        visitor.visit_bool("DelaySwitch", delay.enabled);
+        visitor.visit_bool("DelayMode", delay.ping_pong);
+        visit_unit(visitor, "DelayFeed", delay.feedback);
+        visitor.visit_bool("DelaySync", delay.sync);
+        visitor.visit_float("DelayTime", delay.time, 0.0, DELAY_TIME_MAX);
+        visit_unit(visitor, "DelayMix", delay.mix);
+
+        let distortion = &self.distortion;
+        visitor.visit_bool("DistSwitch", distortion.enabled);
+        visit_unit(visitor, "DistGain", distortion.gain);
+
+        let equalizer = &self.equalizer;
+        visitor.visit_bool("EQSwitch", equalizer.enabled);
+        visit_ratio(visitor, "EQHigh", equalizer.high_gain);
+        visit_ratio(visitor, "EQLow", equalizer.low_gain);
+        visit_ratio(visitor, "EQMid", equalizer.mid_gain);
+
+        let effect_filter = &self.effect_filter;
+        visitor.visit_bool("FXFilterSwitch", effect_filter.enabled);
+        visit_choice(visitor, "FXFilterType", effect_filter.mode);
+        visit_unit(visitor, "FXFilterRes", effect_filter.resonance);
+        visitor.visit_float("FXFilterCut", effect_filter.cutoff_frequency, 0.0, 100.0);
+
+        let lofi = &self.lofi;
+        visitor.visit_bool("LoFiSwitch", lofi.enabled);
+        visit_unit(visitor, "LoFiBitRate", lofi.bitrate);
+        visit_unit(visitor, "LoFiSampleRate", lofi.sample_rate);
+        visit_unit(visitor, "LoFiMix", lofi.mix);
+
+        let reverb = &self.reverb;
+        visitor.visit_bool("ReverbSwitch", reverb.enabled);
+        visit_unit(visitor, "ReverbDamp", reverb.dampen);
+        visit_unit(visitor, "ReverbRoom", reverb.room);
+        visit_choice(visitor, "ReverbLP", reverb.filter_mode());
+        visit_unit(visitor, "ReverbWidth", reverb.width);
+        visit_unit(visitor, "ReverbMix", reverb.mix);
+
+        visit_unit(visitor, "MainVol", self.master_volume_normalized);
+        visitor.visit_int("MaxVoices", self.polyphony.into(), 1, POLYPHONY_MAX);
+        visit_choice(visitor, "PortaMode", self.portamento_mode);
+        visit_choice(visitor, "MidiPlayMode", self.midi_play_mode);
+        visitor.visit_float("Glide", self.glide, 0.0, GLIDE_MAX_MS);
+        visit_unit(visitor, "VeloCurve", self.velocity_curve);
+        visit_unit(visitor, "KeyTrackCurve", self.key_track_curve);
+        visitor.visit_float("PBRange", self.pitch_bend_range, 0.0, PITCH_BEND_RANGE_MAX);
+        visitor.visit_bool("LimitSwitch", self.limit_enabled);
+        visit_unit(visitor, "EnvCurveType", self.envelope_curve);
+        visit_unit(visitor, "FilterEnvCurveType", self.filter_envelope_curve);
+        visitor.visit_bool("OSCSync21", self.hard_sync);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{ParamVisitor, Preset, KNOWN_PARAM_IDS};
+
+    #[derive(Default)]
+    struct IdCollector {
+        ids: Vec<String>,
+    }
+
+    impl ParamVisitor for IdCollector {
+        fn visit_float(&mut self, id: &str, value: f64, min: f64, max: f64) {
+            assert!(min < max);
+            assert!(value.is_finite());
+            self.ids.push(id.to_string());
+        }
+
+        fn visit_int(&mut self, id: &str, _value: i64, min: i64, max: i64) {
+            assert!(min < max);
+            self.ids.push(id.to_string());
+        }
+
+        fn visit_enum(&mut self, id: &str, variant: &str, all_variants: &[&str]) {
+            assert!(all_variants.contains(&variant));
+            self.ids.push(id.to_string());
+        }
+
+        fn visit_bool(&mut self, id: &str, _value: bool) {
+            self.ids.push(id.to_string());
+        }
+    }
+
+    /// Every known parameter except the unused `PCH` is visited once.
+    #[test]
+    fn visit_params() {
+        let path = Path::new("tests").join("init-1.0.2.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let mut collector = IdCollector::default();
+        preset.visit_params(&mut collector);
+
+        let mut visited = collector.ids;
+        visited.sort();
+        let mut expected: Vec<String> = KNOWN_PARAM_IDS
+            .iter()
+            .filter(|id| **id != "PCH")
+            .map(|id| id.to_string())
+            .collect();
+        expected.sort();
+        assert_eq!(visited.len(), KNOWN_PARAM_IDS.len() - 1);
+        assert_eq!(visited, expected);
+    }
+}
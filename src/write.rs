@@ -0,0 +1,523 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+
+use uom::si::frequency::hertz;
+use uom::si::ratio::percent;
+use uom::si::time::millisecond;
+
+use crate::{
+    conversions, Envelope, NoiseColor, Oscillator, Param, PluginParamTree, Preset,
+    EFFECT_SLOT_PREFIX, NOISE_COLOR_PARAM_ID, PRESET_INFO_DEFAULT,
+};
+
+impl PluginParamTree {
+    /// Add a floating point parameter. Values are written the way Rust
+    /// formats them for debugging so they are always read back exactly.
+    fn push_f64(&mut self, id: &str, value: f64) {
+        self.params.push(Param {
+            id: id.to_string(),
+            value: Some(format!("{:?}", value)),
+        });
+    }
+
+    /// Add a boolean parameter. Babylon stores booleans as floating point
+    /// values.
+    fn push_bool(&mut self, id: &str, value: bool) {
+        self.push_f64(id, if value { 1.0 } else { 0.0 });
+    }
+
+    fn push_envelope(&mut self, ids: [&str; 7], envelope: &Envelope) {
+        self.push_f64(ids[0], envelope.attack.get::<millisecond>());
+        self.push_f64(ids[1], envelope.attack_curve.value());
+        self.push_f64(ids[2], envelope.decay.get::<millisecond>());
+        self.push_f64(ids[3], envelope.decay_falloff.value());
+        self.push_f64(ids[4], envelope.sustain.get::<percent>());
+        self.push_f64(ids[5], envelope.release.get::<millisecond>());
+        self.push_f64(ids[6], envelope.release_falloff.value());
+    }
+
+    /// Write the tree as XML in the layout Babylon uses, with the parameters
+    /// sorted by their identifiers.
+    ///
+    /// `serde_xml_rs` is not used because it writes fields as elements rather
+    /// than attributes, which Babylon can't load.
+    pub fn write<W: Write>(&self, mut output: W) -> Result<(), Error> {
+        let mut attributes = self.attributes.clone();
+        attributes.insert("Scale".to_string(), self.scale.to_string());
+        attributes.insert("Root".to_string(), self.root_key.to_string());
+        attributes.insert("PresetName".to_string(), self.preset_name.clone());
+        attributes.insert("CustomScale".to_string(), self.custom_scale.to_string());
+        let optional_attributes = [
+            ("PresetID", self.preset_id.map(|id| id.to_string())),
+            ("PresetFolder", self.preset_folder.map(|id| id.to_string())),
+            ("PresetInfo", self.preset_info.clone()),
+            ("PresetAuthor", self.preset_author.clone()),
+            ("PresetDate", self.preset_date.clone()),
+            ("PresetCategory", self.preset_category.clone()),
+        ];
+        for (name, value) in optional_attributes {
+            if let Some(value) = value {
+                attributes.insert(name.to_string(), value);
+            }
+        }
+
+        writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(output)?;
+        write!(output, "<PluginParamTree")?;
+        for (name, value) in &attributes {
+            write!(output, r#" {}="{}""#, name, escape(value))?;
+        }
+        writeln!(output, ">")?;
+
+        let mut params: Vec<&Param> = self.params.iter().collect();
+        params.sort_by(|a, b| a.id.cmp(&b.id));
+        for param in params {
+            match &param.value {
+                Some(value) => writeln!(
+                    output,
+                    r#"  <PARAM id="{}" value="{}"/>"#,
+                    escape(&param.id),
+                    escape(value)
+                )?,
+                None => writeln!(output, r#"  <PARAM id="{}"/>"#, escape(&param.id))?,
+            }
+        }
+        writeln!(output, "</PluginParamTree>")?;
+        output.flush()
+    }
+}
+
+/// Escape text for use in an attribute value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Preset {
+    /// Write the preset to a file that Babylon can load.
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let output = BufWriter::new(File::create(path)?);
+        self.write(output)
+    }
+
+    /// Write the preset in the format of a Babylon preset file.
+    pub fn write<W: Write>(&self, output: W) -> Result<(), Error> {
+        self.to_param_tree().write(output)
+    }
+
+    /// Convert the preset to the parameters stored in a preset file. Every
+    /// parameter that is read from a file is included, using the same
+    /// encoding, and `PCH` is written back unchanged. Times are in milliseconds, the envelope sustain is a
+    /// percentage and switches are 1.0 or 0.0.
+    pub fn to_param_tree(&self) -> PluginParamTree {
+        let metadata = self.metadata.clone().unwrap_or_default();
+        let mut attributes = self.unknown_attributes.clone();
+        let effect_ids = self
+            .effect_order
+            .iter()
+            .map(|effect_type| effect_type.file_id())
+            .chain(self.extra_effect_order.iter().copied());
+        for (slot, effect_type_id) in effect_ids.enumerate() {
+            attributes.insert(
+                format!("{}{}", EFFECT_SLOT_PREFIX, slot),
+                effect_type_id.to_string(),
+            );
+        }
+
+        let (scale, custom_scale) = self.tuning.scale.attributes();
+        let mut tree = PluginParamTree {
+            scale,
+            custom_scale,
+            root_key: self.tuning.root_key,
+            preset_id: None,
+            preset_folder: None,
+            preset_name: self.name.clone(),
+            preset_info: Some(
+                self.description
+                    .clone()
+                    .unwrap_or_else(|| PRESET_INFO_DEFAULT.to_string()),
+            ),
+            preset_author: metadata.author,
+            preset_date: metadata.created,
+            preset_category: metadata.category,
+            params: Vec::new(),
+            attributes,
+        };
+
+        tree.push_envelope(
+            [
+                "EnvAttack",
+                "AttCurveType",
+                "EnvDecay",
+                "DecCurveType",
+                "EnvSustain",
+                "EnvRelease",
+                "RelCurveType",
+            ],
+            &self.envelope,
+        );
+
+        let tuning_ids = [
+            "TuneA",
+            "TuneASharp",
+            "TuneB",
+            "TuneC",
+            "TuneCSharp",
+            "TuneD",
+            "TuneDSharp",
+            "TuneE",
+            "TuneF",
+            "TuneFSharp",
+            "TuneG",
+            "TuneGSharp",
+        ];
+        for (id, tuning) in tuning_ids.iter().zip(self.tuning.tunings) {
+            tree.push_f64(id, tuning);
+        }
+        tree.push_f64("Transpose", self.tuning.transpose);
+        tree.params.push(Param {
+            id: "PCH".to_string(),
+            value: self.pch.clone(),
+        });
+
+        let filter = &self.filter;
+        tree.push_envelope(
+            [
+                "FilterEnvAttack",
+                "FilterAttCurveType",
+                "FilterEnvDecay",
+                "FilterDecCurveType",
+                "FilterEnvSustain",
+                "FilterEnvRelease",
+                "FilterRelCurveType",
+            ],
+            &filter.envelope,
+        );
+        tree.push_bool("FilterSwitch", filter.enabled);
+        tree.push_f64("FilterType", filter.mode.file_id() as f64);
+        tree.push_f64("FilterRes", filter.resonance);
+        tree.push_f64("FilterCut", filter.cutoff_frequency / 100.0);
+        tree.push_f64("FilterKey", filter.key_tracking);
+        tree.push_f64("FilterEnv", filter.envelope_amount);
+        tree.push_bool("FilterDriveSwitch", filter.effect_enabled);
+        tree.push_f64("FilterDriveType", filter.effect_mode.file_id() as f64);
+        tree.push_f64("FilterDrive", filter.effect_amount);
+
+        for (index, oscillator) in self.oscillators.iter().enumerate() {
+            let id = |name: &str| format!("{}_{}", name, index + 1);
+            tree.push_bool(&id("OSCSwitch"), oscillator.enabled);
+            tree.push_f64(&id("OSCWaveType"), oscillator.waveform.file_id() as f64);
+            tree.push_bool(&id("OSCInvert"), oscillator.invert);
+            tree.push_f64(&id("OSCPan"), oscillator.pan);
+            tree.push_f64(&id("OSCPhase"), oscillator.phase);
+            tree.push_f64(&id("OSCPitch"), oscillator.pitch);
+            tree.push_f64(&id("OSCFine"), oscillator.fine_tuning as f64);
+            tree.push_f64(&id("OSCSemi"), oscillator.semitone_tuning as f64);
+            tree.push_f64(&id("OSCOctave"), oscillator.octave_tuning as f64);
+            tree.push_bool(&id("OSCReverse"), oscillator.reverse);
+            tree.push_bool(&id("OSCFreeRun"), oscillator.free_run);
+            tree.push_f64(&id("OSCVol"), oscillator.volume);
+            if !Oscillator::supports_modulation(index) {
+                continue;
+            }
+            tree.push_bool(&id("OSCSyncAll"), oscillator.sync_all);
+            tree.push_f64(&id("OSCNumVoice"), oscillator.unison.voices as f64);
+            tree.push_f64(&id("OSCDetune"), oscillator.unison.detune);
+            tree.push_f64(&id("OSCSpread"), oscillator.unison.spread);
+            tree.push_f64(&id("OSCUniMix"), oscillator.unison.mix);
+            tree.push_bool(&id("OSCAMSwitch"), oscillator.am_enabled);
+            tree.push_f64(&id("OSCAM"), oscillator.am_amount);
+            tree.push_bool(&id("OSCFMSwitch"), oscillator.fm_enabled);
+            tree.push_f64(&id("OSCFM"), oscillator.fm_amount);
+            tree.push_bool(&id("OSCRMSwitch"), oscillator.rm_enabled);
+            tree.push_f64(&id("OSCRM"), oscillator.rm_amount);
+        }
+
+        tree.push_bool("OSCSwitch_N", self.noise.enabled);
+        tree.push_f64("OSCWidth_N", self.noise.width);
+        tree.push_f64("OSCPan_N", self.noise.pan);
+        tree.push_f64("OSCVol_N", self.noise.volume);
+        if self.noise.color != NoiseColor::White {
+            tree.push_f64(NOISE_COLOR_PARAM_ID, self.noise.color.file_id() as f64);
+        }
+
+        for (index, lfo) in self.lfos.iter().enumerate() {
+            let id = |name: &str| format!("{}_{}", name, index + 1);
+            tree.push_bool(&id("LFOSwitch"), lfo.enabled);
+            tree.push_f64(&id("LFOWaveType"), lfo.waveform.file_id() as f64);
+            tree.push_bool(&id("LFOSync"), lfo.sync);
+            tree.push_bool(&id("LFOInvert"), lfo.invert);
+            tree.push_bool(&id("LFOReverse"), lfo.reverse);
+            tree.push_bool(&id("LFOMono"), lfo.mono);
+            tree.push_bool(&id("LFOFreeRun"), lfo.free_run);
+            tree.push_f64(&id("LFOFreq"), lfo.frequency);
+            tree.push_f64(&id("LFOPhase"), lfo.phase);
+        }
+
+        for (index, mod_envelope) in self.mod_envelopes.iter().enumerate() {
+            let id = |name: &str| format!("{}_{}", name, index + 1);
+            tree.push_bool(&id("ModEnvSwitch"), mod_envelope.enabled);
+            tree.push_f64(&id("ModEnvCurveType"), mod_envelope.curve);
+            tree.push_envelope(
+                [
+                    &id("ModEnvAttack"),
+                    &id("ModAttCurveType"),
+                    &id("ModEnvDecay"),
+                    &id("ModDecCurveType"),
+                    &id("ModEnvSustain"),
+                    &id("ModEnvRelease"),
+                    &id("ModRelCurveType"),
+                ],
+                &mod_envelope.envelope,
+            );
+        }
+
+        tree.push_bool("VibSwitch", self.vibrato.enabled);
+        tree.push_f64("VibAttack", self.vibrato.attack.get::<millisecond>());
+        tree.push_f64("VibFrequency", self.vibrato.frequency.get::<hertz>());
+        tree.push_f64("VibDelay", self.vibrato.delay.get::<millisecond>());
+
+        for (index, item) in self.matrix.iter().enumerate() {
+            let id = |name: &str| format!("{}_{}", name, index + 1);
+            tree.push_f64(&id("MatrixSource"), item.source.file_id() as f64);
+            tree.push_f64(&id("MatrixTarget"), item.target.file_id() as f64);
+            tree.push_f64(&id("MatrixAmount"), item.amount);
+        }
+
+        tree.push_bool("ChorusSwitch", self.chorus.enabled);
+        tree.push_f64("ChorusDepth", self.chorus.depth);
+        tree.push_f64("ChorusMix", self.chorus.mix);
+        tree.push_f64("ChorusPdelay", self.chorus.pre_delay);
+        tree.push_f64("ChorusRatio", self.chorus.ratio);
+
+        tree.push_f64(
+            "DelayLP",
+            conversions::delay_filter_mode_to_control(self.delay.filter_mode),
+        );
+        tree.push_bool("DelaySwitch", self.delay.enabled);
+        tree.push_bool("DelayMode", self.delay.ping_pong);
+        tree.push_f64("DelayFeed", self.delay.feedback);
+        tree.push_bool("DelaySync", self.delay.sync);
+        tree.push_f64("DelayTime", self.delay.time);
+        tree.push_f64("DelayMix", self.delay.mix);
+
+        tree.push_bool("DistSwitch", self.distortion.enabled);
+        tree.push_f64("DistGain", self.distortion.gain);
+
+        tree.push_bool("EQSwitch", self.equalizer.enabled);
+        tree.push_f64("EQHigh", self.equalizer.high_gain.get::<percent>());
+        tree.push_f64("EQLow", self.equalizer.low_gain.get::<percent>());
+        tree.push_f64("EQMid", self.equalizer.mid_gain.get::<percent>());
+
+        // The effect filter only has a subset of the parameters of the main
+        // filter.
+        tree.push_bool("FXFilterSwitch", self.effect_filter.enabled);
+        tree.push_f64("FXFilterType", self.effect_filter.mode.file_id() as f64);
+        tree.push_f64("FXFilterRes", self.effect_filter.resonance);
+        tree.push_f64("FXFilterCut", self.effect_filter.cutoff_frequency / 100.0);
+
+        tree.push_bool("LoFiSwitch", self.lofi.enabled);
+        tree.push_f64("LoFiBitRate", self.lofi.bitrate);
+        tree.push_f64("LoFiSampleRate", self.lofi.sample_rate);
+        tree.push_f64("LoFiMix", self.lofi.mix);
+
+        tree.push_bool("ReverbSwitch", self.reverb.enabled);
+        tree.push_f64("ReverbDamp", self.reverb.dampen);
+        tree.push_f64("ReverbRoom", self.reverb.room);
+        tree.push_f64("ReverbLP", self.reverb.filter);
+        tree.push_f64("ReverbWidth", self.reverb.width);
+        tree.push_f64("ReverbMix", self.reverb.mix);
+
+        tree.push_f64("MainVol", self.master_volume_normalized);
+        tree.push_f64("MaxVoices", self.polyphony as f64);
+        tree.push_f64("PortaMode", self.portamento_mode.file_id() as f64);
+        tree.push_f64("MidiPlayMode", self.midi_play_mode.file_id() as f64);
+        tree.push_f64("Glide", self.glide);
+        tree.push_f64("VeloCurve", self.velocity_curve);
+        tree.push_f64("KeyTrackCurve", self.key_track_curve);
+        tree.push_f64("PBRange", self.pitch_bend_range);
+        tree.push_bool("LimitSwitch", self.limit_enabled);
+        tree.push_f64("EnvCurveType", self.envelope_curve);
+        tree.push_f64("FilterEnvCurveType", self.filter_envelope_curve);
+        tree.push_bool("OSCSync21", self.hard_sync);
+
+        tree.params.extend(self.unknown_params.iter().cloned());
+
+        tree
+    }
+}
+
+impl From<&Preset> for PluginParamTree {
+    fn from(preset: &Preset) -> Self {
+        preset.to_param_tree()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+    use std::path::{Path, PathBuf};
+
+    use approx::assert_relative_eq;
+
+    use crate::{
+        ModSource, ModTarget, Param, PluginParamTree, Preset, KNOWN_PARAM_IDS, NOISE_COLOR_PARAM_ID,
+    };
+
+    fn find_presets(dir: &Path, presets: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                find_presets(&path, presets);
+            } else if path.extension().is_some_and(|ext| ext == "bab") {
+                presets.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn known_param_ids() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let tree = preset.to_param_tree();
+        let written: BTreeSet<&str> = tree.params.iter().map(|p| p.id.as_str()).collect();
+        // The color of white noise is not written.
+        let known: BTreeSet<&str> = KNOWN_PARAM_IDS
+            .iter()
+            .copied()
+            .filter(|id| *id != NOISE_COLOR_PARAM_ID)
+            .collect();
+        assert_eq!(written, known);
+        assert_eq!(tree.params.len(), known.len());
+    }
+
+    /// Every preset that can be read is read back the same after being
+    /// written.
+    #[test]
+    fn round_trip() {
+        let mut presets = Vec::new();
+        find_presets(Path::new("tests"), &mut presets);
+        presets.sort();
+        for path in presets {
+            let Ok(preset) = Preset::read_file(&path) else {
+                continue;
+            };
+            let mut written = Vec::new();
+            preset.write(&mut written).unwrap();
+            let reread = Preset::read_named(written.as_slice(), "written").unwrap();
+            assert_eq!(
+                format!("{:?}", reread),
+                format!("{:?}", preset),
+                "{}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn to_param_tree() {
+        let path = Path::new("tests")
+            .join("effects")
+            .join("effect-order-reversed-1.0.2.bab");
+        let preset = Preset::read_file(path).unwrap();
+        let mut tree = PluginParamTree::from(&preset);
+        let value = |tree: &PluginParamTree, id: &str| {
+            let param = tree.params.iter().find(|param| param.id == id).unwrap();
+            param.value.clone().unwrap()
+        };
+        assert_eq!(value(&tree, "EnvAttack"), "2.0");
+        let sustain: f64 = value(&tree, "EnvSustain").parse().unwrap();
+        assert_relative_eq!(sustain, 0.9, epsilon = 0.0001);
+        assert_eq!(tree.attributes["FX_Order_0"], "6");
+        assert_eq!(tree.attributes["FX_Order_6"], "0");
+
+        // PCH is written back unchanged.
+        let pch = |tree: &PluginParamTree| {
+            let param = tree.params.iter().find(|param| param.id == "PCH").unwrap();
+            param.value.clone()
+        };
+        assert_eq!(pch(&tree).unwrap(), "0.9140000343322754");
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let init = PluginParamTree::from(&Preset::read_file(path).unwrap());
+        assert_eq!(pch(&init).unwrap(), "0.07000000029802322");
+        let path = Path::new("tests").join("init-1.0.2.bab");
+        let init = PluginParamTree::from(&Preset::read_file(path).unwrap());
+        assert_eq!(pch(&init), None);
+
+        // The raw parameters can be changed before writing.
+        tree.params
+            .iter_mut()
+            .find(|param| param.id == "MaxVoices")
+            .unwrap()
+            .value = Some("3.0".to_string());
+        let mut written = Vec::new();
+        tree.write(&mut written).unwrap();
+        let reread = Preset::read_named(written.as_slice(), "written").unwrap();
+        assert_eq!(reread.polyphony, 3);
+        assert_eq!(reread.effect_order, preset.effect_order);
+    }
+
+    /// Parameters and attributes that are not recognized are written back.
+    #[test]
+    fn unknown() {
+        let path = Path::new("tests").join("unknown-1.0.4.bab");
+        let preset = Preset::read_file(path).unwrap();
+        assert_eq!(
+            preset.unknown_params,
+            vec![Param {
+                id: "AFutureParam".to_string(),
+                value: Some("0.25".to_string()),
+            }]
+        );
+        assert_eq!(preset.unknown_attributes["EnvLock"], "1");
+        assert_eq!(preset.unknown_attributes["FutureAttribute"], "future");
+
+        let mut written = Vec::new();
+        preset.write(&mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.contains(r#"<PARAM id="AFutureParam" value="0.25"/>"#));
+        assert!(written.contains(r#" EnvLock="1""#));
+        assert!(written.contains(r#" FutureAttribute="future""#));
+    }
+
+    #[test]
+    fn write_file() {
+        let path = Path::new("tests").join("init-1.0.4.bab");
+        let mut preset = Preset::read_file(path).unwrap();
+        preset.name = "Written & <Read>".to_string();
+        preset.description = Some("A \"quoted\"\ndescription".to_string());
+        preset.matrix[1].source = ModSource::ModWheel;
+        preset.matrix[1].target = ModTarget::Pitch;
+        preset.matrix[1].amount = 0.25;
+
+        let path = std::env::temp_dir().join("synthahol-babylon-write_file.bab");
+        preset.write_file(&path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let reread = Preset::read_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let reread = reread.unwrap();
+
+        assert!(written.contains(r#"FX_Order_0="0""#));
+        assert!(written.contains(r#"<PARAM id="EnvAttack" value="2.0"/>"#));
+        assert!(written.contains(r#"<PARAM id="FilterSwitch" value="0.0"/>"#));
+        assert_eq!(reread.name, preset.name);
+        assert_eq!(reread.description, preset.description);
+        assert_eq!(reread.matrix[1].source, ModSource::ModWheel);
+        assert_eq!(reread.matrix[1].target, ModTarget::Pitch);
+        assert_eq!(reread.matrix[1].amount, 0.25);
+    }
+}